@@ -1,35 +1,576 @@
-use crate::world3d::{VoxelMaterial, World3D};
+use crate::world3d::{NeighborSet, Voxel, VoxelMaterial, World3D};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::ops::Range;
 
-#[derive(Clone)]
+// Manière de combiner croissance et coût métabolique au sein d'un tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GrowthModel {
+    // La croissance s'applique d'abord, le coût est ensuite prélevé sur la
+    // population agrandie
+    GrowthThenCost,
+    // Croissance et coût sont calculés sur la même taille de départ et
+    // appliqués en un seul taux net, sans effet d'ordre
+    NetRate,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BiologyRules {
+    // Voisins accessibles lors d'un déplacement (6 faces ou les 26 voisins)
+    pub movement_neighbors: NeighborSet,
+    pub reproduction_enabled: bool,
+    pub metabolism_enabled: bool,
+    pub growth_model: GrowthModel,
+    // Vérifier le bilan de biomasse à chaque tick et signaler les écarts
+    pub accounting: bool,
+    // Taille à partir de laquelle une population fonde une civilisation
+    pub civilization_threshold: u32,
+    // Marge supplémentaire (fraction du seuil) exigée pour refonder sur un site
+    // qui a déjà accueilli une civilisation
+    pub civilization_hysteresis: f32,
+    // Ticks pendant lesquels un site ne peut pas refonder après une fondation
+    // ou un effondrement
+    pub founding_cooldown: u32,
+    // Population maximale d'une civilisation (croissance et butins de guerre)
+    pub max_civ_population: u32,
+    // Surcoût métabolique par degré d'écart au-delà de la zone de confort
+    // (fraction du coût de base)
+    pub thermoregulation_cost: f32,
+    // Part des migrants épuisés par le voyage
+    pub movement_cost: f32,
+    // Fraction de l'écart entre température préférée et température locale
+    // rattrapée à chaque tick par une population qui survit (0 = pas d'adaptation)
+    pub thermal_adaptation: f32,
+    // Nombre maximal d'espèces enregistrées ; au-delà, les identifiants des
+    // espèces éteintes sont recyclés (voir `SpeciesRegistry::make_room`)
+    pub max_species: usize,
+    // Fraction du déficit en nutriments d'un voxel comblée à chaque tick
+    // (voir `VoxelMaterial::base_nutrients`)
+    pub nutrient_regeneration: f32,
+    // Chance par tick qu'une population d'au moins `speciation_min_size`
+    // individus donne naissance à une nouvelle espèce (0 = jamais)
+    pub speciation_chance: f32,
+    pub speciation_min_size: u32,
+    // Proies tuées par tick, en fraction de la taille du prédateur ; part de
+    // ces proies convertie en prédateurs ; et fraction des prédateurs sans
+    // proie sur leur voxel qui meurt de faim
+    pub predation_rate: f32,
+    pub predation_efficiency: f32,
+    pub starvation_rate: f32,
+    // Mortalité par tick et par degré de froid au-delà de `FROST_MARGIN`
+    // tolérances thermiques sous la température préférée
+    pub frost_mortality: f32,
+}
+
+impl Default for BiologyRules {
+    fn default() -> Self {
+        Self {
+            movement_neighbors: NeighborSet::Orthogonal,
+            reproduction_enabled: true,
+            metabolism_enabled: true,
+            growth_model: GrowthModel::GrowthThenCost,
+            accounting: false,
+            civilization_threshold: 500,
+            civilization_hysteresis: 0.2,
+            founding_cooldown: 100,
+            max_civ_population: 1_000_000_000,
+            thermoregulation_cost: 0.1,
+            movement_cost: 0.05,
+            thermal_adaptation: 0.01,
+            max_species: 1000,
+            nutrient_regeneration: 0.02,
+            speciation_chance: 0.001,
+            speciation_min_size: 1000,
+            predation_rate: 0.1,
+            predation_efficiency: 0.5,
+            starvation_rate: 0.05,
+            frost_mortality: 0.02,
+        }
+    }
+}
+
+// Bilan de biomasse d'un pas de biologie : sources et puits explicites
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BiomassLedger {
+    pub before: u64,
+    pub after: u64,
+    pub growth: u64,
+    pub metabolism: u64,
+    pub unsuitable_habitat: u64,
+    pub salinity: u64,
+    pub overcrowding: u64,
+    pub removed: u64, // Populations sans espèce ou hors du monde
+    // Migrants partis de chez eux, et ceux arrivés à destination (la
+    // différence, épuisée par le voyage, est comptée dans `metabolism`)
+    pub dispersed: u64,
+    pub arrived: u64,
+    // Proies tuées par des prédateurs, et prédateurs morts faute de proie
+    pub predation: u64,
+    pub starvation: u64,
+    // Morts de froid, y compris sur un voxel gelé
+    pub frost: u64,
+    // (identifiant d'espèce inconnu, taille) des populations retirées faute d'espèce
+    pub missing_species: Vec<(u32, u32)>,
+}
+
+impl BiomassLedger {
+    pub fn expected_after(&self) -> i64 {
+        self.before as i64 + self.growth as i64
+            - (self.metabolism + self.unsuitable_habitat + self.salinity + self.overcrowding
+                + self.removed + self.predation + self.starvation + self.frost) as i64
+    }
+
+    // Biomasse créée (positif) ou détruite (négatif) sans explication
+    pub fn discrepancy(&self) -> i64 {
+        self.after as i64 - self.expected_after()
+    }
+}
+
+// Mortalité par tick d'une population sur un matériau inadapté
+const UNSUITABLE_DIE_OFF_RATE: f32 = 0.25;
+const UNSUITABLE_DIE_OFF_FLOOR: u32 = 2;
+
+// Mortalité par tick d'une population prise dans la glace
+const ICE_DIE_OFF_RATE: f32 = 0.5;
+
+// Nombre de tolérances thermiques sous la température préférée en deçà
+// duquel le froid devient mortel (voir `BiologyRules::frost_mortality`)
+const FROST_MARGIN: f32 = 2.0;
+
+// Tolérance thermique de référence : les espèces plus étroites se reproduisent
+// plus vite à leur optimum, les plus larges moins vite
+const REFERENCE_THERMAL_TOLERANCE: f32 = 5.0;
+
+// Écart de température (°C) supporté sans effort de thermorégulation
+const COMFORT_BAND: f32 = 5.0;
+
+// Rayon maximal (en voxels) de la recherche de chemin lors d'un déplacement
+const PATHFINDING_RADIUS: u32 = 2;
+
+// Réponse thermique minimale (voir `thermal_response`) d'un voxel pour que
+// des migrants s'y installent : en dessous, ils y dépériraient
+const MIN_DISPERSAL_RESPONSE: f32 = 0.1;
+
+// Facteur de reproduction selon l'écart à la température préférée : une
+// gaussienne dont le pic est d'autant plus haut que l'espèce est spécialisée
+fn thermal_response(temp_diff: f32, tolerance: f32) -> f32 {
+    let tolerance = tolerance.max(0.1);
+    let peak = 1.2 * (REFERENCE_THERMAL_TOLERANCE / tolerance).sqrt().clamp(0.5, 2.0);
+    peak * (-(temp_diff * temp_diff) / (2.0 * tolerance * tolerance)).exp()
+}
+
+// Petite recherche en largeur à travers les voxels habitables autour de `from` :
+// renvoie le premier pas vers le voxel habitable atteignable le plus proche de `target`
+fn route_step(
+    world: &World3D,
+    from: (u32, u32, u32),
+    target: (u32, u32, u32),
+    neighbors: NeighborSet,
+) -> Option<(u32, u32, u32)> {
+    let distance_sq = |a: (u32, u32, u32), b: (u32, u32, u32)| {
+        let dx = a.0 as i64 - b.0 as i64;
+        let dy = a.1 as i64 - b.1 as i64;
+        let dz = a.2 as i64 - b.2 as i64;
+        dx * dx + dy * dy + dz * dz
+    };
+    let in_range = |p: (u32, u32, u32)| {
+        p.0.abs_diff(from.0) <= PATHFINDING_RADIUS
+            && p.1.abs_diff(from.1) <= PATHFINDING_RADIUS
+            && p.2.abs_diff(from.2) <= PATHFINDING_RADIUS
+    };
+
+    // Chaque voxel visité retient le premier pas qui y mène
+    let mut first_step = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(from);
+    let mut best: Option<((u32, u32, u32), i64)> = None;
+
+    while let Some(current) = queue.pop_front() {
+        for next in world.neighbors(current.0, current.1, current.2, neighbors) {
+            if next == from || first_step.contains_key(&next) || !in_range(next) {
+                continue;
+            }
+            if !world.get(next.0, next.1, next.2).material.is_habitable() {
+                continue;
+            }
+
+            let step = if current == from {
+                next
+            } else {
+                first_step[&current]
+            };
+            first_step.insert(next, step);
+            queue.push_back(next);
+
+            let dist = distance_sq(next, target);
+            if !matches!(best, Some((_, d)) if d <= dist) {
+                best = Some((step, dist));
+            }
+        }
+    }
+
+    best.map(|(step, _)| step)
+}
+
+// Le sol et l'eau appauvris retrouvent lentement leurs nutriments
+fn regenerate_nutrients(world: &mut World3D, rules: &BiologyRules) {
+    if rules.nutrient_regeneration <= 0.0 {
+        return;
+    }
+    for voxel in world.voxels.iter_mut() {
+        let deficit = voxel.material.base_nutrients() - voxel.nutrients;
+        if deficit > 0.0 {
+            voxel.nutrients += deficit * rules.nutrient_regeneration.min(1.0);
+        }
+    }
+}
+
+// Chaque prédateur chasse sa proie sur son propre voxel et grandit de ce qu'il
+// mange ; sans proie, il dépérit. Après la fusion, il y a au plus une
+// population par espèce et par voxel.
+fn hunt(
+    rules: &BiologyRules,
+    populations: &mut [Population],
+    diet_of: &impl Fn(u32) -> Option<Diet>,
+    ledger: &mut BiomassLedger,
+) {
+    let positions: HashMap<(u32, u32, u32, u32), usize> = populations
+        .iter()
+        .enumerate()
+        .map(|(i, pop)| ((pop.x, pop.y, pop.z, pop.species_id), i))
+        .collect();
+
+    for i in 0..populations.len() {
+        let prey_species = match diet_of(populations[i].species_id) {
+            Some(Diet::Consumer { prey_species }) => prey_species,
+            _ => continue,
+        };
+        let hunter = &populations[i];
+        let prey = positions
+            .get(&(hunter.x, hunter.y, hunter.z, prey_species))
+            .copied()
+            .filter(|&j| j != i && populations[j].size > 0);
+
+        match prey {
+            Some(j) => {
+                let hunger = (hunter.size as f32 * rules.predation_rate) as u32;
+                let eaten = hunger.max(1).min(populations[j].size);
+                let gained = (eaten as f32 * rules.predation_efficiency) as u32;
+                populations[j].size -= eaten;
+                let fed = populations[i].size.saturating_add(gained);
+                ledger.predation += eaten as u64;
+                ledger.growth += (fed - populations[i].size) as u64;
+                populations[i].size = fed;
+            }
+            None => {
+                let starved = (hunter.size as f32 * rules.starvation_rate) as u32;
+                let starved = starved.max(1).min(hunter.size);
+                populations[i].size -= starved;
+                ledger.starvation += starved as u64;
+            }
+        }
+    }
+}
+
+fn total_biomass(populations: &[Population]) -> u64 {
+    populations.iter().map(|p| p.size as u64).sum()
+}
+
+// Source de nourriture d'une espèce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Diet {
+    // Croît grâce aux nutriments de son voxel
+    Producer,
+    // Croît uniquement en mangeant `prey_species` sur son propre voxel
+    Consumer { prey_species: u32 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Species {
     pub id: u32,
     pub metabolism: f32,
     pub reproduction_rate: f32,
     pub mobility: f32,
     pub preferred_temperature: f32,
+    // Écart-type (°C) de la courbe de reproduction autour de la température
+    // préférée : faible pour les spécialistes, élevé pour les généralistes
+    pub thermal_tolerance: f32,
+    pub salinity_tolerance: (f32, f32), // Plage de salinité supportée (min, max)
+    pub diet: Diet,
+}
+
+// Plages de tirage des traits d'une espèce, pour orienter la création
+// (ex. un monde d'extrêmophiles au métabolisme rapide)
+#[derive(Debug, Clone)]
+pub struct SpeciesTemplate {
+    pub metabolism: Range<f32>,
+    pub reproduction_rate: Range<f32>,
+    pub mobility: Range<f32>,
+    pub preferred_temperature: Range<f32>,
+    pub thermal_tolerance: Range<f32>,
+    pub min_salinity: Range<f32>,
+    // Largeur de la plage de salinité tolérée
+    pub salinity_width: Range<f32>,
+}
+
+impl Default for SpeciesTemplate {
+    fn default() -> Self {
+        Self {
+            metabolism: 0.5..2.0,
+            reproduction_rate: 0.01..0.1,
+            mobility: 0.1..1.0,
+            preferred_temperature: 15.0..25.0,
+            thermal_tolerance: 3.0..8.0,
+            min_salinity: 0.0..0.3,
+            salinity_width: 0.2..0.8,
+        }
+    }
+}
+
+// Tirage uniforme ; une plage vide donne sa borne basse
+fn sample(range: &Range<f32>, rng: &mut impl Rng) -> f32 {
+    if range.start < range.end {
+        rng.gen_range(range.clone())
+    } else {
+        range.start
+    }
+}
+
+// Écart-type des mutations, en fraction de la largeur de la plage du trait
+const MUTATION_SCALE: f32 = 0.05;
+
+// Tirage gaussien centré réduit (méthode de Box-Muller)
+fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+// Valeur légèrement bruitée, bornée à la plage du trait
+fn mutate(value: f32, range: &Range<f32>, rng: &mut impl Rng) -> f32 {
+    let width = range.end - range.start;
+    (value + gaussian(rng) * width * MUTATION_SCALE).clamp(range.start, range.end)
 }
 
 impl Species {
     pub fn new(id: u32) -> Self {
-        let mut rng = rand::thread_rng();
+        Self::random(id, &mut rand::thread_rng())
+    }
+
+    pub fn random(id: u32, rng: &mut impl Rng) -> Self {
+        Self::from_template(id, &SpeciesTemplate::default(), rng)
+    }
+
+    pub fn from_template(id: u32, template: &SpeciesTemplate, rng: &mut impl Rng) -> Self {
+        let min_salinity = sample(&template.min_salinity, rng);
+        Self {
+            id,
+            metabolism: sample(&template.metabolism, rng),
+            reproduction_rate: sample(&template.reproduction_rate, rng),
+            mobility: sample(&template.mobility, rng),
+            preferred_temperature: sample(&template.preferred_temperature, rng),
+            thermal_tolerance: sample(&template.thermal_tolerance, rng),
+            salinity_tolerance: (min_salinity, min_salinity + sample(&template.salinity_width, rng)),
+            diet: Diet::Producer,
+        }
+    }
+
+    // Une espèce née dans ce voxel : préférences calées sur ses conditions
+    pub fn adapted_to(id: u32, voxel: &Voxel, rng: &mut impl Rng) -> Self {
+        let mut species = Self::random(id, rng);
+        species.preferred_temperature = voxel.temperature + rng.gen_range(-2.0..2.0);
+        let half_width = rng.gen_range(0.1..0.4);
+        species.salinity_tolerance =
+            ((voxel.salinity - half_width).max(0.0), voxel.salinity + half_width);
+        species
+    }
+
+    // Une espèce fille : les traits du parent plus la dérive de la population
+    // fondatrice, légèrement mutés et bornés aux plages de SpeciesTemplate. La
+    // température préférée n'est pas bornée, pour garder l'adaptation locale
+    // (comme pour `adapted_to`).
+    pub fn mutated_from(
+        id: u32,
+        parent: &Species,
+        drift: &TraitDrift,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let ranges = SpeciesTemplate::default();
+        let temperature = &ranges.preferred_temperature;
+        let temperature_width = temperature.end - temperature.start;
         Self {
             id,
-            metabolism: rng.gen_range(0.5..2.0),
-            reproduction_rate: rng.gen_range(0.01..0.1),
-            mobility: rng.gen_range(0.1..1.0),
-            preferred_temperature: rng.gen_range(15.0..25.0),
+            metabolism: mutate(parent.metabolism + drift.metabolism, &ranges.metabolism, rng),
+            reproduction_rate: mutate(
+                parent.reproduction_rate + drift.reproduction_rate,
+                &ranges.reproduction_rate,
+                rng,
+            ),
+            mobility: mutate(parent.mobility + drift.mobility, &ranges.mobility, rng),
+            preferred_temperature: parent.preferred_temperature
+                + drift.preferred_temperature
+                + gaussian(rng) * temperature_width * MUTATION_SCALE,
+            thermal_tolerance: mutate(parent.thermal_tolerance, &ranges.thermal_tolerance, rng),
+            salinity_tolerance: parent.salinity_tolerance,
+            diet: parent.diet,
+        }
+    }
+
+    // Vrai si l'espèce peut prospérer dans ce voxel dès sa création
+    pub fn suits(&self, voxel: &Voxel) -> bool {
+        (voxel.temperature - self.preferred_temperature).abs() < 5.0
+            && (voxel.material != VoxelMaterial::Water || self.tolerates_salinity(voxel.salinity))
+    }
+
+    pub fn tolerates_salinity(&self, salinity: f32) -> bool {
+        salinity >= self.salinity_tolerance.0 && salinity <= self.salinity_tolerance.1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpeciesError {
+    DuplicateId(u32),
+    UnknownId(u32),
+}
+
+impl fmt::Display for SpeciesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpeciesError::DuplicateId(id) => write!(f, "a species with id {} already exists", id),
+            SpeciesError::UnknownId(id) => write!(f, "no species with id {}", id),
         }
     }
 }
 
-#[derive(Clone)]
+impl std::error::Error for SpeciesError {}
+
+// Liste des espèces garantissant des identifiants uniques
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SpeciesRegistry {
+    species: Vec<Species>,
+    next_id: u32,
+    // Identifiants d'espèces éteintes à réutiliser, le plus petit en dernier
+    free_ids: Vec<u32>,
+}
+
+impl SpeciesRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Prochain identifiant libre : d'abord ceux des espèces éteintes
+    fn take_id(&mut self) -> u32 {
+        self.free_ids.pop().unwrap_or_else(|| {
+            self.next_id += 1;
+            self.next_id - 1
+        })
+    }
+
+    // Créer une espèce aléatoire avec le prochain identifiant libre
+    pub fn create(&mut self, rng: &mut impl Rng) -> &Species {
+        let species = Species::random(self.take_id(), rng);
+        self.species.push(species);
+        self.species.last().unwrap()
+    }
+
+    pub fn create_from_template(
+        &mut self,
+        template: &SpeciesTemplate,
+        rng: &mut impl Rng,
+    ) -> &Species {
+        let species = Species::from_template(self.take_id(), template, rng);
+        self.species.push(species);
+        self.species.last().unwrap()
+    }
+
+    pub fn create_adapted(&mut self, voxel: &Voxel, rng: &mut impl Rng) -> &Species {
+        let species = Species::adapted_to(self.take_id(), voxel, rng);
+        self.species.push(species);
+        self.species.last().unwrap()
+    }
+
+    // Créer une espèce fille de `parent_id` (voir `Species::mutated_from`) ;
+    // None si le parent est inconnu
+    pub fn create_mutant(
+        &mut self,
+        parent_id: u32,
+        drift: &TraitDrift,
+        rng: &mut impl Rng,
+    ) -> Option<&Species> {
+        let parent = self.get(parent_id)?.clone();
+        let species = Species::mutated_from(self.take_id(), &parent, drift, rng);
+        self.species.push(species);
+        self.species.last()
+    }
+
+    // Ajouter une espèce construite à la main, en refusant les doublons
+    pub fn insert(&mut self, species: Species) -> Result<(), SpeciesError> {
+        if self.get(species.id).is_some() {
+            return Err(SpeciesError::DuplicateId(species.id));
+        }
+        self.next_id = self.next_id.max(species.id.saturating_add(1));
+        self.free_ids.retain(|&id| id != species.id);
+        self.species.push(species);
+        Ok(())
+    }
+
+    // Retirer les espèces sans aucune population ; leurs identifiants seront
+    // réutilisés par les prochaines créations. Renvoie le nombre d'espèces retirées.
+    pub fn reclaim_extinct(&mut self, populations: &[Population]) -> usize {
+        let before = self.species.len();
+        let free_ids = &mut self.free_ids;
+        self.species.retain(|species| {
+            let alive = populations.iter().any(|p| p.species_id == species.id);
+            if !alive {
+                free_ids.push(species.id);
+            }
+            alive
+        });
+        self.free_ids.sort_unstable_by(|a, b| b.cmp(a));
+        before - self.species.len()
+    }
+
+    // Vrai si une nouvelle espèce peut être créée sans dépasser `max_species`,
+    // quitte à recycler les espèces éteintes
+    pub fn make_room(&mut self, max_species: usize, populations: &[Population]) -> bool {
+        if self.species.len() >= max_species {
+            self.reclaim_extinct(populations);
+        }
+        self.species.len() < max_species
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Species> {
+        self.species.iter().find(|s| s.id == id)
+    }
+}
+
+impl std::ops::Deref for SpeciesRegistry {
+    type Target = [Species];
+
+    fn deref(&self) -> &[Species] {
+        &self.species
+    }
+}
+
+// Dérive des traits propre à une population, relative aux traits de son espèce
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TraitDrift {
+    pub metabolism: f32,
+    pub reproduction_rate: f32,
+    pub mobility: f32,
+    pub preferred_temperature: f32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Population {
     pub species_id: u32,
     pub x: u32,
     pub y: u32,
     pub z: u32,
     pub size: u32,
+    pub drift: TraitDrift,
 }
 
 impl Population {
@@ -40,55 +581,130 @@ impl Population {
             y,
             z,
             size,
+            drift: TraitDrift::default(),
         }
     }
+
+    // Matériau Organic laissé par cette population : densité = taille / 100,
+    // saturée à 255 plutôt que tronquée par le cast. L'espèce n'est pas encodée,
+    // le variant Organic ne portant qu'une densité.
+    pub fn organic_tag(&self) -> VoxelMaterial {
+        let density = u8::try_from(self.size / 100).unwrap_or(u8::MAX);
+        VoxelMaterial::Organic(density)
+    }
+
+    // Fusionner une autre population dans celle-ci : les tailles s'additionnent
+    // et les traits sont moyennés en fonction de la taille de chacune
+    pub fn merge_with(&mut self, other: &Population) {
+        let total = self.size.saturating_add(other.size);
+        if total == 0 {
+            return;
+        }
+
+        let w_self = self.size as f32 / total as f32;
+        let w_other = other.size as f32 / total as f32;
+        let blend = |a: f32, b: f32| a * w_self + b * w_other;
+
+        self.drift = TraitDrift {
+            metabolism: blend(self.drift.metabolism, other.drift.metabolism),
+            reproduction_rate: blend(self.drift.reproduction_rate, other.drift.reproduction_rate),
+            mobility: blend(self.drift.mobility, other.drift.mobility),
+            preferred_temperature: blend(
+                self.drift.preferred_temperature,
+                other.drift.preferred_temperature,
+            ),
+        };
+        self.size = total;
+    }
 }
 
+// Un pas de biologie. Les nutriments épuisés se régénèrent d'abord un peu ;
+// puis, après fusion des populations qui partagent un voxel, chaque population
+// subit dans l'ordre :
+//   1. la mortalité due à un matériau inadapté ou à une salinité hors tolérance,
+//   2. la régulation par la capacité de charge (partagée entre espèces),
+//   3. croissance et coût métabolique, combinés selon `rules.growth_model`,
+//      puis adaptation à la température locale,
+//   4. la consommation des nutriments du voxel,
+//   5. une éventuelle migration vers un voxel voisin où elle peut survivre,
+//   6. le marquage Organic des voxels densément peuplés.
 pub fn step_biology(
     world: &mut World3D,
+    rules: &BiologyRules,
     species_list: &[Species],
     populations: &mut Vec<Population>,
-) {
-    let mut rng = rand::thread_rng();
+    rng: &mut impl Rng,
+) -> BiomassLedger {
     let mut new_populations: Vec<Population> = Vec::new();
+    let mut ledger = BiomassLedger {
+        before: total_biomass(populations),
+        ..Default::default()
+    };
+
+    regenerate_nutrients(world, rules);
 
     // Fusionner les populations proches sur le même voxel
-    let mut population_map = std::collections::HashMap::new();
+    // (BTreeMap pour un ordre de parcours stable d'une exécution à l'autre)
+    let mut population_map = std::collections::BTreeMap::new();
 
     // Ajouter les populations existantes au map
     for pop in populations.iter() {
-        let key = (pop.x, pop.y, pop.z, pop.species_id);
+        let key = (pop.z, pop.y, pop.x, pop.species_id);
         population_map
             .entry(key)
-            .and_modify(|existing_size| *existing_size += pop.size)
-            .or_insert(pop.size);
+            .and_modify(|existing: &mut Population| existing.merge_with(pop))
+            .or_insert_with(|| pop.clone());
     }
 
     // Ajouter les nouvelles populations au map
     for pop in new_populations.iter() {
-        let key = (pop.x, pop.y, pop.z, pop.species_id);
+        let key = (pop.z, pop.y, pop.x, pop.species_id);
         population_map
             .entry(key)
-            .and_modify(|existing_size| *existing_size += pop.size)
-            .or_insert(pop.size);
+            .and_modify(|existing: &mut Population| existing.merge_with(pop))
+            .or_insert_with(|| pop.clone());
     }
 
     // Reconstruire la liste des populations
     populations.clear();
-    for ((x, y, z, species_id), size) in population_map {
-        populations.push(Population::new(species_id, x, y, z, size));
+    populations.extend(population_map.into_values());
+    if cfg!(debug_assertions) && total_biomass(populations) != ledger.before {
+        eprintln!(
+            "Biomass leak while merging populations: {} before, {} after",
+            ledger.before,
+            total_biomass(populations)
+        );
+    }
+
+    let diet_of = |species_id: u32| {
+        species_list.iter().find(|s| s.id == species_id).map(|s| s.diet)
+    };
+    hunt(rules, populations, &diet_of, &mut ledger);
+
+    // Demande totale par voxel : les producteurs qui partagent un voxel se
+    // partagent aussi sa capacité de charge
+    let mut voxel_demand: HashMap<(u32, u32, u32), u32> = HashMap::new();
+    for pop in populations.iter() {
+        if diet_of(pop.species_id) == Some(Diet::Producer) {
+            *voxel_demand.entry((pop.x, pop.y, pop.z)).or_insert(0) += pop.size;
+        }
     }
 
     populations.retain_mut(|pop| {
         // Trouver l'espèce correspondant à cette population
         let species = species_list.iter().find(|s| s.id == pop.species_id);
         if species.is_none() {
+            ledger.removed += pop.size as u64;
+            ledger.missing_species.push((pop.species_id, pop.size));
             return false;
         }
         let species = species.unwrap();
+        // Les prédateurs ne vivent que de leurs proies (voir `hunt`)
+        let consumer = matches!(species.diet, Diet::Consumer { .. });
 
         // Vérifier si la position est valide dans le monde
         if pop.x >= world.width || pop.y >= world.height || pop.z >= world.depth {
+            ledger.removed += pop.size as u64;
             return false;
         }
 
@@ -97,93 +713,884 @@ pub fn step_biology(
         let voxel = &mut world.voxels[voxel_index];
 
         // Vérifier si le matériau du voxel est adapté à la vie
-        let suitable_material = matches!(
-            voxel.material,
-            VoxelMaterial::Soil | VoxelMaterial::Water | VoxelMaterial::Organic(_)
-        );
+        let suitable_material = voxel.material.is_habitable();
+
+        // Une population dont le voxel a gelé meurt en masse
+        if voxel.material == VoxelMaterial::Ice {
+            let proportional = (pop.size as f32 * ICE_DIE_OFF_RATE) as u32;
+            let loss = (proportional + UNSUITABLE_DIE_OFF_FLOOR).min(pop.size);
+            pop.size -= loss;
+            ledger.frost += loss as u64;
+            return pop.size > 0;
+        }
 
         if !suitable_material {
-            // Réduire la taille de la population si le matériau est inadapté
-            pop.size = pop.size.saturating_sub(5);
+            // Réduire la taille de la population si le matériau est inadapté :
+            // une part proportionnelle à la taille, plus un petit plancher fixe
+            let proportional = (pop.size as f32 * UNSUITABLE_DIE_OFF_RATE) as u32;
+            let loss = (proportional + UNSUITABLE_DIE_OFF_FLOOR).min(pop.size);
+            pop.size -= loss;
+            ledger.unsuitable_habitat += loss as u64;
             return pop.size > 0;
         }
 
+        // Les populations aquatiques souffrent d'une eau trop salée ou trop douce
+        if voxel.material == VoxelMaterial::Water && !species.tolerates_salinity(voxel.salinity) {
+            let (min, max) = species.salinity_tolerance;
+            let excess = (min - voxel.salinity).max(voxel.salinity - max);
+            let loss = ((pop.size as f32 * (0.1 + excess).min(1.0)) as u32).max(1).min(pop.size);
+            pop.size -= loss;
+            ledger.salinity += loss as u64;
+            if pop.size == 0 {
+                return false;
+            }
+        }
+
         // Calculer la compatibilité de la température avec l'espèce
-        let temp_diff = (voxel.temperature - species.preferred_temperature).abs();
-        let temp_factor = if temp_diff < 5.0 {
-            1.2 // Conditions idéales
-        } else if temp_diff < 10.0 {
-            1.0 // Conditions neutres
-        } else {
-            0.8 // Conditions difficiles
-        };
+        let preferred_temperature = species.preferred_temperature + pop.drift.preferred_temperature;
+        let temp_diff = (voxel.temperature - preferred_temperature).abs();
+        let temp_factor = thermal_response(temp_diff, species.thermal_tolerance);
+
+        // Bien en dessous de sa température préférée, une population meurt de
+        // froid, d'autant plus vite que l'écart est grand
+        let frost_limit = preferred_temperature - FROST_MARGIN * species.thermal_tolerance;
+        let cold_excess = frost_limit - voxel.temperature;
+        if cold_excess > 0.0 {
+            let rate = (rules.frost_mortality * cold_excess).min(1.0);
+            let loss = ((pop.size as f32 * rate) as u32).max(1).min(pop.size);
+            pop.size -= loss;
+            ledger.frost += loss as u64;
+            if pop.size == 0 {
+                return false;
+            }
+        }
 
         // Limiter la croissance en fonction de la capacité de charge locale
-        let carrying_capacity = (voxel.nutrients * 10.0) as u32;
-        if pop.size > carrying_capacity {
-            pop.size = pop.size.saturating_sub((pop.size - carrying_capacity) / 10);
+        // (la fertilité du sol module cette capacité ; la surexploitation l'épuise).
+        // Chaque population n'en reçoit qu'une part proportionnelle à sa taille.
+        let voxel_capacity = voxel.nutrients * 10.0 * voxel.fertility;
+        let demand = voxel_demand.get(&(pop.x, pop.y, pop.z)).copied().unwrap_or(0);
+        let carrying_capacity = if demand > 0 {
+            (voxel_capacity * pop.size as f32 / demand as f32) as u32
+        } else {
+            voxel_capacity as u32
+        };
+        if !consumer && pop.size > carrying_capacity {
+            let excess = (pop.size - carrying_capacity) / 10;
+            pop.size -= excess;
+            ledger.overcrowding += excess as u64;
+            voxel.fertility = (voxel.fertility - 0.001).max(0.1);
         }
 
-        // Calculer la croissance de la population
-        let growth_rate = species.reproduction_rate * temp_factor;
-        let growth = (pop.size as f32 * growth_rate) as i32;
-        pop.size = (pop.size as i32 + growth).max(0) as u32;
+        // Taux de croissance et de coût métabolique (ce dernier alourdi par
+        // l'effort de thermorégulation hors de la zone de confort)
+        let growth_rate = if rules.reproduction_enabled && !consumer {
+            let reproduction_rate =
+                (species.reproduction_rate + pop.drift.reproduction_rate).max(0.0);
+            reproduction_rate * temp_factor
+        } else {
+            0.0
+        };
+        let cost_rate = if rules.metabolism_enabled {
+            let metabolism = (species.metabolism + pop.drift.metabolism).max(0.0);
+            let thermal_stress = (temp_diff - COMFORT_BAND).max(0.0);
+            metabolism * 0.01 * (1.0 + rules.thermoregulation_cost * thermal_stress)
+        } else {
+            0.0
+        };
+
+        let growth = (pop.size as f32 * growth_rate) as u32;
+        let grown = pop.size.saturating_add(growth);
+        let metabolic_cost = match rules.growth_model {
+            GrowthModel::GrowthThenCost => (grown as f32 * cost_rate) as u32,
+            GrowthModel::NetRate => (pop.size as f32 * cost_rate) as u32,
+        }
+        .min(grown);
+        ledger.growth += (grown - pop.size) as u64;
+        ledger.metabolism += metabolic_cost as u64;
+        pop.size = grown - metabolic_cost;
 
-        // Appliquer le coût métabolique
-        let metabolic_cost = (pop.size as f32 * species.metabolism * 0.01) as u32;
-        pop.size = pop.size.saturating_sub(metabolic_cost);
+        // Les survivants s'adaptent peu à peu à la température locale ; leurs
+        // migrants héritent de cette adaptation
+        if pop.size > 0 {
+            pop.drift.preferred_temperature +=
+                (voxel.temperature - preferred_temperature) * rules.thermal_adaptation;
+        }
 
         // Consommer les nutriments du voxel
-        let nutrient_consumption = pop.size as f32 * 0.1;
-        voxel.nutrients = (voxel.nutrients - nutrient_consumption).max(0.0);
+        if !consumer {
+            let nutrient_consumption = pop.size as f32 * 0.1;
+            voxel.nutrients = (voxel.nutrients - nutrient_consumption).max(0.0);
+        }
 
         // Déplacer la population vers un voxel voisin avec une certaine probabilité
-        if rng.gen::<f32>() < species.mobility * 0.1 {
-            let directions = [
-                (-1i32, 0i32, 0i32),
-                (1, 0, 0),
-                (0, -1, 0),
-                (0, 1, 0),
-                (0, 0, -1),
-                (0, 0, 1),
-            ];
-
-            let (dx, dy, dz) = directions[rng.gen_range(0..directions.len())];
-            let new_x = pop.x as i32 + dx;
-            let new_y = pop.y as i32 + dy;
-            let new_z = pop.z as i32 + dz;
-
-            // Vérifier si la nouvelle position est valide
-            if new_x >= 0
-                && new_y >= 0
-                && new_z >= 0
-                && new_x < world.width as i32
-                && new_y < world.height as i32
-                && new_z < world.depth as i32
-            {
+        let mobility = (species.mobility + pop.drift.mobility).max(0.0);
+        if rng.gen::<f32>() < mobility * 0.1 {
+            // Les voisins en diagonale sont plus loin : on pondère par l'inverse de la distance
+            let candidates: Vec<((u32, u32, u32), f32)> = world
+                .neighbors(pop.x, pop.y, pop.z, rules.movement_neighbors)
+                .map(|(nx, ny, nz)| {
+                    let steps =
+                        (nx != pop.x) as u32 + (ny != pop.y) as u32 + (nz != pop.z) as u32;
+                    ((nx, ny, nz), 1.0 / (steps as f32).sqrt())
+                })
+                .collect();
+
+            let total_weight: f32 = candidates.iter().map(|(_, w)| w).sum();
+            let mut roll = rng.gen::<f32>() * total_weight;
+            let mut destination = None;
+            for (pos, weight) in candidates.iter() {
+                if roll < *weight {
+                    destination = Some(*pos);
+                    break;
+                }
+                roll -= weight;
+            }
+            if destination.is_none() {
+                destination = candidates.last().map(|(pos, _)| *pos);
+            }
+
+            // Voisin infranchissable : contourner l'obstacle par un voxel habitable proche
+            if let Some((dx, dy, dz)) = destination {
+                if !world.get(dx, dy, dz).material.is_habitable() {
+                    destination = route_step(
+                        world,
+                        (pop.x, pop.y, pop.z),
+                        (dx, dy, dz),
+                        rules.movement_neighbors,
+                    );
+                }
+            }
+
+            // Rester sur place plutôt que migrer vers un voxel où l'on ne survivrait pas
+            destination = destination.filter(|&(dx, dy, dz)| {
+                let target = world.get(dx, dy, dz);
+                let temp_diff = (target.temperature - preferred_temperature).abs();
+                target.material.is_habitable()
+                    && (target.material != VoxelMaterial::Water
+                        || species.tolerates_salinity(target.salinity))
+                    && thermal_response(temp_diff, species.thermal_tolerance)
+                        >= MIN_DISPERSAL_RESPONSE
+            });
+
+            if let Some((new_x, new_y, new_z)) = destination {
                 // Diviser la population : une partie reste, l'autre se déplace
                 let moving_size = pop.size / 2;
                 if moving_size > 10 {
                     pop.size -= moving_size;
-                    new_populations.push(Population::new(
+                    // Le voyage coûte de l'énergie : une partie des migrants n'arrive pas
+                    let exhausted = if rules.metabolism_enabled {
+                        ((moving_size as f32 * rules.movement_cost) as u32).min(moving_size)
+                    } else {
+                        0
+                    };
+                    ledger.metabolism += exhausted as u64;
+                    ledger.dispersed += moving_size as u64;
+                    ledger.arrived += (moving_size - exhausted) as u64;
+                    let mut migrants = Population::new(
                         pop.species_id,
-                        new_x as u32,
-                        new_y as u32,
-                        new_z as u32,
-                        moving_size,
-                    ));
+                        new_x,
+                        new_y,
+                        new_z,
+                        moving_size - exhausted,
+                    );
+                    migrants.drift = pop.drift.clone();
+                    new_populations.push(migrants);
                 }
             }
         }
 
         // Marquer les voxels avec de grandes populations comme Organic
         if pop.size > 100 {
-            voxel.material = VoxelMaterial::Organic((pop.size / 100).min(255) as u8);
+            world.set_material_at(voxel_index, pop.organic_tag());
         }
 
         pop.size > 0
     });
 
-    // Ajouter les nouvelles populations générées
+    // Ajouter les nouvelles populations générées, en vérifiant que les migrants
+    // arrivent tous (ni perdus ni dupliqués entre leur départ et leur arrivée)
+    if cfg!(debug_assertions) && total_biomass(&new_populations) != ledger.arrived {
+        eprintln!(
+            "Biomass leak during dispersal: {} migrants arrived, {} added",
+            ledger.arrived,
+            total_biomass(&new_populations)
+        );
+    }
     populations.extend(new_populations);
+
+    ledger.after = total_biomass(populations);
+    ledger
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Un monde d'un seul voxel de sol à 20 °C, de la fertilité donnée
+    fn soil_world(fertility: f32) -> World3D {
+        let mut world = World3D::new(1, 1, 1);
+        let voxel = world.get_mut(0, 0, 0);
+        *voxel = Voxel::soil();
+        voxel.temperature = 20.0;
+        voxel.fertility = fertility;
+        world.refresh_material_counts();
+        world
+    }
+
+    // Une espèce sédentaire, à l'aise à 20 °C
+    fn species(id: u32, diet: Diet) -> Species {
+        Species {
+            id,
+            metabolism: 0.5,
+            reproduction_rate: 0.1,
+            mobility: 0.0,
+            preferred_temperature: 20.0,
+            thermal_tolerance: 5.0,
+            salinity_tolerance: (0.0, 1.0),
+            diet,
+        }
+    }
+
+    fn settled_size(fertility: f32) -> u32 {
+        let mut world = soil_world(fertility);
+        let species = vec![species(0, Diet::Producer)];
+        // Nutriments reconstitués à chaque tick : seule la fertilité limite
+        let rules = BiologyRules {
+            nutrient_regeneration: 1.0,
+            speciation_chance: 0.0,
+            ..Default::default()
+        };
+        let mut populations = vec![Population::new(0, 0, 0, 0, 50)];
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        populations.iter().map(|p| p.size).sum()
+    }
+
+    #[test]
+    fn fertile_soil_sustains_a_larger_population() {
+        let rich = settled_size(1.5);
+        let poor = settled_size(0.3);
+        assert!(poor > 0, "the poor soil should still sustain some life");
+        assert!(rich > 2 * poor, "rich soil sustains {}, poor soil {}", rich, poor);
+    }
+
+    // Tailles (proies, prédateurs) sur un même voxel après quelques ticks ;
+    // les proies arrivent en deux populations, fusionnées au premier pas
+    fn hunt_on_one_voxel(predators: u32) -> (u32, u32) {
+        let mut world = soil_world(1.0);
+        let species = vec![
+            species(0, Diet::Producer),
+            species(1, Diet::Consumer { prey_species: 0 }),
+        ];
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        let mut populations =
+            vec![Population::new(0, 0, 0, 0, 400), Population::new(0, 0, 0, 0, 400)];
+        if predators > 0 {
+            populations.push(Population::new(1, 0, 0, 0, predators));
+        }
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..5 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        let size_of = |id: u32| {
+            populations.iter().filter(|p| p.species_id == id).map(|p| p.size).sum::<u32>()
+        };
+        (size_of(0), size_of(1))
+    }
+
+    #[test]
+    fn predators_grow_while_their_prey_declines() {
+        let (prey_alone, _) = hunt_on_one_voxel(0);
+        let (prey, predators) = hunt_on_one_voxel(200);
+        assert!(predators > 200, "the predators should grow, got {}", predators);
+        assert!(prey < prey_alone, "prey {} with predators, {} without", prey, prey_alone);
+        assert!(prey < 800, "the prey should decline, got {}", prey);
+    }
+
+    #[test]
+    fn freezing_crashes_a_healthy_population() {
+        let mut world = soil_world(1.0);
+        let species = vec![species(0, Diet::Producer)];
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        let mut populations = vec![Population::new(0, 0, 0, 0, 500)];
+        let mut rng = StdRng::seed_from_u64(4);
+        let size = |populations: &[Population]| populations.iter().map(|p| p.size).sum::<u32>();
+
+        // Quelques ticks à 20 °C : la population se maintient
+        for _ in 0..3 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        let healthy = size(&populations);
+        assert!(healthy >= 400, "the population should be healthy, got {}", healthy);
+
+        // Le voxel gèle sous elle
+        world.set_material(0, 0, 0, VoxelMaterial::Ice);
+        world.get_mut(0, 0, 0).temperature = -15.0;
+        for _ in 0..5 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        let frozen = size(&populations);
+        assert!(frozen < healthy / 10, "{} left of {}", frozen, healthy);
+    }
+
+    #[test]
+    fn merging_weights_traits_by_size() {
+        let mut big = Population::new(0, 0, 0, 0, 300);
+        big.drift.metabolism = 0.4;
+        big.drift.preferred_temperature = -2.0;
+        let mut small = Population::new(0, 0, 0, 0, 100);
+        small.drift.metabolism = -0.4;
+        small.drift.preferred_temperature = 6.0;
+
+        big.merge_with(&small);
+        assert_eq!(big.size, 400);
+        assert!((big.drift.metabolism - 0.2).abs() < 1e-6, "{}", big.drift.metabolism);
+        assert!((big.drift.preferred_temperature - 0.0).abs() < 1e-6);
+
+        // Avec une population géante, la taille fusionnée sature au lieu de déborder
+        let mut huge = Population::new(0, 0, 0, 0, u32::MAX - 5);
+        huge.merge_with(&small);
+        assert_eq!(huge.size, u32::MAX);
+    }
+
+    // Un tick d'une population de 1000 dont la croissance compense tout juste
+    // le coût métabolique, calculé sur la taille de départ
+    fn tick_at_equilibrium(growth_model: GrowthModel) -> (u32, BiomassLedger) {
+        let mut world = soil_world(1.0);
+        world.get_mut(0, 0, 0).nutrients = 1000.0;
+        // Croissance 0,1 × 1,2 (pic de la réponse thermique) = coût 12 × 0,01
+        let species = vec![Species { metabolism: 12.0, ..species(0, Diet::Producer) }];
+        let rules = BiologyRules { growth_model, speciation_chance: 0.0, ..Default::default() };
+        let mut populations = vec![Population::new(0, 0, 0, 0, 1000)];
+        let mut rng = StdRng::seed_from_u64(5);
+        let ledger = step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        (populations[0].size, ledger)
+    }
+
+    #[test]
+    fn growth_models_diverge_by_the_cost_of_the_newborns() {
+        let (net, net_ledger) = tick_at_equilibrium(GrowthModel::NetRate);
+        let (sequential, sequential_ledger) = tick_at_equilibrium(GrowthModel::GrowthThenCost);
+        let growth = net_ledger.growth;
+        assert!(growth > 100, "growth {}", growth);
+        assert_eq!(sequential_ledger.growth, growth);
+
+        // Taux net : la population reste à l'équilibre
+        assert_eq!(net_ledger.metabolism, growth);
+        assert_eq!(net, 1000);
+        // Croissance puis coût : les nouveau-nés paient aussi, la population recule
+        let cost = ((1000 + growth) as f32 * (12.0 * 0.01)) as u64;
+        assert_eq!(sequential_ledger.metabolism, cost);
+        assert_eq!(sequential as u64, 1000 + growth - cost);
+        assert!(sequential < net);
+    }
+
+    #[test]
+    fn boxed_in_populations_route_out_through_the_open_side() {
+        // Une cellule de sol murée de roche, sauf à l'est, où un couloir de sol
+        // tourne vers le nord jusqu'à la cible
+        let mut world = World3D::new(5, 5, 1);
+        for y in 0..5 {
+            for x in 0..5 {
+                world.set_material(x, y, 0, VoxelMaterial::Rock);
+            }
+        }
+        for (x, y) in [(2, 2), (3, 2), (4, 2), (4, 3), (4, 4)] {
+            world.set_material(x, y, 0, VoxelMaterial::Soil);
+        }
+        for neighbors in [NeighborSet::Orthogonal, NeighborSet::Full] {
+            let step = route_step(&world, (2, 2, 0), (2, 4, 0), neighbors);
+            assert_eq!(step, Some((3, 2, 0)), "{:?}", neighbors);
+        }
+
+        // Le côté ouvert se referme : plus aucune issue
+        world.set_material(3, 2, 0, VoxelMaterial::Rock);
+        assert_eq!(route_step(&world, (2, 2, 0), (2, 4, 0), NeighborSet::Full), None);
+    }
+
+    // Une population très mobile sur un damier 2×2 dont seule la diagonale est
+    // habitable : atteint-elle l'autre coin ?
+    fn reaches_the_diagonal(movement_neighbors: NeighborSet) -> bool {
+        let mut world = World3D::new(2, 2, 1);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let material = if x == y { VoxelMaterial::Soil } else { VoxelMaterial::Rock };
+            world.set_material(x, y, 0, material);
+            world.get_mut(x, y, 0).temperature = 20.0;
+        }
+        let species = vec![Species { mobility: 10.0, ..species(0, Diet::Producer) }];
+        let rules = BiologyRules {
+            movement_neighbors,
+            nutrient_regeneration: 1.0,
+            speciation_chance: 0.0,
+            ..Default::default()
+        };
+        let mut populations = vec![Population::new(0, 0, 0, 0, 500)];
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..10 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        populations.iter().any(|pop| (pop.x, pop.y) == (1, 1))
+    }
+
+    #[test]
+    fn only_full_neighborhoods_cross_diagonals() {
+        assert!(reaches_the_diagonal(NeighborSet::Full));
+        assert!(!reaches_the_diagonal(NeighborSet::Orthogonal));
+    }
+
+    // Taille d'une population d'eau douce après quelques ticks dans un voxel
+    // d'eau à 20 °C de la salinité donnée
+    fn freshwater_size_after(salinity: f32) -> u32 {
+        let mut world = World3D::new(1, 1, 1);
+        let voxel = world.get_mut(0, 0, 0);
+        *voxel = Voxel::water();
+        voxel.temperature = 20.0;
+        voxel.salinity = salinity;
+        world.refresh_material_counts();
+        let freshwater = Species { salinity_tolerance: (0.0, 0.1), ..species(0, Diet::Producer) };
+        let species = vec![freshwater];
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        let mut populations = vec![Population::new(0, 0, 0, 0, 300)];
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..5 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        populations.iter().map(|p| p.size).sum()
+    }
+
+    #[test]
+    fn freshwater_species_decline_in_salt_water() {
+        let fresh = freshwater_size_after(0.05);
+        let salty = freshwater_size_after(0.8);
+        assert!(fresh > 200, "the fresh water should sustain them, got {}", fresh);
+        assert!(salty < fresh / 2, "{} left in salt water, {} in fresh water", salty, fresh);
+    }
+
+    #[test]
+    fn seeded_ticks_do_not_depend_on_the_input_order() {
+        let mut world = World3D::new(4, 4, 1);
+        for y in 0..4 {
+            for x in 0..4 {
+                world.set_material(x, y, 0, VoxelMaterial::Soil);
+                world.get_mut(x, y, 0).temperature = 20.0;
+            }
+        }
+        let wanderer = |id: u32| Species { mobility: 5.0, ..species(id, Diet::Producer) };
+        let species = vec![wanderer(0), wanderer(1)];
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        let populations: Vec<Population> = (0..8)
+            .map(|i| Population::new(i % 2, i % 4, i / 2, 0, 100 + 20 * i))
+            .collect();
+
+        // Le même tick, les populations données dans l'ordre puis à l'envers
+        let tick = |mut populations: Vec<Population>| {
+            let mut world = world.clone();
+            let mut rng = StdRng::seed_from_u64(8);
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+            populations.iter().map(|p| (p.species_id, p.x, p.y, p.z, p.size)).collect::<Vec<_>>()
+        };
+        let forward = tick(populations.clone());
+        let backward = tick(populations.into_iter().rev().collect());
+        assert!(forward.len() >= 8);
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn predators_without_prey_starve() {
+        let mut world = soil_world(1.0);
+        let species = vec![species(1, Diet::Consumer { prey_species: 0 })];
+        let mut populations = vec![Population::new(1, 0, 0, 0, 200)];
+        let mut rng = StdRng::seed_from_u64(3);
+        step_biology(&mut world, &BiologyRules::default(), &species, &mut populations, &mut rng);
+        assert!(populations.iter().map(|p| p.size).sum::<u32>() < 200);
+    }
+
+    #[test]
+    fn biomass_is_conserved_without_reproduction_or_metabolism() {
+        // Trois voxels de sol riches (pas de surpopulation) ; deux populations à
+        // fusionner et une qui peut migrer
+        let mut world = World3D::new(3, 1, 1);
+        for x in 0..3 {
+            let voxel = world.get_mut(x, 0, 0);
+            *voxel = Voxel::soil();
+            voxel.temperature = 20.0;
+            voxel.fertility = 1.0;
+            voxel.nutrients = 1000.0;
+        }
+        world.refresh_material_counts();
+        let mobile = Species { mobility: 1.0, ..species(1, Diet::Producer) };
+        let species = vec![species(0, Diet::Producer), mobile];
+        let rules = BiologyRules {
+            reproduction_enabled: false,
+            metabolism_enabled: false,
+            speciation_chance: 0.0,
+            ..Default::default()
+        };
+        let mut populations = vec![
+            Population::new(0, 0, 0, 0, 30),
+            Population::new(0, 0, 0, 0, 20),
+            Population::new(1, 2, 0, 0, 40),
+        ];
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..5 {
+            let ledger = step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+            assert_eq!((ledger.before, ledger.after), (90, 90), "{:?}", ledger);
+            assert_eq!(ledger.discrepancy(), 0);
+            assert_eq!(total_biomass(&populations), 90);
+        }
+    }
+
+    #[test]
+    fn the_registry_numbers_species_and_refuses_duplicates() {
+        let mut registry = SpeciesRegistry::new();
+        let mut rng = StdRng::seed_from_u64(1);
+        let ids: Vec<u32> = (0..3).map(|_| registry.create(&mut rng).id).collect();
+        assert_eq!(ids, [0, 1, 2]);
+
+        // Un doublon est refusé et le registre reste inchangé
+        let duplicate = registry.insert(species(1, Diet::Producer));
+        assert!(matches!(duplicate, Err(SpeciesError::DuplicateId(1))));
+        assert_eq!(registry.len(), 3);
+        // Un identifiant libre est accepté, et la création suivante passe au-delà
+        registry.insert(species(7, Diet::Producer)).unwrap();
+        assert_eq!(registry.create(&mut rng).id, 8);
+    }
+
+    // Tailles successives d'une population sur de la roche, jusqu'à extinction
+    fn sizes_on_rock(size: u32) -> Vec<u32> {
+        let mut world = World3D::new(1, 1, 1);
+        world.set_material(0, 0, 0, VoxelMaterial::Rock);
+        world.get_mut(0, 0, 0).temperature = 20.0;
+        let species = vec![species(0, Diet::Producer)];
+        let mut populations = vec![Population::new(0, 0, 0, 0, size)];
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut sizes = Vec::new();
+        while let Some(pop) = populations.first() {
+            sizes.push(pop.size);
+            let ledger = step_biology(
+                &mut world,
+                &BiologyRules::default(),
+                &species,
+                &mut populations,
+                &mut rng,
+            );
+            assert_eq!(ledger.unsuitable_habitat, *sizes.last().unwrap() as u64 - ledger.after);
+        }
+        sizes
+    }
+
+    #[test]
+    fn populations_on_rock_die_off_gradually_whatever_their_size() {
+        let small = sizes_on_rock(40);
+        let large = sizes_on_rock(40_000);
+        // Un quart de la population par tick, plus deux individus
+        assert_eq!(&small[..3], [40, 28, 19]);
+        assert_eq!(large[1], 29_998);
+        // La petite ne disparaît pas d'un coup, et la grande, mille fois plus
+        // nombreuse, ne tient que quelques fois plus longtemps
+        assert!(small.len() > 3, "{:?}", small);
+        assert!(large.len() < small.len() * 5, "{} vs {} ticks", large.len(), small.len());
+    }
+
+    #[test]
+    fn colocated_species_share_the_carrying_capacity() {
+        // Taille totale après 100 ticks de `count` espèces sur le même voxel
+        let settled_total = |count: u32| -> u32 {
+            let mut world = soil_world(1.0);
+            let species: Vec<Species> =
+                (0..count).map(|id| species(id, Diet::Producer)).collect();
+            let rules = BiologyRules {
+                nutrient_regeneration: 1.0,
+                speciation_chance: 0.0,
+                ..Default::default()
+            };
+            let mut populations: Vec<Population> =
+                (0..count).map(|id| Population::new(id, 0, 0, 0, 50)).collect();
+            let mut rng = StdRng::seed_from_u64(1);
+            for _ in 0..100 {
+                step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+            }
+            assert_eq!(populations.len(), count as usize);
+            populations.iter().map(|p| p.size).sum()
+        };
+
+        // Trois espèces se partagent ce qu'une seule occuperait, sans le tripler
+        let alone = settled_total(1);
+        let together = settled_total(3);
+        assert!(together < alone * 3 / 2, "alone {}, together {}", alone, together);
+        assert!(together > alone * 2 / 3, "alone {}, together {}", alone, together);
+    }
+
+    #[test]
+    fn species_adapted_to_the_ice_prefer_the_cold() {
+        // Un sol de toundra gelée et une mer polaire
+        let mut tundra = Voxel::soil();
+        tundra.temperature = -15.0;
+        let mut polar_sea = Voxel::water();
+        polar_sea.temperature = -1.0;
+        polar_sea.salinity = 0.8;
+
+        let mut registry = SpeciesRegistry::new();
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..20 {
+            let species = registry.create_adapted(&tundra, &mut rng);
+            assert!(species.preferred_temperature < -10.0, "{}", species.preferred_temperature);
+            assert!(species.suits(&tundra));
+            let species = registry.create_adapted(&polar_sea, &mut rng);
+            assert!(species.preferred_temperature < 2.0, "{}", species.preferred_temperature);
+            assert!(species.suits(&polar_sea));
+        }
+        // Bien en dessous des espèces aléatoires, nées pour 15 à 25 °C
+        let random = Species::random(99, &mut rng);
+        assert!(random.preferred_temperature >= 15.0);
+    }
+
+    #[test]
+    fn harsh_climates_cost_more_metabolism() {
+        // Coût métabolique d'un tick pour la même population, sans croissance
+        let metabolic_cost = |temperature: f32, thermoregulation_cost: f32| {
+            let mut world = soil_world(1.0);
+            world.get_mut(0, 0, 0).temperature = temperature;
+            world.get_mut(0, 0, 0).nutrients = 1000.0;
+            let species = vec![species(0, Diet::Producer)];
+            let rules = BiologyRules {
+                reproduction_enabled: false,
+                thermoregulation_cost,
+                speciation_chance: 0.0,
+                ..Default::default()
+            };
+            let mut populations = vec![Population::new(0, 0, 0, 0, 1000)];
+            let mut rng = StdRng::seed_from_u64(0);
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng).metabolism
+        };
+
+        // 0,5 × 0,01 × 1000 à l'optimum, et dans la zone de confort
+        assert_eq!(metabolic_cost(20.0, 0.2), 5);
+        assert_eq!(metabolic_cost(24.0, 0.2), 5);
+        // 10 degrés au-delà de la zone de confort : le coût triple
+        assert_eq!(metabolic_cost(35.0, 0.2), 15);
+        // Sans surcoût de thermorégulation, le climat ne change rien
+        assert_eq!(metabolic_cost(35.0, 0.0), 5);
+    }
+
+    #[test]
+    fn templates_bias_the_drawn_traits() {
+        // Des extrêmophiles au métabolisme rapide, à l'aise vers 60 °C
+        let extremophiles = SpeciesTemplate {
+            metabolism: 3.0..5.0,
+            preferred_temperature: 55.0..65.0,
+            salinity_width: 0.5..0.5,
+            ..Default::default()
+        };
+        let mut registry = SpeciesRegistry::new();
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..200 {
+            let species = registry.create_from_template(&extremophiles, &mut rng);
+            assert!(species.metabolism >= 3.0 && species.metabolism < 5.0);
+            assert!((55.0..65.0).contains(&species.preferred_temperature));
+            // Une plage vide donne sa borne basse
+            let (min, max) = species.salinity_tolerance;
+            assert!((max - min - 0.5).abs() < 1e-6);
+        }
+        // Le modèle par défaut reste celui des espèces aléatoires
+        let default = SpeciesTemplate::default();
+        for _ in 0..200 {
+            let species = registry.create_from_template(&default, &mut rng);
+            assert!(species.metabolism < 2.0);
+        }
+    }
+
+    // Taille après `ticks` ticks d'une population de 1000, d'une espèce de la
+    // tolérance thermique donnée, sur un sol à `temperature`
+    fn size_after(tolerance: f32, temperature: f32, ticks: usize) -> u32 {
+        let mut world = soil_world(1.0);
+        world.get_mut(0, 0, 0).temperature = temperature;
+        let species = vec![Species {
+            metabolism: 4.0,
+            thermal_tolerance: tolerance,
+            ..species(0, Diet::Producer)
+        }];
+        let rules = BiologyRules {
+            nutrient_regeneration: 1.0,
+            thermal_adaptation: 0.0,
+            speciation_chance: 0.0,
+            ..Default::default()
+        };
+        let mut populations = vec![Population::new(0, 0, 0, 0, 1000)];
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..ticks {
+            world.get_mut(0, 0, 0).nutrients = 10_000.0;
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        populations.iter().map(|p| p.size).sum()
+    }
+
+    #[test]
+    fn specialists_thrive_at_their_optimum_and_suffer_away_from_it() {
+        let (narrow, wide) = (2.0, 10.0);
+        // À l'optimum, le spécialiste croît bien plus vite
+        let (specialist, generalist) = (size_after(narrow, 20.0, 1), size_after(wide, 20.0, 1));
+        assert!(specialist - 1000 > 2 * (generalist - 1000), "{} vs {}", specialist, generalist);
+        // Six degrés plus loin, le spécialiste décline quand le généraliste prospère encore
+        let (specialist, generalist) = (size_after(narrow, 26.0, 10), size_after(wide, 26.0, 10));
+        assert!(specialist < 1000, "specialist {}", specialist);
+        assert!(generalist > 1000, "generalist {}", generalist);
+    }
+
+    #[test]
+    fn organic_tags_saturate_instead_of_wrapping() {
+        let tag =
+            |species_id: u32, size: u32| Population::new(species_id, 0, 0, 0, size).organic_tag();
+        // 300 ne tient pas dans un u8 : l'identifiant d'espèce ne doit pas déborder dans le tag
+        assert_eq!(tag(300, 12_345), VoxelMaterial::Organic(123));
+        assert_eq!(tag(300, 12_345), tag(300 % 256, 12_345));
+        assert_eq!(tag(u32::MAX, 50), VoxelMaterial::Organic(0));
+        // Une densité de 300 sature à 255 au lieu de retomber à 44
+        assert_eq!(tag(300, 30_000), VoxelMaterial::Organic(u8::MAX));
+        assert_eq!(tag(300, u32::MAX), VoxelMaterial::Organic(u8::MAX));
+    }
+
+    // Un sol fertile à x = 1 et, à côté, de la lave ou un second sol ; une espèce
+    // très mobile part de x = 1. Renvoie vrai si la vie atteint x = 0 en 100 ticks.
+    fn reaches_the_neighbor(neighbor: VoxelMaterial) -> bool {
+        let mut world = World3D::new(2, 1, 1);
+        world.set_material(0, 0, 0, neighbor);
+        world.set_material(1, 0, 0, VoxelMaterial::Soil);
+        world.get_mut(0, 0, 0).temperature =
+            if neighbor == VoxelMaterial::Lava { 1200.0 } else { 20.0 };
+        world.get_mut(1, 0, 0).temperature = 20.0;
+        let species = vec![Species { mobility: 10.0, ..species(0, Diet::Producer) }];
+        let rules = BiologyRules::default();
+        let mut populations = vec![Population::new(0, 1, 0, 0, 1000)];
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..100 {
+            for x in 0..2 {
+                world.get_mut(x, 0, 0).nutrients = 1000.0;
+            }
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+            if populations.iter().any(|pop| pop.x == 0) {
+                return true;
+            }
+        }
+        assert!(!populations.is_empty());
+        false
+    }
+
+    #[test]
+    fn populations_never_disperse_into_lava() {
+        assert!(reaches_the_neighbor(VoxelMaterial::Soil));
+        assert!(!reaches_the_neighbor(VoxelMaterial::Lava));
+    }
+
+    #[test]
+    fn dispersal_conserves_biomass() {
+        // Un champ de sol fertile, une population très mobile sur chaque voxel central
+        let mut world = World3D::new(5, 5, 1);
+        for y in 0..5 {
+            for x in 0..5 {
+                let voxel = world.get_mut(x, y, 0);
+                *voxel = Voxel::soil();
+                voxel.temperature = 20.0;
+                voxel.fertility = 1.0;
+                voxel.nutrients = 10_000.0;
+            }
+        }
+        world.refresh_material_counts();
+        let species = vec![Species { mobility: 10.0, ..species(0, Diet::Producer) }];
+        let mut populations: Vec<Population> =
+            (1..4).flat_map(|y| (1..4).map(move |x| Population::new(0, x, y, 0, 200))).collect();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        // Sans croissance ni métabolisme, seule la dispersion déplace la biomasse
+        let rules = BiologyRules {
+            reproduction_enabled: false,
+            metabolism_enabled: false,
+            speciation_chance: 0.0,
+            ..Default::default()
+        };
+        let ledger = step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        assert!(ledger.dispersed >= 500, "{:?}", ledger);
+        assert_eq!(ledger.arrived, ledger.dispersed);
+        assert_eq!((ledger.before, ledger.after), (1800, 1800));
+        assert_eq!(total_biomass(&populations), 1800);
+
+        // Avec le coût du voyage, le bilan explique encore chaque unité perdue
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        let ledger = step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        assert!(ledger.dispersed > ledger.arrived, "{:?}", ledger);
+        assert_eq!(ledger.after as i64, ledger.expected_after(), "{:?}", ledger);
+        assert_eq!(total_biomass(&populations), ledger.after);
+    }
+
+    // Une population tenue `ticks` ticks à `temperature`, nutriments renouvelés
+    fn held_at(
+        temperature: f32,
+        pop: Population,
+        ticks: usize,
+        rules: &BiologyRules,
+    ) -> Population {
+        let mut world = soil_world(1.0);
+        world.get_mut(0, 0, 0).temperature = temperature;
+        let species = vec![species(0, Diet::Producer)];
+        let mut populations = vec![pop];
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..ticks {
+            world.get_mut(0, 0, 0).nutrients = 10_000.0;
+            step_biology(&mut world, rules, &species, &mut populations, &mut rng);
+        }
+        populations.into_iter().next().expect("la population survit")
+    }
+
+    #[test]
+    fn populations_kept_in_the_cool_adapt_to_the_cold() {
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        // Des générations à 12 °C, huit degrés sous la préférence de l'espèce
+        let settlers = held_at(12.0, Population::new(0, 0, 0, 0, 1000), 300, &rules);
+        let shift = settlers.drift.preferred_temperature;
+        assert!(shift < -5.0, "{}", shift);
+        // Sans adaptation thermique, la préférence ne bouge pas
+        let fixed = BiologyRules { thermal_adaptation: 0.0, ..rules.clone() };
+        let unadapted = held_at(12.0, Population::new(0, 0, 0, 0, 1000), 300, &fixed);
+        assert_eq!(unadapted.drift.preferred_temperature, 0.0);
+
+        // Portées à 6 °C, les descendantes acclimatées s'en sortent mieux que des novices
+        let mut hardy = settlers.clone();
+        hardy.size = 1000;
+        let hardy = held_at(6.0, hardy, 10, &fixed);
+        let naive = held_at(6.0, Population::new(0, 0, 0, 0, 1000), 10, &fixed);
+        assert!(hardy.size > 2 * naive.size, "{} vs {}", hardy.size, naive.size);
+    }
+
+    #[test]
+    fn grazed_soil_slowly_recovers_its_nutrients() {
+        let mut world = soil_world(1.0);
+        let fresh = world.get(0, 0, 0).nutrients;
+        assert_eq!(fresh, VoxelMaterial::Soil.base_nutrients());
+        let species = vec![species(0, Diet::Producer)];
+        let rules = BiologyRules { speciation_chance: 0.0, ..Default::default() };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // Une population de 20 broute le voxel pendant trois ticks
+        let mut populations = vec![Population::new(0, 0, 0, 0, 20)];
+        for _ in 0..3 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+        }
+        let grazed = world.get(0, 0, 0).nutrients;
+        assert!(grazed < fresh / 2.0, "{} après broutage", grazed);
+
+        // Une fois le voxel abandonné, les nutriments remontent, sans tout rattraper
+        let mut history = vec![grazed];
+        populations.clear();
+        for _ in 0..5 {
+            step_biology(&mut world, &rules, &species, &mut populations, &mut rng);
+            history.push(world.get(0, 0, 0).nutrients);
+        }
+        assert!(history.windows(2).all(|w| w[0] < w[1]), "{:?}", history);
+        assert!(history[5] < fresh, "{:?}", history);
+    }
 }
@@ -1,8 +1,58 @@
-use crate::biology::Population;
-use crate::world3d::World3D;
-use rand::Rng;
+use crate::biology::{BiologyRules, Population};
+use crate::spatial::SpatialGrid;
+use crate::world3d::{NeighborSet, Voxel, VoxelMaterial, World3D};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
-#[derive(Clone)]
+const COLONY_MIN_POPULATION: u32 = 2000;
+const COLONY_MIN_TECH: f32 = 3.0;
+const COLONY_MAX_AGGRESSION: f32 = 0.5;
+const COLONY_COOLDOWN_TICKS: u32 = 50;
+const COLONY_MIN_RANGE: i32 = 4;
+const COLONY_MAX_RANGE: i32 = 8;
+// Civilizations on harsh ground look this far (in x and y) for a better home,
+// and lose this share of their population on the way
+const MIGRATION_RANGE: i32 = 3;
+const MIGRATION_COST: f32 = 0.1;
+// Civilizations mine the ore within this many voxels (a cube), taking this
+// share of each deposit per tick (at least MIN_EXTRACTION, so deposits run
+// out), and gain ORE_TECH_YIELD tech per unit of ore extracted
+const MINING_RANGE: u32 = 4;
+const MINING_RATE: f32 = 0.01;
+const MIN_EXTRACTION: f32 = 0.001;
+const ORE_TECH_YIELD: f32 = 0.05;
+// Defender strength multiplier when fully surrounded by rock or ice
+const TERRAIN_DEFENSE_BONUS: f32 = 0.5;
+
+// Population per voxel of a civilization's footprint
+const FOOTPRINT_DENSITY: f32 = 1000.0;
+
+// Civilizations whose footprints are less than this far apart are in contact:
+// they may trade, ally or go to war
+pub const CONTACT_RANGE: f32 = 10.0;
+// Side (in voxels) of the spatial grid cells used to look up nearby civilizations
+pub(crate) const CIV_GRID_CELL: u32 = 16;
+
+// Number of traits in a culture vector, and the name of each trait
+pub const CULTURE_TRAITS: usize = 4;
+pub const CULTURE_NAMES: [&str; CULTURE_TRAITS] = ["Agrarian", "Mercantile", "Martial", "Mystic"];
+// Fraction of the gap closed each tick between peaceful neighbors
+const CULTURE_EXCHANGE_RATE: f32 = 0.05;
+// Fraction of the gap a defeated civilization closes toward its conqueror
+const CULTURE_CONQUEST_RATE: f32 = 0.3;
+
+// Neighbors ally when their combined aggression stays below the first bound
+// and their combined spirituality exceeds the second
+const ALLIANCE_MAX_AGGRESSION: f32 = 0.6;
+const ALLIANCE_MIN_SPIRITUALITY: f32 = 1.2;
+// Fraction of the tech gap the lagging ally closes each tick
+const ALLIANCE_TECH_SHARE: f32 = 0.1;
+// Population growth each ally gets from trade every tick
+const ALLIANCE_GROWTH: f32 = 0.005;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Civilization {
     pub id: u32,
     pub name: String,
@@ -13,14 +63,25 @@ pub struct Civilization {
     pub tech_level: f32,
     pub aggression: f32,
     pub spirituality: f32,
+    pub parent_id: Option<u32>,
+    pub colony_cooldown: u32,
+    // Species of the population that rose to civilization (colonies inherit it)
+    pub founding_species: Option<u32>,
+    // Strength of each trait of CULTURE_NAMES, from 0 to 1
+    pub culture: [f32; CULTURE_TRAITS],
+    // Ids of the civilizations this one is allied with (never at war with them)
+    pub allies: Vec<u32>,
 }
 
 impl Civilization {
     pub fn new(id: u32, x: u32, y: u32, z: u32, population: u32) -> Self {
-        let mut rng = rand::thread_rng();
+        Self::random(id, x, y, z, population, &mut rand::thread_rng())
+    }
+
+    pub fn random(id: u32, x: u32, y: u32, z: u32, population: u32, rng: &mut impl Rng) -> Self {
         Self {
             id,
-            name: generate_civ_name(id),
+            name: generate_civ_name(id, rng),
             x,
             y,
             z,
@@ -28,54 +89,587 @@ impl Civilization {
             tech_level: 1.0,
             aggression: rng.gen_range(0.0..1.0),
             spirituality: rng.gen_range(0.0..1.0),
+            parent_id: None,
+            colony_cooldown: 0,
+            founding_species: None,
+            culture: std::array::from_fn(|_| rng.gen_range(0.0..1.0)),
+            allies: Vec::new(),
         }
     }
 
+    // A civilization emerging from `pop`, shaped by the voxel it grew on:
+    // harsh climates breed aggression, rich land gives a head start in tech
+    pub fn founded_by(id: u32, pop: &Population, voxel: &Voxel, rng: &mut impl Rng) -> Self {
+        let mut civ = Civilization::random(id, pop.x, pop.y, pop.z, pop.size, rng);
+
+        let harshness = (((voxel.temperature - 20.0).abs() - 10.0) / 20.0).clamp(0.0, 1.0);
+        civ.aggression = (rng.gen_range(0.0..0.6) + 0.4 * harshness).min(1.0);
+
+        let richness = (voxel.nutrients * voxel.fertility / 10.0).clamp(0.0, 2.0);
+        civ.tech_level += 0.25 * richness;
+
+        civ.founding_species = Some(pop.species_id);
+        civ
+    }
+
+    // A daughter colony inherits its parent's culture and part of its knowledge
+    pub fn colony_of(
+        parent: &Civilization,
+        id: u32,
+        x: u32,
+        y: u32,
+        z: u32,
+        population: u32,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut colony = Civilization::random(id, x, y, z, population, rng);
+        colony.tech_level = parent.tech_level * 0.8;
+        colony.aggression = parent.aggression;
+        colony.spirituality = parent.spirituality;
+        colony.culture = parent.culture;
+        colony.parent_id = Some(parent.id);
+        colony.founding_species = parent.founding_species;
+        colony.colony_cooldown = COLONY_COOLDOWN_TICKS;
+        colony
+    }
+
     pub fn distance_to(&self, other: &Civilization) -> f32 {
         let dx = self.x as f32 - other.x as f32;
         let dy = self.y as f32 - other.y as f32;
         let dz = self.z as f32 - other.z as f32;
         (dx * dx + dy * dy + dz * dz).sqrt()
     }
+
+    // Minimum-image distance on a world that wraps around on every axis
+    pub fn distance_to_wrapped(&self, other: &Civilization, dims: (u32, u32, u32)) -> f32 {
+        let axis = |a: u32, b: u32, size: u32| {
+            let d = (a as f32 - b as f32).abs();
+            d.min(size as f32 - d)
+        };
+        let dx = axis(self.x, other.x, dims.0);
+        let dy = axis(self.y, other.y, dims.1);
+        let dz = axis(self.z, other.z, dims.2);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+
+    // Distance honoring the world's topology
+    pub fn distance_in(&self, other: &Civilization, world: &World3D) -> f32 {
+        world.distance((self.x, self.y, self.z), (other.x, other.y, other.z))
+    }
+
+    // Radius of the ball of voxels the civilization occupies, growing with
+    // its population (a lone settlement covers just its own voxel)
+    pub fn footprint_radius(&self) -> f32 {
+        (self.population as f32 / FOOTPRINT_DENSITY).cbrt()
+    }
+
+    // True if (x, y, z) lies within the civilization's footprint
+    pub fn covers(&self, x: u32, y: u32, z: u32, world: &World3D) -> bool {
+        world.distance((self.x, self.y, self.z), (x, y, z)) <= self.footprint_radius()
+    }
+
+    // Distance between the edges of two footprints (0 when they overlap)
+    pub fn gap_to(&self, other: &Civilization, world: &World3D) -> f32 {
+        let reach = self.footprint_radius() + other.footprint_radius();
+        (self.distance_in(other, world) - reach).max(0.0)
+    }
+
+    // Two nearby aggressive civilizations are considered at war, unless allied
+    pub fn is_hostile_to(&self, other: &Civilization, world: &World3D) -> bool {
+        self.aggression > 0.6
+            && other.aggression > 0.6
+            && self.gap_to(other, world) < CONTACT_RANGE
+            && !self.is_allied_with(other)
+    }
+
+    pub fn is_allied_with(&self, other: &Civilization) -> bool {
+        self.allies.contains(&other.id)
+    }
+
+    // Name of the strongest trait of this civilization's culture
+    pub fn culture_label(&self) -> &'static str {
+        CULTURE_NAMES[self.dominant_trait()]
+    }
+
+    // Index in CULTURE_NAMES of the strongest trait
+    pub fn dominant_trait(&self) -> usize {
+        (0..CULTURE_TRAITS)
+            .max_by(|&a, &b| self.culture[a].total_cmp(&self.culture[b]))
+            .unwrap_or(0)
+    }
+
+    // Move this culture a fraction `rate` of the way toward `other`
+    pub fn blend_culture(&mut self, other: &[f32; CULTURE_TRAITS], rate: f32) {
+        for (trait_value, target) in self.culture.iter_mut().zip(other) {
+            *trait_value += (target - *trait_value) * rate;
+        }
+    }
+
+    pub fn military_strength(&self) -> f32 {
+        // Scale in f64: an f32 cannot represent large populations exactly
+        self.tech_level + (self.population as f64 * 0.001) as f32
+    }
+}
+
+// Multiplier for a defender's strength: the more of its surroundings are rock
+// or ice, the harder it is to assault
+pub fn terrain_defense(world: &World3D, x: u32, y: u32, z: u32) -> f32 {
+    if x >= world.width || y >= world.height || z >= world.depth {
+        return 1.0;
+    }
+
+    let mut total = 0;
+    let mut defensible = 0;
+    for (nx, ny, nz) in world.neighbors(x, y, z, NeighborSet::Full) {
+        total += 1;
+        if matches!(world.get(nx, ny, nz).material, VoxelMaterial::Rock | VoxelMaterial::Ice) {
+            defensible += 1;
+        }
+    }
+
+    if total == 0 {
+        1.0
+    } else {
+        1.0 + TERRAIN_DEFENSE_BONUS * defensible as f32 / total as f32
+    }
 }
 
-fn generate_civ_name(id: u32) -> String {
+fn generate_civ_name(id: u32, rng: &mut impl Rng) -> String {
     let prefixes = ["Astra", "Terra", "Zeno", "Kryth", "Luma", "Vexis", "Orin", "Drak"];
     let suffixes = ["nians", "ites", "oks", "ans", "ari", "oni", "ian", "eth"];
 
-    let mut rng = rand::thread_rng();
     let prefix = prefixes[rng.gen_range(0..prefixes.len())];
     let suffix = suffixes[rng.gen_range(0..suffixes.len())];
 
     format!("{}{} #{}", prefix, suffix, id)
 }
 
+// Syllables a name is built from: a prefix, sometimes a middle, and a suffix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyllableBank {
+    pub prefixes: Vec<String>,
+    pub middles: Vec<String>,
+    pub suffixes: Vec<String>,
+}
+
+impl SyllableBank {
+    pub fn new(prefixes: &[&str], middles: &[&str], suffixes: &[&str]) -> Self {
+        let owned = |parts: &[&str]| parts.iter().map(|p| p.to_string()).collect();
+        Self {
+            prefixes: owned(prefixes),
+            middles: owned(middles),
+            suffixes: owned(suffixes),
+        }
+    }
+}
+
+// Names civilizations from its own seeded RNG. Each culture trait (see
+// CULTURE_NAMES) has its own bank, so civilizations of the same culture sound
+// alike; a name is never handed out twice.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameGenerator {
+    // One bank per culture trait; cultures beyond the list wrap around
+    pub banks: Vec<SyllableBank>,
+    rng: ChaCha12Rng,
+    used: HashSet<String>,
+}
+
+impl NameGenerator {
+    pub fn new(seed: u64) -> Self {
+        let banks = vec![
+            SyllableBank::new(
+                &["Terra", "Vel", "Orin", "Mead", "Hol", "Fen"],
+                &["a", "do", "ra", "wen"],
+                &["ans", "ites", "folk", "dale", "ari"],
+            ),
+            SyllableBank::new(
+                &["Astra", "Luma", "Sel", "Kar", "Mira", "Port"],
+                &["ve", "li", "sa", "no"],
+                &["ians", "eans", "oni", "ese", "ani"],
+            ),
+            SyllableBank::new(
+                &["Drak", "Kryth", "Vexis", "Gor", "Thar", "Ruk"],
+                &["g", "ka", "zu", "rak"],
+                &["oks", "ar", "eth", "ull", "akh"],
+            ),
+            SyllableBank::new(
+                &["Zeno", "Ilu", "Saer", "Ona", "Ethe", "Qui"],
+                &["th", "ae", "li", "on"],
+                &["nians", "im", "iel", "ora", "ith"],
+            ),
+        ];
+        Self::with_banks(seed, banks)
+    }
+
+    pub fn with_banks(seed: u64, banks: Vec<SyllableBank>) -> Self {
+        Self {
+            banks,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            used: HashSet::new(),
+        }
+    }
+
+    // A fresh name in the style of `culture` (an index into CULTURE_NAMES)
+    pub fn next(&mut self, culture: usize) -> String {
+        if self.banks.is_empty() {
+            return self.unique("Civilization".to_string());
+        }
+
+        let bank = &self.banks[culture % self.banks.len()];
+        let rng = &mut self.rng;
+        let mut candidate = String::new();
+        for _ in 0..16 {
+            let prefix = pick_syllable(&bank.prefixes, rng);
+            let middle = if rng.gen_bool(0.5) { pick_syllable(&bank.middles, rng) } else { "" };
+            candidate = format!("{}{}{}", prefix, middle, pick_syllable(&bank.suffixes, rng));
+            if !self.used.contains(&candidate) {
+                break;
+            }
+        }
+        self.unique(candidate)
+    }
+
+    // Give `civ` a name in the style of its culture, tagged with its id
+    pub fn name_civilization(&mut self, civ: &mut Civilization) {
+        civ.name = format!("{} #{}", self.next(civ.dominant_trait()), civ.id);
+    }
+
+    // `name`, numbered if it was already taken
+    fn unique(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut count = 1;
+        while self.used.contains(&unique) {
+            count += 1;
+            unique = format!("{} {}", name, count);
+        }
+        self.used.insert(unique.clone());
+        unique
+    }
+}
+
+fn pick_syllable<'a>(syllables: &'a [String], rng: &mut impl Rng) -> &'a str {
+    if syllables.is_empty() {
+        ""
+    } else {
+        &syllables[rng.gen_range(0..syllables.len())]
+    }
+}
+
+pub(crate) fn next_civ_id(civilizations: &[Civilization]) -> u32 {
+    civilizations.iter().map(|c| c.id + 1).max().unwrap_or(0)
+}
+
+// Neither too cold nor too hot for a civilization to thrive
+fn is_temperate(voxel: &Voxel) -> bool {
+    voxel.temperature >= 10.0 && voxel.temperature <= 30.0
+}
+
+// Temperate ground a civilization can settle on
+fn is_settleable(voxel: &Voxel) -> bool {
+    matches!(voxel.material, VoxelMaterial::Soil | VoxelMaterial::Organic(_)) && is_temperate(voxel)
+}
+
+// Settleable surface voxel within MIGRATION_RANGE of the civ and not already
+// taken, preferring the mildest (closest to 20 degrees), then the nearest
+fn find_refuge(
+    world: &World3D,
+    civ: &Civilization,
+    occupied: &HashSet<(u32, u32, u32)>,
+) -> Option<(u32, u32, u32)> {
+    let mut best: Option<((u32, u32, u32), f32, i32)> = None;
+    for dy in -MIGRATION_RANGE..=MIGRATION_RANGE {
+        for dx in -MIGRATION_RANGE..=MIGRATION_RANGE {
+            let (x, y) = (civ.x as i32 + dx, civ.y as i32 + dy);
+            if x < 0 || y < 0 || x >= world.width as i32 || y >= world.height as i32 {
+                continue;
+            }
+            let (x, y) = (x as u32, y as u32);
+            let z = match world.surface_height_at(x, y) {
+                Some(z) => z,
+                None => continue,
+            };
+            let voxel = world.get(x, y, z);
+            if !is_settleable(voxel) || occupied.contains(&(x, y, z)) {
+                continue;
+            }
+            let mildness = (voxel.temperature - 20.0).abs();
+            let steps = dx.abs().max(dy.abs());
+            let better = match best {
+                Some((_, best_mildness, best_steps)) => {
+                    mildness < best_mildness || (mildness == best_mildness && steps < best_steps)
+                }
+                None => true,
+            };
+            if better {
+                best = Some(((x, y, z), mildness, steps));
+            }
+        }
+    }
+    best.map(|(site, _, _)| site)
+}
+
+// Habitable surface voxel in colonizing range of the civ, away from every existing civ
+fn find_colony_site(
+    world: &World3D,
+    civ: &Civilization,
+    civilizations: &[Civilization],
+    pending_colonies: &[Civilization],
+    rng: &mut impl Rng,
+) -> Option<(u32, u32, u32)> {
+    for _ in 0..16 {
+        let dx = rng.gen_range(-COLONY_MAX_RANGE..=COLONY_MAX_RANGE);
+        let dy = rng.gen_range(-COLONY_MAX_RANGE..=COLONY_MAX_RANGE);
+        if dx.abs().max(dy.abs()) < COLONY_MIN_RANGE {
+            continue;
+        }
+
+        let x = civ.x as i32 + dx;
+        let y = civ.y as i32 + dy;
+        if x < 0 || y < 0 || x >= world.width as i32 || y >= world.height as i32 {
+            continue;
+        }
+        let (x, y) = (x as u32, y as u32);
+
+        let z = match world.surface_height_at(x, y) {
+            Some(z) => z,
+            None => continue,
+        };
+        if !is_settleable(world.get(x, y, z)) {
+            continue;
+        }
+
+        let claimed = civilizations.iter().chain(pending_colonies).any(|other| {
+            let ox = other.x as i32 - x as i32;
+            let oy = other.y as i32 - y as i32;
+            ox.abs().max(oy.abs()) < COLONY_MIN_RANGE
+        });
+        if !claimed {
+            return Some((x, y, z));
+        }
+    }
+
+    None
+}
+
+// Thriving, peaceful, advanced civs send settlers to found daughter colonies
+fn found_colonies(
+    world: &World3D,
+    civilizations: &mut Vec<Civilization>,
+    names: &mut NameGenerator,
+    rng: &mut impl Rng,
+) {
+    let mut colonies = Vec::new();
+
+    for i in 0..civilizations.len() {
+        let civ = &civilizations[i];
+        if civ.colony_cooldown > 0
+            || civ.population < COLONY_MIN_POPULATION
+            || civ.tech_level < COLONY_MIN_TECH
+            || civ.aggression > COLONY_MAX_AGGRESSION
+        {
+            continue;
+        }
+
+        if let Some((x, y, z)) = find_colony_site(world, civ, civilizations, &colonies, rng) {
+            let settlers = civ.population / 4;
+            let id = next_civ_id(civilizations).max(next_civ_id(&colonies));
+            let mut colony = Civilization::colony_of(civ, id, x, y, z, settlers, rng);
+            names.name_civilization(&mut colony);
+
+            let parent = &mut civilizations[i];
+            parent.population -= settlers;
+            parent.colony_cooldown = COLONY_COOLDOWN_TICKS;
+            colonies.push(colony);
+        }
+    }
+
+    civilizations.extend(colonies);
+}
+
+// Memory of where civilizations were founded or fell, so a population
+// hovering around the threshold does not found a new civ every tick
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(from = "SitesRepr", into = "SitesRepr")]
+pub struct FoundingSites {
+    cooldowns: HashMap<(u32, u32, u32), u32>,
+    settled: HashSet<(u32, u32, u32)>,
+}
+
+// Serialized form of FoundingSites: sorted lists, as map keys must be strings
+#[derive(Serialize, Deserialize)]
+struct SitesRepr {
+    cooldowns: Vec<((u32, u32, u32), u32)>,
+    settled: Vec<(u32, u32, u32)>,
+}
+
+impl From<FoundingSites> for SitesRepr {
+    fn from(sites: FoundingSites) -> Self {
+        let mut cooldowns: Vec<_> = sites.cooldowns.into_iter().collect();
+        cooldowns.sort_unstable();
+        let mut settled: Vec<_> = sites.settled.into_iter().collect();
+        settled.sort_unstable();
+        Self { cooldowns, settled }
+    }
+}
+
+impl From<SitesRepr> for FoundingSites {
+    fn from(repr: SitesRepr) -> Self {
+        Self {
+            cooldowns: repr.cooldowns.into_iter().collect(),
+            settled: repr.settled.into_iter().collect(),
+        }
+    }
+}
+
+impl FoundingSites {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Block the site for `cooldown` ticks and require the hysteresis margin afterwards
+    pub fn mark(&mut self, site: (u32, u32, u32), cooldown: u32) {
+        self.settled.insert(site);
+        if cooldown > 0 {
+            self.cooldowns.insert(site, cooldown);
+        }
+    }
+
+    pub fn is_cooling_down(&self, site: (u32, u32, u32)) -> bool {
+        self.cooldowns.contains_key(&site)
+    }
+
+    pub fn was_settled(&self, site: (u32, u32, u32)) -> bool {
+        self.settled.contains(&site)
+    }
+
+    fn tick(&mut self) {
+        self.cooldowns.retain(|_, remaining| {
+            *remaining -= 1;
+            *remaining > 0
+        });
+    }
+}
+
+// Spatial grid of the civilizations' centers, indexed like `civilizations`
+pub(crate) fn civ_grid(civilizations: &[Civilization]) -> SpatialGrid {
+    SpatialGrid::build(CIV_GRID_CELL, civilizations.iter().map(|civ| (civ.x, civ.y, civ.z)))
+}
+
+// Largest footprint radius among `civilizations` (0 without any)
+pub(crate) fn widest_footprint(civilizations: &[Civilization]) -> f32 {
+    civilizations.iter().map(Civilization::footprint_radius).fold(0.0, f32::max)
+}
+
+// Distance from `civ`'s center within which every civilization in contact
+// with it lies, if none has a footprint wider than `widest`
+pub(crate) fn contact_reach(civ: &Civilization, widest: f32) -> f32 {
+    CONTACT_RANGE + civ.footprint_radius() + widest
+}
+
 pub fn maybe_spawn_civilizations(
+    world: &World3D,
     populations: &[Population],
     civilizations: &mut Vec<Civilization>,
+    rules: &BiologyRules,
+    sites: &mut FoundingSites,
+    names: &mut NameGenerator,
+    rng: &mut impl Rng,
 ) {
-    const CIVILIZATION_THRESHOLD: u32 = 500;
+    sites.tick();
 
+    let mut grid = civ_grid(civilizations);
+    let mut widest = widest_footprint(civilizations);
     for pop in populations {
-        if pop.size < CIVILIZATION_THRESHOLD {
+        let site = (pop.x, pop.y, pop.z);
+        if pop.x >= world.width || pop.y >= world.height || pop.z >= world.depth {
+            continue;
+        }
+        if sites.is_cooling_down(site) {
             continue;
         }
 
-        // Check if a civilization already exists at this location
-        let already_exists = civilizations.iter().any(|civ| {
-            civ.x == pop.x && civ.y == pop.y && civ.z == pop.z
-        });
+        let threshold = if sites.was_settled(site) {
+            rules.civilization_threshold as f32 * (1.0 + rules.civilization_hysteresis)
+        } else {
+            rules.civilization_threshold as f32
+        };
+        if (pop.size as f32) < threshold {
+            continue;
+        }
+
+        // Check if the location already belongs to a civilization
+        let already_exists = grid
+            .query_radius(world, site, widest)
+            .into_iter()
+            .any(|i| civilizations[i].covers(pop.x, pop.y, pop.z, world));
 
         if !already_exists {
-            let new_id = civilizations.len() as u32;
-            let civ = Civilization::new(new_id, pop.x, pop.y, pop.z, pop.size);
+            let new_id = next_civ_id(civilizations);
+            let voxel = world.get(pop.x, pop.y, pop.z);
+            let mut civ = Civilization::founded_by(new_id, pop, voxel, rng);
+            names.name_civilization(&mut civ);
+            grid.insert(site);
+            widest = widest.max(civ.footprint_radius());
             civilizations.push(civ);
+            sites.mark(site, rules.founding_cooldown);
         }
     }
 }
 
-pub fn step_civilizations(world: &World3D, civilizations: &mut Vec<Civilization>) {
-    let mut rng = rand::thread_rng();
+// Civilizations mine the ore deposits around them (see MINING_RANGE): the
+// richer the ground, the faster their tech grows, until the veins run dry
+pub fn mine_deposits(world: &mut World3D, civilizations: &mut [Civilization]) {
+    for civ in civilizations.iter_mut() {
+        if civ.x >= world.width || civ.y >= world.height || civ.z >= world.depth {
+            continue;
+        }
+        let mut extracted = 0.0;
+        for (x, y, z) in world.cube_around((civ.x, civ.y, civ.z), MINING_RANGE) {
+            let idx = world.index(x, y, z);
+            let voxel = &mut world.voxels[idx];
+            if voxel.mineral > 0.0 {
+                let taken = (voxel.mineral * MINING_RATE).max(MIN_EXTRACTION).min(voxel.mineral);
+                voxel.mineral -= taken;
+                extracted += taken;
+            }
+        }
+        civ.tech_level += extracted * ORE_TECH_YIELD;
+    }
+}
+
+// Industry warms the land: each civilization heats its voxel and the
+// surrounding ones by `coefficient * tech_level * population` degrees
+pub fn emit_urban_heat(world: &mut World3D, civilizations: &[Civilization], coefficient: f32) {
+    if coefficient == 0.0 {
+        return;
+    }
+
+    for civ in civilizations {
+        if civ.x >= world.width || civ.y >= world.height || civ.z >= world.depth {
+            continue;
+        }
+        let heat = coefficient * civ.tech_level * civ.population as f32;
+        let mut targets: Vec<(u32, u32, u32)> =
+            world.neighbors(civ.x, civ.y, civ.z, NeighborSet::Orthogonal).collect();
+        targets.push((civ.x, civ.y, civ.z));
+        for (x, y, z) in targets {
+            let idx = world.index(x, y, z);
+            world.voxels[idx].temperature += heat;
+        }
+    }
+}
+
+// Returns the civilizations that collapsed this tick
+pub fn step_civilizations(
+    world: &World3D,
+    civilizations: &mut Vec<Civilization>,
+    max_population: u32,
+    names: &mut NameGenerator,
+    rng: &mut impl Rng,
+) -> Vec<Civilization> {
+    // No two civilizations settle on the same voxel
+    let mut occupied: HashSet<(u32, u32, u32)> =
+        civilizations.iter().map(|civ| (civ.x, civ.y, civ.z)).collect();
 
     // Update each civilization
     for civ in civilizations.iter_mut() {
@@ -85,15 +679,27 @@ pub fn step_civilizations(world: &World3D, civilizations: &mut Vec<Civilization>
         // Check environment harshness
         if civ.x < world.width && civ.y < world.height && civ.z < world.depth {
             let voxel = world.get(civ.x, civ.y, civ.z);
-            let harsh = voxel.temperature < 10.0 || voxel.temperature > 30.0;
+            let harsh = !is_temperate(voxel);
 
             if harsh {
-                let loss = (civ.population as f32 * 0.05) as u32;
-                civ.population = civ.population.saturating_sub(loss);
+                // Move somewhere better if possible, otherwise bleed out
+                match find_refuge(world, civ, &occupied) {
+                    Some(refuge) => {
+                        occupied.remove(&(civ.x, civ.y, civ.z));
+                        occupied.insert(refuge);
+                        (civ.x, civ.y, civ.z) = refuge;
+                        let cost = (civ.population as f32 * MIGRATION_COST) as u32;
+                        civ.population = civ.population.saturating_sub(cost);
+                    }
+                    None => {
+                        let loss = (civ.population as f32 * 0.05) as u32;
+                        civ.population = civ.population.saturating_sub(loss);
+                    }
+                }
             } else {
                 // Grow population slightly
                 let growth = (civ.population as f32 * 0.02) as u32;
-                civ.population += growth;
+                civ.population = civ.population.saturating_add(growth).min(max_population);
             }
         }
 
@@ -103,44 +709,548 @@ pub fn step_civilizations(world: &World3D, civilizations: &mut Vec<Civilization>
 
         civ.aggression += (rng.gen::<f32>() - 0.5) * 0.01;
         civ.aggression = civ.aggression.clamp(0.0, 1.0);
+
+        for trait_value in civ.culture.iter_mut() {
+            *trait_value = (*trait_value + (rng.gen::<f32>() - 0.5) * 0.01).clamp(0.0, 1.0);
+        }
+
+        civ.colony_cooldown = civ.colony_cooldown.saturating_sub(1);
     }
 
-    // Check for conflicts between nearby civilizations
-    let civ_count = civilizations.len();
-    for i in 0..civ_count {
-        for j in (i + 1)..civ_count {
-            let distance = {
+    found_colonies(world, civilizations, names, rng);
+    resolve_alliances(world, civilizations, max_population);
+
+    // Check for conflicts between nearby civilizations, pair by pair in index
+    // order; the grid only skips the pairs too far apart to be in contact
+    let grid = civ_grid(civilizations);
+    let mut widest = widest_footprint(civilizations);
+    for i in 0..civilizations.len() {
+        let center = (civilizations[i].x, civilizations[i].y, civilizations[i].z);
+        let mut reach = contact_reach(&civilizations[i], widest);
+        let mut candidates = grid.query_radius(world, center, reach);
+        let mut next = candidates.partition_point(|&k| k <= i);
+        while next < candidates.len() {
+            let j = candidates[next];
+            next += 1;
+            if civilizations[i].is_allied_with(&civilizations[j]) {
+                continue;
+            }
+            let gap = {
                 let civ_i = &civilizations[i];
                 let civ_j = &civilizations[j];
-                civ_i.distance_to(civ_j)
+                civ_i.gap_to(civ_j, world)
             };
 
-            if distance < 10.0 {
+            if gap < CONTACT_RANGE {
                 let aggression_sum = civilizations[i].aggression + civilizations[j].aggression;
 
                 if aggression_sum > 1.2 && rng.gen::<f32>() < 0.1 {
-                    // War! The stronger one wins
-                    let (winner_idx, loser_idx) = if civilizations[i].tech_level
-                        + civilizations[i].population as f32 * 0.001
-                        > civilizations[j].tech_level + civilizations[j].population as f32 * 0.001
-                    {
-                        (i, j)
+                    // War! The more aggressive side attacks; the defender fights
+                    // with the advantage of its terrain
+                    let (attacker, defender) =
+                        if civilizations[i].aggression >= civilizations[j].aggression {
+                            (i, j)
+                        } else {
+                            (j, i)
+                        };
+                    let attack = civilizations[attacker].military_strength();
+                    let defense = {
+                        let civ = &civilizations[defender];
+                        civ.military_strength() * terrain_defense(world, civ.x, civ.y, civ.z)
+                    };
+                    let (winner_idx, loser_idx) = if attack > defense {
+                        (attacker, defender)
                     } else {
-                        (j, i)
+                        (defender, attacker)
                     };
 
                     // Winner gains population, loser loses heavily
                     let spoils = civilizations[loser_idx].population / 3;
-                    civilizations[winner_idx].population += spoils;
+                    let winner = &mut civilizations[winner_idx];
+                    winner.population =
+                        winner.population.saturating_add(spoils).min(max_population);
                     civilizations[loser_idx].population =
                         civilizations[loser_idx].population.saturating_sub(spoils * 2);
 
                     civilizations[winner_idx].tech_level += 0.1;
+
+                    // The conqueror imposes its culture
+                    let winner_culture = civilizations[winner_idx].culture;
+                    civilizations[loser_idx].blend_culture(&winner_culture, CULTURE_CONQUEST_RATE);
+
+                    // Spoils widen the winner's footprint: look again for the
+                    // pairs after this one that it brought into contact
+                    widest = widest.max(civilizations[winner_idx].footprint_radius());
+                    let wider = contact_reach(&civilizations[i], widest);
+                    if wider > reach {
+                        reach = wider;
+                        candidates = grid.query_radius(world, center, reach);
+                        next = candidates.partition_point(|&k| k <= j);
+                    }
+                } else if aggression_sum <= 1.2 {
+                    // Peaceful neighbors trade, and their cultures converge
+                    let culture_i = civilizations[i].culture;
+                    let culture_j = civilizations[j].culture;
+                    civilizations[i].blend_culture(&culture_j, CULTURE_EXCHANGE_RATE);
+                    civilizations[j].blend_culture(&culture_i, CULTURE_EXCHANGE_RATE);
                 }
             }
         }
     }
 
     // Remove collapsed civilizations
-    civilizations.retain(|civ| civ.population > 50);
+    let (survivors, collapsed): (Vec<_>, Vec<_>) =
+        civilizations.drain(..).partition(|civ| civ.population > 50);
+    *civilizations = survivors;
+    for civ in civilizations.iter_mut() {
+        civ.allies.retain(|id| !collapsed.iter().any(|fallen| fallen.id == *id));
+    }
+    collapsed
+}
+
+// Peaceful, spiritual neighbors become allies. Allies in contact trade: the
+// one behind catches up in tech, and each grows a little, once per tick
+// however many allies it trades with.
+fn resolve_alliances(world: &World3D, civilizations: &mut [Civilization], max_population: u32) {
+    let grid = civ_grid(civilizations);
+    let widest = widest_footprint(civilizations);
+    let mut trading = vec![false; civilizations.len()];
+    for i in 0..civilizations.len() {
+        let center = (civilizations[i].x, civilizations[i].y, civilizations[i].z);
+        let candidates = grid.query_radius(world, center, contact_reach(&civilizations[i], widest));
+        let after = candidates.partition_point(|&k| k <= i);
+        for &j in &candidates[after..] {
+            let (civ_i, civ_j) = (&civilizations[i], &civilizations[j]);
+            if civ_i.gap_to(civ_j, world) >= CONTACT_RANGE {
+                continue;
+            }
+            if !civ_i.is_allied_with(civ_j) {
+                let willing = civ_i.aggression + civ_j.aggression < ALLIANCE_MAX_AGGRESSION
+                    && civ_i.spirituality + civ_j.spirituality > ALLIANCE_MIN_SPIRITUALITY;
+                if !willing {
+                    continue;
+                }
+                let (id_i, id_j) = (civ_i.id, civ_j.id);
+                civilizations[i].allies.push(id_j);
+                civilizations[j].allies.push(id_i);
+            }
+
+            let (tech_i, tech_j) = (civilizations[i].tech_level, civilizations[j].tech_level);
+            let shared = (tech_i - tech_j) * ALLIANCE_TECH_SHARE;
+            if shared > 0.0 {
+                civilizations[j].tech_level += shared;
+            } else {
+                civilizations[i].tech_level -= shared;
+            }
+            trading[i] = true;
+            trading[j] = true;
+        }
+    }
+
+    for (civ, _) in civilizations.iter_mut().zip(trading).filter(|(_, trades)| *trades) {
+        let growth = (civ.population as f32 * ALLIANCE_GROWTH) as u32;
+        civ.population = civ.population.saturating_add(growth).min(max_population);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civilizations_leave_frozen_ground_before_collapsing() {
+        // Frozen soil next to temperate soil, both open to the sky
+        let mut world = World3D::new(2, 1, 2);
+        for (x, temperature) in [(0, -10.0), (1, 20.0)] {
+            world.set_material(x, 0, 0, VoxelMaterial::Soil);
+            world.get_mut(x, 0, 0).temperature = temperature;
+        }
+        let mut civilizations = vec![Civilization::new(0, 0, 0, 0, 200)];
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+
+        let collapsed =
+            step_civilizations(&world, &mut civilizations, 10_000, &mut names, &mut rng);
+        assert!(collapsed.is_empty());
+        let civ = &civilizations[0];
+        assert_eq!((civ.x, civ.y, civ.z), (1, 0, 0));
+        // The journey costs some of the population, not all of it
+        assert!(civ.population < 200 && civ.population > 50, "population {}", civ.population);
+    }
+
+    // A temperate soil surface over rock holding `mineral` ore per voxel
+    fn terrain(mineral: f32) -> World3D {
+        let mut world = World3D::new(5, 5, 4);
+        for y in 0..5 {
+            for x in 0..5 {
+                for z in 0..3 {
+                    world.set_material(x, y, z, VoxelMaterial::Rock);
+                    world.get_mut(x, y, z).mineral = mineral;
+                }
+                world.set_material(x, y, 3, VoxelMaterial::Soil);
+            }
+        }
+        for voxel in &mut world.voxels {
+            voxel.temperature = 20.0;
+        }
+        world
+    }
+
+    // Tech of a civilization after `ticks` ticks of growth and mining
+    fn tech_after(world: &mut World3D, ticks: usize) -> f32 {
+        let mut civilizations = vec![Civilization::new(0, 2, 2, 3, 1000)];
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(1);
+        for _ in 0..ticks {
+            step_civilizations(world, &mut civilizations, 10_000, &mut names, &mut rng);
+            mine_deposits(world, &mut civilizations);
+        }
+        civilizations[0].tech_level
+    }
+
+    #[test]
+    fn ore_speeds_up_tech_until_it_runs_out() {
+        let barren = tech_after(&mut terrain(0.0), 50);
+        let mut rich_world = terrain(1.0);
+        let rich = tech_after(&mut rich_world, 50);
+        assert!(rich > barren + 1.0, "rich {}, barren {}", rich, barren);
+
+        // Once the deposits are mined out, growth is back to the barren rate
+        tech_after(&mut rich_world, 2000);
+        assert!(rich_world.voxels.iter().all(|voxel| voxel.mineral == 0.0));
+        assert_eq!(tech_after(&mut rich_world, 50), barren);
+    }
+
+    #[test]
+    fn huge_victors_keep_their_population() {
+        let world = terrain(0.0);
+        let mut civilizations =
+            vec![Civilization::new(0, 1, 2, 3, u32::MAX - 10), Civilization::new(1, 3, 2, 3, 9000)];
+        for civ in &mut civilizations {
+            civ.aggression = 1.0;
+        }
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(3);
+
+        // War breaks out with a chance of 1 in 10 per tick
+        let mut ticks = 0;
+        while civilizations.len() == 2 && civilizations[1].population >= 9000 {
+            step_civilizations(&world, &mut civilizations, u32::MAX, &mut names, &mut rng);
+            ticks += 1;
+            assert!(ticks < 200, "no war in {} ticks", ticks);
+        }
+        assert_eq!(civilizations[0].population, u32::MAX);
+    }
+
+    #[test]
+    fn peaceful_neighbors_ally_and_share_tech() {
+        let mut world = World3D::new(8, 1, 2);
+        for x in 0..8 {
+            world.set_material(x, 0, 0, VoxelMaterial::Soil);
+            world.get_mut(x, 0, 0).temperature = 20.0;
+        }
+        let mut civilizations =
+            vec![Civilization::new(0, 1, 0, 0, 500), Civilization::new(1, 6, 0, 0, 500)];
+        for civ in &mut civilizations {
+            civ.aggression = 0.1;
+            civ.spirituality = 0.8;
+        }
+        civilizations[0].tech_level = 5.0;
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(2);
+
+        for _ in 0..40 {
+            step_civilizations(&world, &mut civilizations, 10_000, &mut names, &mut rng);
+        }
+        let civ = |id: u32| civilizations.iter().find(|civ| civ.id == id).unwrap();
+        assert!(civ(0).allies.contains(&1) && civ(1).allies.contains(&0));
+        let gap = civ(0).tech_level - civ(1).tech_level;
+        assert!(gap.abs() < 0.5, "tech gap {}", gap);
+    }
+
+    #[test]
+    fn thriving_civilizations_found_colonies_on_empty_land() {
+        // Open temperate plains all around
+        let mut world = World3D::new(17, 17, 1);
+        for y in 0..17 {
+            for x in 0..17 {
+                world.set_material(x, y, 0, VoxelMaterial::Soil);
+                world.get_mut(x, y, 0).temperature = 20.0;
+            }
+        }
+        let mut parent = Civilization::new(0, 8, 8, 0, 5000);
+        parent.tech_level = 4.0;
+        parent.aggression = 0.1;
+        let mut civilizations = vec![parent];
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(4);
+
+        step_civilizations(&world, &mut civilizations, 10_000, &mut names, &mut rng);
+        assert_eq!(civilizations.len(), 2);
+        let (parent, colony) = (&civilizations[0], &civilizations[1]);
+        assert_eq!(colony.parent_id, Some(0));
+        assert!(colony.x.abs_diff(8).max(colony.y.abs_diff(8)) >= COLONY_MIN_RANGE as u32);
+        assert_eq!(parent.colony_cooldown, COLONY_COOLDOWN_TICKS);
+        // The settlers leave: the parent ends below where it started
+        assert!(parent.population < 5000, "parent population {}", parent.population);
+        assert!(colony.population > 1000, "colony population {}", colony.population);
+    }
+
+    #[test]
+    fn civilizations_across_the_seam_are_neighbors_only_with_wrapping() {
+        let mut world = World3D::new(32, 8, 2);
+        let mut west = Civilization::new(0, 0, 4, 0, 100);
+        let mut east = Civilization::new(1, 31, 4, 0, 100);
+        west.aggression = 1.0;
+        east.aggression = 1.0;
+
+        assert_eq!(west.distance_to(&east), 31.0);
+        assert_eq!(west.distance_to_wrapped(&east, world.dimensions()), 1.0);
+
+        assert_eq!(west.distance_in(&east, &world), 31.0);
+        assert!(!west.is_hostile_to(&east, &world));
+        world.wrapping = true;
+        assert_eq!(west.distance_in(&east, &world), 1.0);
+        assert!(west.is_hostile_to(&east, &world));
+    }
+
+    // Id of the loser of the first war between an attacker of 5000 on open
+    // soil and a defender of 4000, optionally holed up in a rock cave
+    fn first_war_loser(cave: bool) -> u32 {
+        let mut world = World3D::new(7, 3, 2);
+        for y in 0..3 {
+            for x in 0..7 {
+                world.set_material(x, y, 0, VoxelMaterial::Soil);
+            }
+        }
+        if cave {
+            for (x, y, z) in world.cube_around((1, 1, 0), 1) {
+                if (x, y, z) != (1, 1, 0) {
+                    world.set_material(x, y, z, VoxelMaterial::Rock);
+                }
+            }
+        }
+        for voxel in &mut world.voxels {
+            voxel.temperature = 20.0;
+        }
+        let mut civilizations =
+            vec![Civilization::new(0, 5, 1, 0, 5000), Civilization::new(1, 1, 1, 0, 4000)];
+        civilizations[0].aggression = 1.0;
+        civilizations[1].aggression = 0.9;
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(1);
+
+        for _ in 0..200 {
+            let before: Vec<u32> = civilizations.iter().map(|civ| civ.population).collect();
+            step_civilizations(&world, &mut civilizations, 100_000, &mut names, &mut rng);
+            // The loser of a war loses two thirds of its population
+            if let Some(loser) = (0..2).find(|&i| civilizations[i].population < before[i] / 2) {
+                return civilizations[loser].id;
+            }
+        }
+        panic!("no war in 200 ticks");
+    }
+
+    #[test]
+    fn defenders_hold_out_in_rough_terrain() {
+        let world = terrain(0.0);
+        // Rock underneath only goes so far; rock all around is worth half again
+        assert!(terrain_defense(&world, 2, 2, 3) < 1.5);
+        let mut cave = World3D::new(3, 3, 3);
+        for idx in 0..cave.voxels.len() {
+            cave.set_material_at(idx, VoxelMaterial::Rock);
+        }
+        assert_eq!(terrain_defense(&cave, 1, 1, 1), 1.5);
+
+        // In the open, the larger attacker wins; from a cave, the defender does
+        assert_eq!(first_war_loser(false), 1);
+        assert_eq!(first_war_loser(true), 0);
+    }
+
+    #[test]
+    fn populations_hovering_at_the_threshold_found_a_single_civilization() {
+        let world = terrain(0.0);
+        let rules = BiologyRules::default();
+        let mut populations = vec![Population::new(0, 2, 2, 3, 0)];
+        let mut civilizations = Vec::new();
+        let mut sites = FoundingSites::new();
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+
+        let mut founded = 0;
+        for tick in 0..300 {
+            // Just above the threshold one tick, just below the next
+            let threshold = rules.civilization_threshold;
+            populations[0].size = if tick % 2 == 0 { threshold + 20 } else { threshold - 20 };
+            let before = civilizations.len();
+            maybe_spawn_civilizations(
+                &world,
+                &populations,
+                &mut civilizations,
+                &rules,
+                &mut sites,
+                &mut names,
+                &mut rng,
+            );
+            founded += civilizations.len() - before;
+            // The civilization falls early, as the simulation records it;
+            // the site stays out of reach past the end of its cooldown
+            if tick == 10 {
+                let fallen = civilizations.remove(0);
+                sites.mark((fallen.x, fallen.y, fallen.z), rules.founding_cooldown);
+            }
+        }
+        assert_eq!(founded, 1);
+        assert!(civilizations.is_empty());
+    }
+
+    #[test]
+    fn founders_take_after_their_homeland() {
+        // Mean aggression and tech of 500 civilizations founded on `voxel`
+        let founded_on = |voxel: &Voxel| {
+            let mut rng = ChaCha12Rng::seed_from_u64(9);
+            let pop = Population::new(3, 0, 0, 0, 600);
+            let (mut aggression, mut tech) = (0.0, 0.0);
+            for id in 0..500 {
+                let civ = Civilization::founded_by(id, &pop, voxel, &mut rng);
+                assert_eq!(civ.founding_species, Some(3));
+                aggression += civ.aggression / 500.0;
+                tech += civ.tech_level / 500.0;
+            }
+            (aggression, tech)
+        };
+        let mut benign = Voxel::soil();
+        benign.temperature = 20.0;
+        let mut harsh = benign.clone();
+        harsh.temperature = 45.0;
+        let mut barren = benign.clone();
+        barren.nutrients = 0.0;
+
+        let (calm, rich) = founded_on(&benign);
+        let (fierce, _) = founded_on(&harsh);
+        let (_, poor) = founded_on(&barren);
+        assert!(fierce > calm + 0.2, "harsh {} vs benign {}", fierce, calm);
+        assert!(rich > poor + 0.2, "rich {} vs barren {}", rich, poor);
+    }
+
+    // Temperature under a civilization of the given size and tech after ten
+    // ticks of urban heat and physics (diffusion and cooling toward 20 °C)
+    fn heat_island(population: u32, tech_level: f32, coefficient: f32) -> f32 {
+        use crate::physics::{apply_physics, PhysicsRules};
+
+        let mut world = terrain(0.0);
+        let mut civ = Civilization::new(0, 2, 2, 3, population);
+        civ.tech_level = tech_level;
+        let civilizations = vec![civ];
+        let rules = PhysicsRules { urban_heat: coefficient, ..Default::default() };
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        for tick in 0..10 {
+            emit_urban_heat(&mut world, &civilizations, coefficient);
+            apply_physics(&mut world, &rules, tick, &mut rng);
+        }
+        world.get(2, 2, 3).temperature
+    }
+
+    #[test]
+    fn large_industrial_civilizations_warm_their_land() {
+        let city = heat_island(50_000, 5.0, 1e-6);
+        let village = heat_island(500, 0.5, 1e-6);
+        assert!(city > 21.0, "city at {} °C", city);
+        assert!(village < 20.1, "village at {} °C", village);
+        // Off by default: the same city leaves its land at the ambient temperature
+        let unheated = heat_island(50_000, 5.0, 0.0);
+        assert!((unheated - 20.0).abs() < 0.1, "unheated city at {} °C", unheated);
+    }
+
+    // Distance between the cultures of two peaceful civilizations standing
+    // `spacing` voxels apart on a long strip of soil, at first and after 200 ticks
+    // (too small to found colonies)
+    fn culture_gap(spacing: u32) -> (f32, f32) {
+        let mut world = World3D::new(40, 1, 2);
+        for x in 0..40 {
+            world.set_material(x, 0, 0, VoxelMaterial::Soil);
+            world.get_mut(x, 0, 0).temperature = 20.0;
+        }
+        let mut civilizations: Vec<Civilization> = [(0, 1), (1, 1 + spacing)]
+            .iter()
+            .map(|&(id, x)| {
+                let mut civ = Civilization::new(id, x, 0, 0, 500);
+                civ.aggression = 0.2;
+                civ.spirituality = 0.1;
+                civ.tech_level = 0.0;
+                civ
+            })
+            .collect();
+        civilizations[0].culture = [0.1; CULTURE_TRAITS];
+        civilizations[1].culture = [0.9; CULTURE_TRAITS];
+        let gap = |civs: &[Civilization]| {
+            civs[0].culture.iter().zip(&civs[1].culture).map(|(a, b)| (a - b).abs()).sum()
+        };
+        let before = gap(&civilizations);
+
+        let mut names = NameGenerator::new(0);
+        let mut rng = ChaCha12Rng::seed_from_u64(4);
+        for _ in 0..200 {
+            step_civilizations(&world, &mut civilizations, 1000, &mut names, &mut rng);
+        }
+        assert_eq!(civilizations.len(), 2);
+        (before, gap(&civilizations))
+    }
+
+    #[test]
+    fn trading_neighbors_share_a_culture_while_distant_ones_keep_theirs() {
+        let (before, trading) = culture_gap(3);
+        assert!(trading < before * 0.1, "from {} to {}", before, trading);
+        let (before, isolated) = culture_gap(36);
+        assert!(isolated > before * 0.8, "from {} to {}", before, isolated);
+    }
+
+    #[test]
+    fn populations_inside_a_footprint_found_no_rival() {
+        // A strip of soil, a civilization at x = 1 and large populations at x = 4 and 9
+        let founded_beside = |population: u32| {
+            let mut world = World3D::new(12, 1, 1);
+            for x in 0..12 {
+                world.set_material(x, 0, 0, VoxelMaterial::Soil);
+                world.get_mut(x, 0, 0).temperature = 20.0;
+            }
+            let populations =
+                vec![Population::new(0, 4, 0, 0, 1000), Population::new(0, 9, 0, 0, 1000)];
+            let mut civilizations = vec![Civilization::new(0, 1, 0, 0, population)];
+            maybe_spawn_civilizations(
+                &world,
+                &populations,
+                &mut civilizations,
+                &BiologyRules::default(),
+                &mut FoundingSites::new(),
+                &mut NameGenerator::new(0),
+                &mut ChaCha12Rng::seed_from_u64(0),
+            );
+            civilizations[1..].iter().map(|civ| civ.x).collect::<Vec<_>>()
+        };
+
+        // A footprint of radius 3 takes in x = 4; one of radius 1 does not
+        assert_eq!(Civilization::new(0, 1, 0, 0, 27_000).footprint_radius(), 3.0);
+        assert_eq!(founded_beside(27_000), [9]);
+        assert_eq!(founded_beside(1000), [4, 9]);
+    }
+
+    #[test]
+    fn name_generators_are_deterministic_and_never_repeat() {
+        let names = |seed: u64| {
+            let mut generator = NameGenerator::new(seed);
+            (0..50).map(|i| generator.next(i % CULTURE_TRAITS)).collect::<Vec<_>>()
+        };
+        let first = names(9);
+        assert_eq!(names(9), first);
+        assert_ne!(names(10), first);
+        assert_eq!(first.iter().collect::<HashSet<_>>().len(), 50, "{:?}", first);
+
+        // Each name comes from its culture's bank
+        let banks = NameGenerator::new(9).banks;
+        for (i, name) in first.iter().enumerate() {
+            let bank = &banks[i % CULTURE_TRAITS % banks.len()];
+            assert!(bank.prefixes.iter().any(|p| name.starts_with(p.as_str())), "{}", name);
+        }
+    }
 }
@@ -0,0 +1,176 @@
+use crate::biology::SpeciesRegistry;
+use crate::god::GodState;
+use crate::physics::PhysicsRules;
+use crate::render::MetricsRecorder;
+use crate::time_sim::{Multiverse, SimulationState, SimulationStateBuilder};
+use crate::world3d::{StrataConfig, ThermalProfile, World3D};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Settings of a whole run, so a simulation can be driven without the binary
+// (e.g. headless batch runs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationConfig {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub ticks: u64,
+    // The same seed replays the same run; None picks one at random
+    pub seed: Option<u64>,
+    pub species: usize,
+    // Populations seeded on habitable ground before the first tick
+    pub seed_populations: usize,
+    // Stop early once the world has not changed for this many ticks
+    pub equilibrium_window: Option<usize>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            width: 64,
+            height: 64,
+            depth: 32,
+            ticks: 1000,
+            seed: None,
+            species: 3,
+            seed_populations: 5,
+            equilibrium_window: Some(10),
+        }
+    }
+}
+
+impl SimulationConfig {
+    // The state before the first tick: world, species, god and life all follow
+    // from `seed`
+    pub fn initial_state(&self, seed: u64) -> SimulationState {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (strata, thermal) = (StrataConfig::default(), ThermalProfile::default());
+        let world = World3D::generate_basic_world_with(
+            self.width,
+            self.height,
+            self.depth,
+            &strata,
+            &thermal,
+            rng.gen(),
+        )
+        .expect("default strata are valid");
+
+        let mut species = SpeciesRegistry::new();
+        for _ in 0..self.species {
+            species.create(&mut rng);
+        }
+        let god_state = GodState::random(&mut rng);
+
+        let mut state = SimulationStateBuilder::new()
+            .world(world)
+            .physics_rules(PhysicsRules::default())
+            .species(species)
+            .god_state(god_state)
+            .seed(rng.gen())
+            .build()
+            .expect("the initial state has no life to validate yet");
+        state.seed_life(self.seed_populations, &mut rng);
+        state
+    }
+}
+
+// Run `config` to completion without any output
+pub fn run_simulation(config: &SimulationConfig) -> Multiverse {
+    run_simulation_with(config, |_| {})
+}
+
+// Same as run_simulation, also recording one MetricRow per state: the initial
+// one, then one per tick, so config.ticks + 1 rows unless equilibrium stops
+// the run early
+pub fn run_simulation_recorded(config: &SimulationConfig) -> (Multiverse, MetricsRecorder) {
+    let mut recorder = MetricsRecorder::new();
+    let multiverse = run_simulation_with(config, |multiverse| {
+        if let Some(state) = multiverse.current_state() {
+            recorder.record(state);
+        }
+    });
+    (multiverse, recorder)
+}
+
+// Run `config` to completion (or equilibrium), calling `observe` with the new
+// multiverse, then again after every tick
+pub fn run_simulation_with(
+    config: &SimulationConfig,
+    mut observe: impl FnMut(&Multiverse),
+) -> Multiverse {
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut multiverse = Multiverse::new(config.initial_state(seed));
+    observe(&multiverse);
+
+    for _ in 0..config.ticks {
+        // No memory budget is set, so storing the new state cannot fail
+        multiverse.step().expect("the multiverse has no memory budget");
+        observe(&multiverse);
+
+        if let Some(window) = config.equilibrium_window {
+            if multiverse.is_at_equilibrium(window) {
+                break;
+            }
+        }
+    }
+    multiverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiny_configs_run_to_completion() {
+        let config = SimulationConfig {
+            width: 8,
+            height: 8,
+            depth: 8,
+            ticks: 10,
+            seed: Some(3),
+            equilibrium_window: None,
+            ..SimulationConfig::default()
+        };
+        let multiverse = run_simulation(&config);
+
+        assert_eq!(multiverse.get_tick(), 10);
+        assert_eq!(multiverse.timeline_summary().len(), 1);
+        // The initial state plus one per tick
+        assert_eq!(multiverse.current_timeline().len(), 11);
+        let state = multiverse.current_state().unwrap();
+        assert_eq!((state.world.width, state.world.height, state.world.depth), (8, 8, 8));
+        // Speciation only ever adds to the three initial species
+        assert!(state.species.len() >= 3);
+        assert!(multiverse.replay_to(0).unwrap().same_contents(&config.initial_state(3)));
+    }
+
+    #[test]
+    fn recorded_runs_have_a_row_per_state() {
+        let config = SimulationConfig {
+            width: 8,
+            height: 8,
+            depth: 8,
+            ticks: 10,
+            seed: Some(5),
+            equilibrium_window: None,
+            ..SimulationConfig::default()
+        };
+        let (_, recorder) = run_simulation_recorded(&config);
+        // The initial state, then one row per tick
+        let ticks: Vec<u64> = recorder.rows().iter().map(|row| row.tick).collect();
+        assert_eq!(ticks, (0..=10).collect::<Vec<_>>());
+
+        let path = std::env::temp_dir().join(format!("metrics_{}.csv", std::process::id()));
+        recorder.to_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "tick,total_biomass,civ_count,civ_population,avg_tech,avg_temperature,\
+             climate_stability,surface_fertility"
+        );
+        assert_eq!(lines.len(), 12);
+        assert!(lines[11].starts_with("10,"));
+    }
+}
@@ -0,0 +1,32 @@
+use crate::god::{ActionEffect, ActionError, GodAction};
+
+// Notable things that happened during a tick
+#[derive(Debug, Clone)]
+pub enum SimEvent {
+    GodIntervention {
+        god: usize,
+        action: GodAction,
+        reason: String,
+        outcome: Result<ActionEffect, ActionError>,
+    },
+    // A population referenced a species that does not exist and was removed
+    MissingSpecies {
+        species_id: u32,
+        population_lost: u32,
+    },
+    // Part of a population at (x, y, z) branched off into a new species
+    Speciation {
+        parent_id: u32,
+        species_id: u32,
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+    // The sea reached a new level, flooding voxels and drowning some of the
+    // life on them
+    SeaLevelRise {
+        sea_level: u32,
+        voxels_flooded: u32,
+        population_lost: u32,
+    },
+}
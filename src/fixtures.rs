@@ -0,0 +1,125 @@
+// Golden-state fixtures: canonical seeded scenarios whose final state is
+// pinned, so any change to physics, biology or civilizations that alters a
+// seeded run shows up as a failing test. After an intended behavior change,
+// regenerate the golden values with
+//   GOLDEN_REGENERATE=1 cargo test --lib golden -- --nocapture
+// and paste the printed values in place of the old ones.
+
+use crate::config::SimulationConfig;
+use crate::render::collect_metrics;
+use crate::time_sim::{Multiverse, SimulationState};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// A seeded run: a cubic world of `size` voxels a side, as SimulationConfig
+// builds it, with the given (x, y, population) civilizations founded on the
+// surface, simulated `ticks` ticks
+pub(crate) struct Scenario {
+    pub name: &'static str,
+    pub size: u32,
+    pub seed: u64,
+    pub civilizations: &'static [(u32, u32, u32)],
+    pub ticks: u64,
+}
+
+// What a scenario is pinned to: the final content hash and a few headline
+// metrics, which say more than the hash about what changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Golden {
+    pub content_hash: u64,
+    pub civ_count: usize,
+    pub civ_population: u64,
+    pub population_count: usize,
+    pub total_biomass: u64,
+}
+
+impl Scenario {
+    // The scenario before its first tick
+    pub fn initial_state(&self) -> SimulationState {
+        let config = SimulationConfig {
+            width: self.size,
+            height: self.size,
+            depth: self.size,
+            ..Default::default()
+        };
+        let mut state = config.initial_state(self.seed);
+        let specs: Vec<_> = self.civilizations.iter().map(|&(x, y, pop)| (x, y, 0, pop)).collect();
+        state
+            .seed_civilizations(&specs, &mut StdRng::seed_from_u64(self.seed))
+            .expect("scenario civilizations stand on habitable ground");
+        state
+    }
+
+    pub fn run(&self) -> Golden {
+        let mut multiverse = Multiverse::new(self.initial_state());
+        for _ in 0..self.ticks {
+            multiverse.step().expect("no memory budget is set");
+        }
+        let state = multiverse.current_state().expect("a run always has a current state");
+        let metrics = collect_metrics(state);
+        Golden {
+            content_hash: state.content_hash(),
+            civ_count: metrics.civ_count,
+            civ_population: metrics.civ_population,
+            population_count: metrics.population_count,
+            total_biomass: metrics.total_biomass,
+        }
+    }
+
+    // Fails unless the run reproduces `golden`; prints the values to paste
+    // instead when GOLDEN_REGENERATE is set
+    pub fn check(&self, golden: Golden) {
+        let actual = self.run();
+        if std::env::var_os("GOLDEN_REGENERATE").is_some() {
+            println!(
+                "{}: content_hash: {:#018x}, civ_count: {}, civ_population: {}, \
+                 population_count: {}, total_biomass: {}",
+                self.name,
+                actual.content_hash,
+                actual.civ_count,
+                actual.civ_population,
+                actual.population_count,
+                actual.total_biomass
+            );
+            return;
+        }
+        assert_eq!(
+            actual, golden,
+            "scenario {} drifted from its golden state (regenerate it if intended)",
+            self.name
+        );
+    }
+}
+
+// The canonical scenario: a 16³ world, seed 42, two rival civilizations, 100 ticks
+pub(crate) const CANONICAL: Scenario = Scenario {
+    name: "canonical 16³",
+    size: 16,
+    seed: 42,
+    civilizations: &[(4, 4, 2000), (11, 11, 2000)],
+    ticks: 100,
+};
+
+// The fixed-point heat diffusion of `deterministic-physics` gives slightly
+// different temperatures, hence its own golden state
+#[cfg(not(feature = "deterministic-physics"))]
+const CANONICAL_GOLDEN: Golden = Golden {
+    content_hash: 0x46d1a8509cad2fe2,
+    civ_count: 2,
+    civ_population: 21442,
+    population_count: 12,
+    total_biomass: 157,
+};
+#[cfg(feature = "deterministic-physics")]
+const CANONICAL_GOLDEN: Golden = Golden {
+    content_hash: 0xa51c8ef38cc6ed4f,
+    civ_count: 2,
+    civ_population: 21442,
+    population_count: 12,
+    total_biomass: 157,
+};
+
+#[test]
+fn golden_canonical_scenario() {
+    CANONICAL.check(CANONICAL_GOLDEN);
+}
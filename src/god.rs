@@ -1,106 +1,493 @@
-use crate::civilization::Civilization;
+use crate::biology::Population;
+use crate::civilization::{civ_grid, contact_reach, widest_footprint};
+use crate::events::SimEvent;
+use crate::render::collect_metrics;
 use crate::time_sim::SimulationState;
+use crate::world3d::{VoxelMaterial, World3D};
 use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GodState {
     pub curiosity: f32,
     pub benevolence: f32,
     pub cruelty: f32,
     pub boredom: f32,
+    // Multiplier on the chance of acting each tick (0 = never intervenes)
+    pub activity: f32,
 }
 
 impl Default for GodState {
     fn default() -> Self {
-        let mut rng = rand::thread_rng();
+        Self::random(&mut rand::thread_rng())
+    }
+}
+
+impl GodState {
+    // A god with a random temperament, drawn from `rng`
+    pub fn random(rng: &mut impl Rng) -> Self {
         Self {
             curiosity: rng.gen_range(0.3..0.8),
             benevolence: rng.gen_range(0.4..0.7),
             cruelty: rng.gen_range(0.1..0.4),
             boredom: 0.0,
+            activity: 1.0,
+        }
+    }
+
+    // Fixed temperaments, for reproducible scenarios
+    pub fn benevolent() -> Self {
+        Self::builder().curiosity(0.5).benevolence(0.9).cruelty(0.05).build()
+    }
+
+    pub fn cruel() -> Self {
+        Self::builder().curiosity(0.5).benevolence(0.2).cruelty(0.9).build()
+    }
+
+    pub fn curious() -> Self {
+        Self::builder().curiosity(0.95).benevolence(0.5).cruelty(0.2).build()
+    }
+
+    pub fn builder() -> GodStateBuilder {
+        GodStateBuilder::default()
+    }
+}
+
+// Explicit construction of a GodState; unset traits sit in the middle of the
+// ranges GodState::random draws from, and every trait is clamped on build
+#[derive(Debug, Clone)]
+pub struct GodStateBuilder {
+    curiosity: f32,
+    benevolence: f32,
+    cruelty: f32,
+    boredom: f32,
+    activity: f32,
+}
+
+impl Default for GodStateBuilder {
+    fn default() -> Self {
+        Self {
+            curiosity: 0.55,
+            benevolence: 0.55,
+            cruelty: 0.25,
+            boredom: 0.0,
+            activity: 1.0,
         }
     }
 }
 
+impl GodStateBuilder {
+    pub fn curiosity(mut self, curiosity: f32) -> Self {
+        self.curiosity = curiosity;
+        self
+    }
+
+    pub fn benevolence(mut self, benevolence: f32) -> Self {
+        self.benevolence = benevolence;
+        self
+    }
+
+    pub fn cruelty(mut self, cruelty: f32) -> Self {
+        self.cruelty = cruelty;
+        self
+    }
+
+    pub fn boredom(mut self, boredom: f32) -> Self {
+        self.boredom = boredom;
+        self
+    }
+
+    pub fn activity(mut self, activity: f32) -> Self {
+        self.activity = activity;
+        self
+    }
+
+    // Traits are clamped to 0..=1; activity only has to be non-negative
+    pub fn build(self) -> GodState {
+        GodState {
+            curiosity: self.curiosity.clamp(0.0, 1.0),
+            benevolence: self.benevolence.clamp(0.0, 1.0),
+            cruelty: self.cruelty.clamp(0.0, 1.0),
+            boredom: self.boredom.clamp(0.0, 1.0),
+            activity: self.activity.max(0.0),
+        }
+    }
+}
+
+// Side of the square columns of the world the gods reason about
+pub const REGION_SIZE: u32 = 8;
+
+// Radius of catastrophes that do not choose one (e.g. older scripts)
+pub const DEFAULT_CATASTROPHE_RADIUS: f32 = 5.0;
+
+// Spirituality a civilization needs before a benevolent god answers its prayers
+pub const PRAYER_SPIRITUALITY: f32 = 0.7;
+
+// What lives in one REGION_SIZE x REGION_SIZE block of columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionSummary {
+    pub origin: (u32, u32),
+    pub civ_count: u32,
+    pub civ_population: u64,
+    pub biomass: u64,
+    // Population-weighted center of the region's civilizations
+    pub civ_center: Option<(u32, u32, u32)>,
+}
+
 pub struct WorldSummary {
     pub num_civilizations: u32,
     pub avg_tech_level: f32,
     pub total_biomass: u32,
     pub wars_ongoing: u32,
     pub climate_stability: f32,
+    pub world_dimensions: (u32, u32, u32),
+    pub civ_ids: Vec<u32>,
+    // Registered species, living or extinct
+    pub species_ids: Vec<u32>,
+    // Civilization with the smallest population
+    pub most_fragile_civ: Option<u32>,
+    // Most spiritual civilization, if any is at least PRAYER_SPIRITUALITY
+    pub most_devout_civ: Option<u32>,
+    // Only regions holding at least one civilization or population
+    pub regions: Vec<RegionSummary>,
 }
 
-#[derive(Debug, Clone)]
+impl WorldSummary {
+    // The region with the largest civilized population
+    pub fn densest_civ_region(&self) -> Option<&RegionSummary> {
+        self.regions
+            .iter()
+            .filter(|r| r.civ_count > 0)
+            .max_by_key(|r| r.civ_population)
+    }
+}
+
+// A region being summarized, with the weighted x/y/z sums and total weight of its civs
+type RegionAccumulator = (RegionSummary, [u64; 4]);
+
+fn region_at(
+    regions: &mut BTreeMap<(u32, u32), RegionAccumulator>,
+    x: u32,
+    y: u32,
+) -> &mut RegionAccumulator {
+    let key = (x / REGION_SIZE, y / REGION_SIZE);
+    regions.entry(key).or_insert_with(|| {
+        let summary = RegionSummary {
+            origin: (key.0 * REGION_SIZE, key.1 * REGION_SIZE),
+            civ_count: 0,
+            civ_population: 0,
+            biomass: 0,
+            civ_center: None,
+        };
+        (summary, [0; 4])
+    })
+}
+
+fn summarize_regions(state: &SimulationState) -> Vec<RegionSummary> {
+    let mut regions = BTreeMap::new();
+
+    for civ in &state.civilizations {
+        let (region, sums) = region_at(&mut regions, civ.x, civ.y);
+        region.civ_count += 1;
+        region.civ_population += civ.population as u64;
+        let weight = civ.population.max(1) as u64;
+        sums[0] += civ.x as u64 * weight;
+        sums[1] += civ.y as u64 * weight;
+        sums[2] += civ.z as u64 * weight;
+        sums[3] += weight;
+    }
+    for pop in &state.populations {
+        region_at(&mut regions, pop.x, pop.y).0.biomass += pop.size as u64;
+    }
+
+    regions
+        .into_values()
+        .map(|(mut region, sums)| {
+            if sums[3] > 0 {
+                let center = |sum: u64| (sum / sums[3]) as u32;
+                region.civ_center = Some((center(sums[0]), center(sums[1]), center(sums[2])));
+            }
+            region
+        })
+        .collect()
+}
+
+// Changes to the physics rules; rules left at None are not touched
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PhysicsRulesDelta {
     pub heat_diffusion_delta: f32,
     pub cooling_rate_delta: f32,
+    pub gravity_enabled: Option<bool>,
+    pub storms_enabled: Option<bool>,
+    pub rock_melting_point_delta: Option<f32>,
+    pub solar_heating_delta: Option<f32>,
+    pub urban_heat_delta: Option<f32>,
+    pub evaporation_point_delta: Option<f32>,
+    pub evaporation_rate_delta: Option<f32>,
+    pub lightning_chance_delta: Option<f32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GodAction {
     ChangePhysics(PhysicsRulesDelta),
-    SpawnCatastrophe { x: u32, y: u32, z: u32, intensity: f32 },
+    // Damage fades linearly from the epicenter out to `radius` voxels; what it
+    // does depends on the kind (older logs without one are meteors)
+    SpawnCatastrophe {
+        #[serde(default)]
+        kind: CatastropheKind,
+        x: u32,
+        y: u32,
+        z: u32,
+        intensity: f32,
+        radius: f32,
+    },
     BlessCivilization { civ_id: u32, tech_boost: f32 },
+    // Reward a devout civilization; its faith grows stronger
+    AnswerPrayer { civ_id: u32 },
+    // Bring life back at the habitable voxel nearest the target; a species id
+    // that is not registered gets a new species adapted to the spot
+    Resurrect { x: u32, y: u32, z: u32, species_id: u32, size: u32 },
+    // Raise the world's sea level, flooding the new layers at once
+    RaiseSeaLevel { layers: u32 },
     None,
 }
 
-pub fn build_world_summary(state: &SimulationState) -> WorldSummary {
-    let num_civilizations = state.civilizations.len() as u32;
+impl GodAction {
+    // Name of the variant, e.g. to tally actions by kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GodAction::ChangePhysics(_) => "ChangePhysics",
+            GodAction::SpawnCatastrophe { .. } => "SpawnCatastrophe",
+            GodAction::BlessCivilization { .. } => "BlessCivilization",
+            GodAction::AnswerPrayer { .. } => "AnswerPrayer",
+            GodAction::Resurrect { .. } => "Resurrect",
+            GodAction::RaiseSeaLevel { .. } => "RaiseSeaLevel",
+            GodAction::None => "None",
+        }
+    }
+}
 
-    let avg_tech_level = if num_civilizations > 0 {
-        state
-            .civilizations
-            .iter()
-            .map(|c| c.tech_level)
-            .sum::<f32>()
-            / num_civilizations as f32
-    } else {
-        0.0
-    };
+// Interventions with the tick they happened on, as a persistent list: a state
+// and its clones share their common history, so a timeline storing a state
+// per tick costs one entry per action rather than a copy of the whole log
+#[derive(Clone, Default)]
+pub struct ActionLog {
+    last: Option<Arc<LogEntry>>,
+    len: usize,
+}
+
+struct LogEntry {
+    tick: u64,
+    action: GodAction,
+    previous: Option<Arc<LogEntry>>,
+}
 
-    let total_biomass: u32 = state.populations.iter().map(|p| p.size).sum();
+impl ActionLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, tick: u64, action: GodAction) {
+        let previous = self.last.take();
+        self.last = Some(Arc::new(LogEntry { tick, action, previous }));
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Newest first
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &GodAction)> + '_ {
+        std::iter::successors(self.last.as_deref(), |entry| entry.previous.as_deref())
+            .map(|entry| (entry.tick, &entry.action))
+    }
+
+    // Oldest first
+    pub fn to_vec(&self) -> Vec<(u64, GodAction)> {
+        let mut entries: Vec<_> =
+            self.iter().map(|(tick, action)| (tick, action.clone())).collect();
+        entries.reverse();
+        entries
+    }
+}
+
+// Unlinked one entry at a time: dropping a long log recursively could
+// overflow the stack
+impl Drop for ActionLog {
+    fn drop(&mut self) {
+        let mut next = self.last.take();
+        while let Some(entry) = next {
+            next = match Arc::try_unwrap(entry) {
+                Ok(mut entry) => entry.previous.take(),
+                // Still shared with another state
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+// Saved as a plain list, oldest first
+impl Serialize for ActionLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_vec().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ActionLog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut log = ActionLog::new();
+        for (tick, action) in Vec::<(u64, GodAction)>::deserialize(deserializer)? {
+            log.push(tick, action);
+        }
+        Ok(log)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CatastropheKind {
+    // Kills by distance, heats the area and leaves a crater of lava and rock
+    #[default]
+    Meteor,
+    // Kills a share of every population and civilization in reach; no
+    // terrain change
+    Plague,
+    // Turns air and soil in reach, up to the epicenter's level, into water
+    Flood,
+}
+
+impl CatastropheKind {
+    pub const ALL: [CatastropheKind; 3] =
+        [CatastropheKind::Meteor, CatastropheKind::Plague, CatastropheKind::Flood];
+
+    // Lowercase name, as written in scripts
+    pub fn name(&self) -> &'static str {
+        match self {
+            CatastropheKind::Meteor => "meteor",
+            CatastropheKind::Plague => "plague",
+            CatastropheKind::Flood => "flood",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+// Share of the catastrophe radius a meteor crater spans; its inner half is lava
+const CRATER_FRACTION: f32 = 0.3;
+
+// Intensity at which a plague kills everything at its epicenter
+const PLAGUE_LETHAL_INTENSITY: f32 = 50.0;
+
+// What an applied action actually changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionEffect {
+    pub physics_changed: bool,
+    pub voxels_modified: u32,
+    pub population_killed: u32,
+    pub populations_affected: u32,
+    pub civs_boosted: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionError {
+    UnknownCivilization(u32),
+    OutOfBounds { x: u32, y: u32, z: u32 },
+    Uninhabitable,
+    // A new species was needed but every slot holds a living one
+    SpeciesLimit,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::UnknownCivilization(id) => write!(f, "no civilization with id {}", id),
+            ActionError::OutOfBounds { x, y, z } => {
+                write!(f, "target ({},{},{}) is outside the world", x, y, z)
+            }
+            ActionError::Uninhabitable => write!(f, "no habitable voxel is left in the world"),
+            ActionError::SpeciesLimit => write!(f, "the species limit is reached"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+pub fn build_world_summary(state: &SimulationState) -> WorldSummary {
+    let metrics = collect_metrics(state);
 
     // Count "wars" as pairs of nearby aggressive civilizations
+    let civs = &state.civilizations;
+    let grid = civ_grid(civs);
+    let widest = widest_footprint(civs);
     let mut wars_ongoing = 0;
-    for i in 0..state.civilizations.len() {
-        for j in (i + 1)..state.civilizations.len() {
-            let distance = state.civilizations[i].distance_to(&state.civilizations[j]);
-            if distance < 10.0
-                && state.civilizations[i].aggression > 0.6
-                && state.civilizations[j].aggression > 0.6
-            {
+    for (i, civ) in civs.iter().enumerate() {
+        let reach = contact_reach(civ, widest);
+        for j in grid.query_radius(&state.world, (civ.x, civ.y, civ.z), reach) {
+            if j > i && civ.is_hostile_to(&civs[j], &state.world) {
                 wars_ongoing += 1;
             }
         }
     }
 
-    // Simple climate stability: average temperature deviation
-    let temps: Vec<f32> = state
-        .world
-        .voxels
-        .iter()
-        .map(|v| v.temperature)
-        .collect();
-    let avg_temp = temps.iter().sum::<f32>() / temps.len() as f32;
-    let variance = temps
-        .iter()
-        .map(|t| (t - avg_temp).powi(2))
-        .sum::<f32>()
-        / temps.len() as f32;
-    let climate_stability = 1.0 / (1.0 + variance / 100.0);
-
     WorldSummary {
-        num_civilizations,
-        avg_tech_level,
-        total_biomass,
+        num_civilizations: metrics.civ_count as u32,
+        avg_tech_level: metrics.avg_tech,
+        total_biomass: metrics.total_biomass.min(u32::MAX as u64) as u32,
         wars_ongoing,
-        climate_stability,
+        climate_stability: metrics.climate_stability,
+        world_dimensions: state.world.dimensions(),
+        civ_ids: state.civilizations.iter().map(|c| c.id).collect(),
+        species_ids: state.species.iter().map(|s| s.id).collect(),
+        most_fragile_civ: state.civilizations.iter().min_by_key(|c| c.population).map(|c| c.id),
+        most_devout_civ: state
+            .civilizations
+            .iter()
+            .filter(|c| c.spirituality >= PRAYER_SPIRITUALITY)
+            .max_by(|a, b| a.spirituality.total_cmp(&b.spirituality))
+            .map(|c| c.id),
+        regions: summarize_regions(state),
     }
 }
 
 pub fn choose_action(god: &mut GodState, summary: &WorldSummary) -> GodAction {
-    let mut rng = rand::thread_rng();
+    choose_action_with_reason(god, summary, &mut rand::thread_rng()).0
+}
+
+// Crueler gods hit harder: sampled intensities are scaled from 0.5x to 1.5x
+fn catastrophe_intensity(god: &GodState, base: std::ops::Range<f32>, rng: &mut impl Rng) -> f32 {
+    rng.gen_range(base) * (0.5 + god.cruelty)
+}
+
+// Stronger (and so crueler) catastrophes reach further
+fn catastrophe_radius(intensity: f32) -> f32 {
+    2.0 + intensity / 5.0
+}
+
+// A random voxel inside the world
+fn random_target(summary: &WorldSummary, rng: &mut impl Rng) -> (u32, u32, u32) {
+    let (width, height, depth) = summary.world_dimensions;
+    (
+        rng.gen_range(0..width.max(1)),
+        rng.gen_range(0..height.max(1)),
+        rng.gen_range(0..depth.max(1)),
+    )
+}
 
+// Same as choose_action, plus a short human-readable reason for the decision
+pub fn choose_action_with_reason(
+    god: &mut GodState,
+    summary: &WorldSummary,
+    rng: &mut impl Rng,
+) -> (GodAction, String) {
     // Update god's emotional state based on world summary
     if summary.num_civilizations == 0 {
         god.boredom += 0.1;
@@ -125,102 +512,872 @@ pub fn choose_action(god: &mut GodState, summary: &WorldSummary) -> GodAction {
 
     // Decide action based on emotional state
     let roll = rng.gen::<f32>();
+    let chance = |base: f32| (base * god.activity.max(0.0)).clamp(0.0, 1.0);
 
-    if god.boredom > 0.7 && summary.num_civilizations > 0 {
+    if summary.total_biomass == 0 && god.benevolence > 0.6 && roll < chance(0.3) {
+        // Benevolent and the world is dead? Give life a second chance
+        let (x, y, z) = random_target(summary, rng);
+        let species_id = if summary.species_ids.is_empty() {
+            0
+        } else {
+            summary.species_ids[rng.gen_range(0..summary.species_ids.len())]
+        };
+        let action = GodAction::Resurrect {
+            x,
+            y,
+            z,
+            species_id,
+            size: rng.gen_range(50..200),
+        };
+        (action, "benevolent, resurrecting life in a dead world".to_string())
+    } else if god.boredom > 0.7 && !summary.civ_ids.is_empty() && roll < chance(1.0) {
         // Bored? Do something interesting
         if rng.gen::<f32>() < 0.5 {
-            GodAction::BlessCivilization {
-                civ_id: rng.gen_range(0..summary.num_civilizations),
+            let civ_id = summary.civ_ids[rng.gen_range(0..summary.civ_ids.len())];
+            let action = GodAction::BlessCivilization {
+                civ_id,
                 tech_boost: rng.gen_range(0.5..2.0),
-            }
+            };
+            let reason = format!("bored, blessing civilization #{} for entertainment", civ_id);
+            (action, reason)
         } else {
-            GodAction::SpawnCatastrophe {
-                x: rng.gen_range(0..64),
-                y: rng.gen_range(0..64),
-                z: rng.gen_range(0..32),
-                intensity: rng.gen_range(5.0..20.0),
-            }
-        }
-    } else if god.cruelty > 0.6 && summary.wars_ongoing > 1 && roll < 0.15 {
-        // Cruel and wars are happening? Make it worse
-        GodAction::SpawnCatastrophe {
-            x: rng.gen_range(0..64),
-            y: rng.gen_range(0..64),
-            z: rng.gen_range(0..32),
-            intensity: rng.gen_range(10.0..30.0),
-        }
-    } else if god.benevolence > 0.7 && summary.num_civilizations > 0 && roll < 0.1 {
-        // Benevolent? Help a civilization
-        GodAction::BlessCivilization {
-            civ_id: rng.gen_range(0..summary.num_civilizations),
-            tech_boost: rng.gen_range(1.0..3.0),
+            // A bored god is not picky about the kind of disaster
+            let kind = CatastropheKind::ALL[rng.gen_range(0..CatastropheKind::ALL.len())];
+            let intensity = catastrophe_intensity(god, 5.0..20.0, rng);
+            let (x, y, z) = random_target(summary, rng);
+            let action = GodAction::SpawnCatastrophe {
+                kind,
+                x,
+                y,
+                z,
+                intensity,
+                radius: catastrophe_radius(intensity),
+            };
+            let reason = format!(
+                "bored, unleashing a {} of intensity {:.1} (cruelty {:.2})",
+                kind.name(),
+                intensity,
+                god.cruelty
+            );
+            (action, reason)
         }
-    } else if god.curiosity > 0.8 && roll < 0.05 {
+    } else if god.cruelty > 0.6 && summary.wars_ongoing > 1 && roll < chance(0.15) {
+        // Cruel and wars are happening? Strike the densest civilized region,
+        // the cruelest gods with a plague that spares the land but not the people
+        let kind = if rng.gen::<f32>() < god.cruelty - 0.5 {
+            CatastropheKind::Plague
+        } else {
+            CatastropheKind::Meteor
+        };
+        let intensity = catastrophe_intensity(god, 10.0..30.0, rng);
+        let (x, y, z) = summary
+            .densest_civ_region()
+            .and_then(|region| region.civ_center)
+            .unwrap_or_else(|| random_target(summary, rng));
+        let action = GodAction::SpawnCatastrophe {
+            kind,
+            x,
+            y,
+            z,
+            intensity,
+            radius: catastrophe_radius(intensity),
+        };
+        let reason = format!(
+            "cruel amid {} wars, {} of intensity {:.1} on ({},{},{}) (cruelty {:.2})",
+            summary.wars_ongoing,
+            kind.name(),
+            intensity,
+            x,
+            y,
+            z,
+            god.cruelty
+        );
+        (action, reason)
+    } else if let (true, Some(civ_id)) =
+        (god.benevolence > 0.6 && roll < chance(0.05), summary.most_devout_civ)
+    {
+        // Benevolent? Answer the prayers of the most devout civilization
+        let action = GodAction::AnswerPrayer { civ_id };
+        let reason = format!("benevolent, answering the prayers of civilization #{}", civ_id);
+        (action, reason)
+    } else if let (true, Some(civ_id)) =
+        (god.benevolence > 0.7 && roll < chance(0.1), summary.most_fragile_civ)
+    {
+        // Benevolent? Help the most fragile civilization
+        let action = GodAction::BlessCivilization {
+            civ_id,
+            tech_boost: rng.gen_range(1.0..3.0),
+        };
+        let reason = format!("benevolent, helping the fragile civilization #{}", civ_id);
+        (action, reason)
+    } else if god.curiosity > 0.8 && roll < chance(0.05) {
         // Curious? Tweak the physics
-        GodAction::ChangePhysics(PhysicsRulesDelta {
+        let action = GodAction::ChangePhysics(PhysicsRulesDelta {
             heat_diffusion_delta: rng.gen_range(-0.05..0.05),
             cooling_rate_delta: rng.gen_range(-0.01..0.01),
-        })
+            solar_heating_delta: Some(rng.gen_range(-0.05..0.05)),
+            evaporation_rate_delta: Some(rng.gen_range(-0.001..0.001)),
+            ..Default::default()
+        });
+        (action, "curious, experimenting with physics".to_string())
     } else {
-        GodAction::None
+        (GodAction::None, "watching".to_string())
     }
 }
 
-pub fn apply_action(state: &mut SimulationState, action: GodAction) {
+pub fn apply_action(
+    state: &mut SimulationState,
+    action: GodAction,
+) -> Result<ActionEffect, ActionError> {
+    let mut effect = ActionEffect::default();
+
     match action {
         GodAction::ChangePhysics(delta) => {
-            state.physics_rules.heat_diffusion_rate =
-                (state.physics_rules.heat_diffusion_rate + delta.heat_diffusion_delta)
-                    .clamp(0.0, 1.0);
-            state.physics_rules.cooling_rate =
-                (state.physics_rules.cooling_rate + delta.cooling_rate_delta).clamp(0.0, 0.1);
-        }
-        GodAction::SpawnCatastrophe { x, y, z, intensity } => {
-            // Raise temperature in a region
-            for dz in 0..3 {
-                for dy in 0..3 {
-                    for dx in 0..3 {
-                        let nx = x + dx;
-                        let ny = y + dy;
-                        let nz = z + dz;
-
-                        if nx < state.world.width
-                            && ny < state.world.height
-                            && nz < state.world.depth
-                        {
-                            let voxel = state.world.get_mut(nx, ny, nz);
-                            voxel.temperature += intensity;
-                        }
-                    }
+            let rules = Arc::make_mut(&mut state.physics_rules);
+            rules.heat_diffusion_rate =
+                (rules.heat_diffusion_rate + delta.heat_diffusion_delta).clamp(0.0, 1.0);
+            rules.cooling_rate = (rules.cooling_rate + delta.cooling_rate_delta).clamp(0.0, 0.1);
+            if let Some(enabled) = delta.gravity_enabled {
+                rules.gravity_enabled = enabled;
+            }
+            if let Some(enabled) = delta.storms_enabled {
+                rules.storms_enabled = enabled;
+            }
+            let nudge = |value: &mut f32, delta: Option<f32>, min: f32, max: f32| {
+                if let Some(delta) = delta {
+                    *value = (*value + delta).clamp(min, max);
                 }
+            };
+            nudge(&mut rules.rock_melting_point, delta.rock_melting_point_delta, 0.0, f32::MAX);
+            nudge(&mut rules.solar_heating, delta.solar_heating_delta, 0.0, f32::MAX);
+            nudge(&mut rules.urban_heat, delta.urban_heat_delta, 0.0, f32::MAX);
+            nudge(&mut rules.evaporation_point, delta.evaporation_point_delta, f32::MIN, f32::MAX);
+            nudge(&mut rules.evaporation_rate, delta.evaporation_rate_delta, 0.0, 1.0);
+            nudge(&mut rules.lightning_chance, delta.lightning_chance_delta, 0.0, 1.0);
+            effect.physics_changed = true;
+        }
+        GodAction::SpawnCatastrophe { kind, x, y, z, intensity, radius } => {
+            if x >= state.world.width || y >= state.world.height || z >= state.world.depth {
+                return Err(ActionError::OutOfBounds { x, y, z });
             }
 
-            // Kill nearby populations
-            state.populations.retain_mut(|pop| {
-                let dist = ((pop.x as i32 - x as i32).pow(2)
-                    + (pop.y as i32 - y as i32).pow(2)
-                    + (pop.z as i32 - z as i32).pow(2)) as f32;
-                let dist = dist.sqrt();
+            // Craters and floods keep the material histogram up to date
+            state.world.ensure_material_counts();
 
-                if dist < 5.0 {
-                    pop.size = pop.size.saturating_sub((intensity * 10.0) as u32);
+            // Full strength at the epicenter, fading to 1 / (radius + 1) at the edge
+            let radius = radius.max(0.0);
+            let world = &state.world;
+            let falloff = |target: (u32, u32, u32)| {
+                let dist = world.distance((x, y, z), target);
+                (dist <= radius).then(|| 1.0 - dist / (radius + 1.0))
+            };
+            let in_reach: Vec<(usize, f32, f32)> = world
+                .cube_around((x, y, z), radius as u32)
+                .into_iter()
+                .filter_map(|target| {
+                    let (nx, ny, nz) = target;
+                    let dist = world.distance((x, y, z), target);
+                    falloff(target).map(|strength| (world.index(nx, ny, nz), dist, strength))
+                })
+                .collect();
+
+            match kind {
+                CatastropheKind::Meteor => {
+                    // Kill nearby populations
+                    state.populations.retain_mut(|pop| {
+                        if let Some(strength) = falloff((pop.x, pop.y, pop.z)) {
+                            let before = pop.size;
+                            let killed = (intensity * 10.0 * strength) as u32;
+                            pop.size = pop.size.saturating_sub(killed);
+                            effect.population_killed =
+                                effect.population_killed.saturating_add(before - pop.size);
+                            effect.populations_affected += 1;
+                        }
+                        pop.size > 0
+                    });
+
+                    // Raise temperature around the epicenter and dig the crater
+                    let crater = radius * CRATER_FRACTION;
+                    for &(idx, dist, strength) in &in_reach {
+                        state.world.voxels[idx].temperature += intensity * strength;
+                        let material = state.world.voxels[idx].material;
+                        if dist <= crater && material != VoxelMaterial::Air {
+                            let molten = dist <= crater / 2.0;
+                            let rubble =
+                                if molten { VoxelMaterial::Lava } else { VoxelMaterial::Rock };
+                            state.world.set_material_at(idx, rubble);
+                        }
+                    }
+                    effect.voxels_modified += in_reach.len() as u32;
                 }
-                pop.size > 0
-            });
+                CatastropheKind::Plague => {
+                    // The same share of every population and civilization in reach dies
+                    let lethality =
+                        |strength: f32| (intensity / PLAGUE_LETHAL_INTENSITY * strength).min(1.0);
+                    state.populations.retain_mut(|pop| {
+                        if let Some(strength) = falloff((pop.x, pop.y, pop.z)) {
+                            let killed = (pop.size as f32 * lethality(strength)) as u32;
+                            pop.size -= killed;
+                            effect.population_killed =
+                                effect.population_killed.saturating_add(killed);
+                            effect.populations_affected += 1;
+                        }
+                        pop.size > 0
+                    });
+                    for civ in state.civilizations.iter_mut() {
+                        if let Some(strength) = falloff((civ.x, civ.y, civ.z)) {
+                            let killed = (civ.population as f32 * lethality(strength)) as u32;
+                            civ.population -= killed;
+                            effect.population_killed =
+                                effect.population_killed.saturating_add(killed);
+                        }
+                    }
+                }
+                CatastropheKind::Flood => {
+                    // Water rises up to the epicenter's level, not above it
+                    let layer = state.world.width as usize * state.world.height as usize;
+                    for &(idx, _, _) in &in_reach {
+                        let floodable = matches!(
+                            state.world.voxels[idx].material,
+                            VoxelMaterial::Air | VoxelMaterial::Soil
+                        );
+                        if floodable && idx / layer <= z as usize {
+                            state.world.set_material_at(idx, VoxelMaterial::Water);
+                            effect.voxels_modified += 1;
+                        }
+                    }
+                }
+            }
         }
         GodAction::BlessCivilization { civ_id, tech_boost } => {
-            if let Some(civ) = state.civilizations.iter_mut().find(|c| c.id == civ_id) {
-                civ.tech_level += tech_boost;
-                civ.population = (civ.population as f32 * 1.2) as u32;
+            let max_population = state.biology_rules.max_civ_population;
+            let civ = state
+                .civilizations
+                .iter_mut()
+                .find(|c| c.id == civ_id)
+                .ok_or(ActionError::UnknownCivilization(civ_id))?;
+            civ.tech_level += tech_boost;
+            let grown = (civ.population as f64 * 1.2) as u64;
+            civ.population = grown.min(max_population as u64) as u32;
+            effect.civs_boosted.push(civ_id);
+        }
+        GodAction::AnswerPrayer { civ_id } => {
+            let max_population = state.biology_rules.max_civ_population;
+            let civ = state
+                .civilizations
+                .iter_mut()
+                .find(|c| c.id == civ_id)
+                .ok_or(ActionError::UnknownCivilization(civ_id))?;
+            civ.tech_level += 0.5;
+            let grown = (civ.population as f64 * 1.1) as u64;
+            civ.population = grown.min(max_population as u64) as u32;
+            civ.spirituality = (civ.spirituality + 0.05).min(1.0);
+            effect.civs_boosted.push(civ_id);
+        }
+        GodAction::Resurrect { x, y, z, species_id, size } => {
+            if x >= state.world.width || y >= state.world.height || z >= state.world.depth {
+                return Err(ActionError::OutOfBounds { x, y, z });
             }
+            let (x, y, z) =
+                nearest_habitable(&state.world, x, y, z).ok_or(ActionError::Uninhabitable)?;
+            let species_id = if state.species.get(species_id).is_some() {
+                species_id
+            } else {
+                let species = Arc::make_mut(&mut state.species);
+                if !species.make_room(state.biology_rules.max_species, &state.populations) {
+                    return Err(ActionError::SpeciesLimit);
+                }
+                let voxel = state.world.get(x, y, z);
+                species.create_adapted(voxel, &mut state.rng).id
+            };
+            state.populations.push(Population::new(species_id, x, y, z, size));
+            effect.populations_affected += 1;
+        }
+        GodAction::RaiseSeaLevel { layers } => {
+            let world = &mut state.world;
+            world.sea_level = world.sea_level.saturating_add(layers).min(world.depth);
+            let (flooded, drowned) = state.flood_to_sea_level();
+            effect.voxels_modified += flooded;
+            effect.population_killed += drowned;
         }
         GodAction::None => {}
     }
+
+    Ok(effect)
 }
 
-pub fn step_god(state: &mut SimulationState) -> GodAction {
+// Closest voxel (straight-line distance, honoring wrapping) that life could live in
+fn nearest_habitable(world: &World3D, x: u32, y: u32, z: u32) -> Option<(u32, u32, u32)> {
+    let mut best = None;
+    let mut best_distance = f32::INFINITY;
+    for nz in 0..world.depth {
+        for ny in 0..world.height {
+            for nx in 0..world.width {
+                if !world.get(nx, ny, nz).material.is_habitable() {
+                    continue;
+                }
+                let distance = world.distance((nx, ny, nz), (x, y, z));
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = Some((nx, ny, nz));
+                }
+            }
+        }
+    }
+    best
+}
+
+// What each god would do next, without touching its mood, the world or the
+// state's RNG. Every god sees the same snapshot, so unlike `step_god` a god
+// does not react to the ones acting before it.
+pub fn preview_actions(state: &SimulationState) -> Vec<GodAction> {
     let summary = build_world_summary(state);
-    let action = choose_action(&mut state.god_state, &summary);
-    apply_action(state, action.clone());
-    action
+    let mut rng = state.rng.clone();
+    state
+        .gods
+        .iter()
+        .map(|god| choose_action_with_reason(&mut god.clone(), &summary, &mut rng).0)
+        .collect()
+}
+
+// Every god in the pantheon acts in turn; later gods see (and may undo) the
+// effects of earlier ones within the same tick
+pub fn step_god(state: &mut SimulationState) -> Vec<GodAction> {
+    let mut actions = Vec::with_capacity(state.gods.len());
+
+    for god in 0..state.gods.len() {
+        let summary = build_world_summary(state);
+        let (action, reason) =
+            choose_action_with_reason(&mut state.gods[god], &summary, &mut state.rng);
+
+        // Failed interventions (e.g. a blessing for a civ that just collapsed) are simply
+        // wasted, but still logged
+        if !matches!(action, GodAction::None) {
+            state.god_action_log.push(state.tick, action.clone());
+            let outcome = apply_action(state, action.clone());
+            state.events.push(SimEvent::GodIntervention {
+                god,
+                action: action.clone(),
+                reason,
+                outcome,
+            });
+        }
+        actions.push(action);
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::civilization::Civilization;
+    use crate::config::SimulationConfig;
+    use crate::time_sim::{simulate_tick, SimulationStateBuilder};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn the_log_grows_only_on_real_interventions() {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(17);
+        // A restless tinkerer and a quiet god: plenty of turns of both kinds
+        state.gods = vec![
+            GodState::builder().curiosity(0.95).activity(5.0).build(),
+            GodState::benevolent(),
+        ];
+
+        for _ in 0..200 {
+            let before = state.god_action_log.len();
+            simulate_tick(&mut state);
+            let interventions = state
+                .events
+                .iter()
+                .filter(|event| matches!(event, SimEvent::GodIntervention { .. }))
+                .count();
+            assert_eq!(state.god_action_log.len(), before + interventions);
+        }
+        assert!(!state.god_action_log.is_empty());
+        assert!(state.god_action_log.iter().all(|(_, action)| !matches!(action, GodAction::None)));
+    }
+
+    #[test]
+    fn cruel_gods_answer_wars_with_catastrophes() {
+        // A lively world with two wars going on
+        let summary = WorldSummary {
+            num_civilizations: 3,
+            avg_tech_level: 2.0,
+            total_biomass: 5000,
+            wars_ongoing: 2,
+            climate_stability: 0.8,
+            world_dimensions: (16, 16, 8),
+            civ_ids: vec![0, 1, 2],
+            species_ids: vec![0],
+            most_fragile_civ: Some(2),
+            most_devout_civ: None,
+            regions: Vec::new(),
+        };
+        let catastrophes = |mut god: GodState| {
+            let mut rng = StdRng::seed_from_u64(4);
+            let mut counts = BTreeMap::new();
+            for _ in 0..2000 {
+                let (action, _) = choose_action_with_reason(&mut god, &summary, &mut rng);
+                *counts.entry(action.kind()).or_insert(0) += 1;
+            }
+            counts
+        };
+
+        let cruel = catastrophes(GodState::cruel());
+        let spawned = cruel.get("SpawnCatastrophe").copied().unwrap_or(0);
+        assert!(spawned > 200, "{:?}", cruel);
+        assert!(cruel.iter().all(|(name, &count)| {
+            matches!(*name, "SpawnCatastrophe" | "None") || count < spawned
+        }));
+        // The same god without its cruelty leaves the wars alone
+        let mild = catastrophes(GodState::builder().curiosity(0.5).benevolence(0.2).build());
+        assert!(!mild.contains_key("SpawnCatastrophe"), "{:?}", mild);
+    }
+
+    #[test]
+    fn cruel_gods_strike_the_most_populous_region() {
+        let config = SimulationConfig { width: 32, height: 32, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(6);
+        // Two peaceful giants sharing a region, whose total overflows a u32,
+        // and four small civilizations at war on the other side of the world
+        state.civilizations = vec![
+            Civilization::new(0, 4, 4, 4, 3_000_000_000),
+            Civilization::new(1, 5, 4, 4, 2_000_000_000),
+        ];
+        for (id, (x, y)) in [(20, 20), (21, 20), (20, 21), (21, 21)].into_iter().enumerate() {
+            let mut civ = Civilization::new(id as u32 + 2, x, y, 4, 1000);
+            civ.aggression = 1.0;
+            state.civilizations.push(civ);
+        }
+        for civ in &mut state.civilizations[..2] {
+            civ.aggression = 0.0;
+        }
+        let summary = build_world_summary(&state);
+        assert_eq!(summary.densest_civ_region().unwrap().civ_population, 5_000_000_000);
+
+        let mut god = GodState::cruel();
+        let mut rng = StdRng::seed_from_u64(6);
+        let (mut near, mut total) = (0, 0);
+        for _ in 0..2000 {
+            if let (GodAction::SpawnCatastrophe { x, y, .. }, _) =
+                choose_action_with_reason(&mut god, &summary, &mut rng)
+            {
+                total += 1;
+                if x < REGION_SIZE && y < REGION_SIZE {
+                    near += 1;
+                }
+            }
+        }
+        // By chance, 1 catastrophe in 16 would land in that region
+        assert!(total > 100, "{} catastrophes", total);
+        assert!(near * 2 > total, "{} of {} near the giants", near, total);
+    }
+
+    #[test]
+    fn plagues_spare_the_land() {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(9);
+        let (x, y, z) = {
+            let pop = &state.populations[0];
+            (pop.x, pop.y, pop.z)
+        };
+        state.civilizations.push(Civilization::new(0, x, y, z, 1000));
+        let materials = |state: &SimulationState| -> Vec<VoxelMaterial> {
+            state.world.voxels.iter().map(|voxel| voxel.material).collect()
+        };
+        let biomass = |state: &SimulationState| -> u32 {
+            state.populations.iter().map(|pop| pop.size).sum()
+        };
+        let (land, life) = (materials(&state), biomass(&state));
+
+        let plague = GodAction::SpawnCatastrophe {
+            kind: CatastropheKind::Plague,
+            x,
+            y,
+            z,
+            intensity: 25.0,
+            radius: 4.0,
+        };
+        let effect = apply_action(&mut state, plague).unwrap();
+        assert_eq!(materials(&state), land);
+        assert_eq!(effect.voxels_modified, 0);
+        let civ_population = state.civilizations[0].population;
+        assert!(civ_population < 1000);
+        assert!(biomass(&state) < life);
+        assert_eq!(effect.population_killed, life - biomass(&state) + 1000 - civ_population);
+    }
+
+    #[test]
+    fn blessings_stop_at_the_population_cap() {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(5);
+        let max_population = state.biology_rules.max_civ_population;
+        state.civilizations.push(Civilization::new(7, 4, 4, 2, max_population - 10));
+
+        let bless = |civ_id| GodAction::BlessCivilization { civ_id, tech_boost: 1.0 };
+        let effect = apply_action(&mut state, bless(7)).unwrap();
+        assert_eq!(effect.civs_boosted, [7]);
+        assert_eq!(state.civilizations[0].population, max_population);
+
+        assert_eq!(apply_action(&mut state, bless(8)), Err(ActionError::UnknownCivilization(8)));
+    }
+
+    #[test]
+    fn war_counts_match_a_check_of_every_pair() {
+        let config = SimulationConfig { width: 24, height: 24, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(12);
+        let mut rng = StdRng::seed_from_u64(12);
+        for id in 0..80 {
+            let (x, y, z) = (rng.gen_range(0..24), rng.gen_range(0..24), rng.gen_range(0..8));
+            let mut civ = Civilization::random(id, x, y, z, rng.gen_range(100..20_000), &mut rng);
+            civ.aggression = rng.gen_range(0.4..1.0);
+            state.civilizations.push(civ);
+        }
+
+        for wrapping in [false, true] {
+            state.world.wrapping = wrapping;
+            let civs = &state.civilizations;
+            let mut wars = 0;
+            for i in 0..civs.len() {
+                for j in (i + 1)..civs.len() {
+                    if civs[i].is_hostile_to(&civs[j], &state.world) {
+                        wars += 1;
+                    }
+                }
+            }
+            assert!(wars > 0);
+            assert_eq!(build_world_summary(&state).wars_ongoing, wars);
+        }
+    }
+
+    #[test]
+    fn clones_share_history_but_not_later_entries() {
+        let mut log = ActionLog::new();
+        log.push(1, GodAction::RaiseSeaLevel { layers: 1 });
+        let mut branch = log.clone();
+        branch.push(2, GodAction::RaiseSeaLevel { layers: 2 });
+        log.push(3, GodAction::RaiseSeaLevel { layers: 3 });
+
+        let ticks =
+            |log: &ActionLog| log.to_vec().iter().map(|(tick, _)| *tick).collect::<Vec<_>>();
+        assert_eq!(ticks(&log), [1, 3]);
+        assert_eq!(ticks(&branch), [1, 2]);
+
+        let json = serde_json::to_string(&branch).unwrap();
+        let loaded: ActionLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(ticks(&loaded), [1, 2]);
+    }
+
+    #[test]
+    fn long_logs_drop_without_overflowing_the_stack() {
+        let mut log = ActionLog::new();
+        for tick in 0..500_000 {
+            log.push(tick, GodAction::None);
+        }
+        drop(log);
+    }
+
+    #[test]
+    fn a_cruel_and_a_benevolent_god_both_leave_their_mark() {
+        // The canonical rivals and a third civilization, all spoiling for a fight
+        let mut state = crate::fixtures::CANONICAL.initial_state();
+        let z = state.world.surface_height_at(4, 11).unwrap();
+        state.civilizations.push(Civilization::new(2, 4, 11, z, 2000));
+        for civ in &mut state.civilizations {
+            civ.aggression = 1.0;
+        }
+        assert!(build_world_summary(&state).wars_ongoing > 1);
+        state.gods = vec![GodState::cruel(), GodState::benevolent()];
+        let mut destroyed = false;
+        let mut helped = false;
+        for _ in 0..500 {
+            simulate_tick(&mut state);
+            for event in &state.events {
+                if let SimEvent::GodIntervention { god, action, .. } = event {
+                    match action {
+                        GodAction::SpawnCatastrophe { .. } if *god == 0 => destroyed = true,
+                        GodAction::BlessCivilization { .. }
+                        | GodAction::AnswerPrayer { .. }
+                        | GodAction::Resurrect { .. }
+                            if *god == 1 =>
+                        {
+                            helped = true
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Each god's kind of intervention made it into the log
+        let kinds: Vec<&str> =
+            state.god_action_log.iter().map(|(_, action)| action.kind()).collect();
+        let helpful = ["BlessCivilization", "AnswerPrayer", "Resurrect"];
+        assert!(destroyed && helped, "{:?}", kinds);
+        assert!(kinds.contains(&"SpawnCatastrophe"), "{:?}", kinds);
+        assert!(kinds.iter().any(|kind| helpful.contains(kind)), "{:?}", kinds);
+    }
+
+    #[test]
+    fn crueler_gods_unleash_stronger_catastrophes() {
+        // A peaceful world: only boredom makes these gods strike
+        let summary = WorldSummary {
+            num_civilizations: 2,
+            avg_tech_level: 2.0,
+            total_biomass: 5000,
+            wars_ongoing: 0,
+            climate_stability: 0.8,
+            world_dimensions: (16, 16, 8),
+            civ_ids: vec![0, 1],
+            species_ids: vec![0],
+            most_fragile_civ: None,
+            most_devout_civ: None,
+            regions: Vec::new(),
+        };
+        let mean_intensity = |cruelty: f32| {
+            let god = GodState::builder().cruelty(cruelty).boredom(1.0).build();
+            let mut rng = StdRng::seed_from_u64(2);
+            let mut intensities = Vec::new();
+            for _ in 0..2000 {
+                let (action, reason) =
+                    choose_action_with_reason(&mut god.clone(), &summary, &mut rng);
+                if let GodAction::SpawnCatastrophe { intensity, .. } = action {
+                    assert!(reason.contains(&format!("intensity {:.1}", intensity)), "{}", reason);
+                    intensities.push(intensity);
+                }
+            }
+            assert!(intensities.len() > 500, "{} catastrophes", intensities.len());
+            intensities.iter().sum::<f32>() / intensities.len() as f32
+        };
+
+        let (gentle, cruel) = (mean_intensity(0.1), mean_intensity(0.9));
+        // Intensities are scaled by 0.5 + cruelty: 0.6 against 1.4
+        assert!(cruel > gentle * 2.0, "cruel {} vs gentle {}", cruel, gentle);
+    }
+
+    #[test]
+    fn catastrophes_land_inside_a_small_world() {
+        // The canonical 16³ world, its two civilizations at war, and a third
+        let mut state = crate::fixtures::CANONICAL.initial_state();
+        let z = state.world.surface_height_at(4, 11).unwrap();
+        state.civilizations.push(Civilization::new(2, 4, 11, z, 2000));
+        for civ in &mut state.civilizations {
+            civ.aggression = 1.0;
+        }
+        let summary = build_world_summary(&state);
+        assert_eq!(summary.world_dimensions, (16, 16, 16));
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut catastrophes = 0;
+        for i in 0..4000 {
+            // Bored or cruel, every other sample
+            let mut god = GodState::cruel();
+            god.boredom = if i % 2 == 0 { 1.0 } else { 0.0 };
+            if let (GodAction::SpawnCatastrophe { x, y, z, .. }, _) =
+                choose_action_with_reason(&mut god, &summary, &mut rng)
+            {
+                catastrophes += 1;
+                assert!(x < 16 && y < 16 && z < 16, "out of bounds at ({}, {}, {})", x, y, z);
+            }
+        }
+        assert!(catastrophes > 1000, "{} catastrophes", catastrophes);
+    }
+
+    #[test]
+    fn previews_leave_the_god_untouched_while_steps_change_it() {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(5);
+        // A bored tinkerer in a world without civilizations: every turn moves its mood
+        state.gods = vec![GodState::builder().curiosity(0.9).boredom(0.5).activity(5.0).build()];
+        let mood = |state: &SimulationState| {
+            let god = &state.gods[0];
+            (god.curiosity, god.benevolence, god.cruelty, god.boredom)
+        };
+        let (before, rng, hash) = (mood(&state), state.rng.clone(), state.content_hash());
+
+        let preview = preview_actions(&state);
+        assert_eq!(mood(&state), before);
+        assert_eq!(state.rng, rng);
+        assert_eq!(state.content_hash(), hash);
+        assert!(state.god_action_log.is_empty());
+
+        // The step takes the action the preview announced, and moves the mood
+        let actions = step_god(&mut state);
+        assert_eq!(format!("{:?}", actions), format!("{:?}", preview));
+        assert_ne!(mood(&state), before);
+        assert_ne!(state.rng, rng);
+    }
+
+    #[test]
+    fn prayers_are_answered_for_the_most_spiritual_civilization() {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(23);
+        // Two devout civilizations and a worldly one, under a kind and busy god
+        state.civilizations = [(0, 0.75), (1, 0.9), (2, 0.3)]
+            .iter()
+            .map(|&(id, spirituality)| {
+                let mut civ = Civilization::new(id, 4 + 4 * id, 8, 4, 1000);
+                civ.spirituality = spirituality;
+                civ
+            })
+            .collect();
+        state.gods =
+            vec![GodState::builder().benevolence(0.9).cruelty(0.0).activity(5.0).build()];
+
+        let mut answered = [0u32; 3];
+        for _ in 0..300 {
+            for action in step_god(&mut state) {
+                if let GodAction::AnswerPrayer { civ_id } = action {
+                    answered[civ_id as usize] += 1;
+                }
+            }
+        }
+        assert!(answered[1] > 0, "{:?}", answered);
+        assert!(answered[1] > answered[0] && answered[1] > answered[2], "{:?}", answered);
+        // Answered prayers deepen the faith
+        assert!(state.civilizations[1].spirituality > 0.9);
+    }
+
+    #[test]
+    fn activity_scales_how_often_gods_act() {
+        // An empty world and a lively one at war, to tempt every kind of god
+        let summary = |civs: u32| WorldSummary {
+            num_civilizations: civs,
+            avg_tech_level: 2.0,
+            total_biomass: if civs > 0 { 5000 } else { 50 },
+            wars_ongoing: civs.saturating_sub(1),
+            climate_stability: 0.8,
+            world_dimensions: (16, 16, 8),
+            civ_ids: (0..civs).collect(),
+            species_ids: vec![0],
+            most_fragile_civ: civs.checked_sub(1),
+            most_devout_civ: civs.checked_sub(1),
+            regions: Vec::new(),
+        };
+        let actions = |activity: f32| {
+            let mut rng = StdRng::seed_from_u64(12);
+            let mut count = 0;
+            for (traits, civs) in [(0.9, 0), (0.9, 3), (0.1, 0), (0.1, 3)] {
+                let mut god = GodState::builder()
+                    .curiosity(traits)
+                    .benevolence(traits)
+                    .cruelty(traits)
+                    .activity(activity)
+                    .build();
+                for _ in 0..250 {
+                    let (action, _) = choose_action_with_reason(&mut god, &summary(civs), &mut rng);
+                    count += !matches!(action, GodAction::None) as u32;
+                }
+            }
+            count
+        };
+
+        assert_eq!(actions(0.0), 0);
+        let (normal, hyperactive) = (actions(1.0), actions(10.0));
+        assert!(normal > 0);
+        assert!(hyperactive > 3 * normal, "{} vs {}", hyperactive, normal);
+        // Mild gods rarely qualify for an action, whatever their activity
+        assert!(hyperactive > 300, "{} actions in 1000 turns", hyperactive);
+    }
+
+    #[test]
+    fn a_delta_turning_gravity_off_leaves_loose_soil_hanging() {
+        // Soil floating over a pocket of air, on a rock floor
+        let soil_after_a_tick = |delta: Option<PhysicsRulesDelta>| {
+            let mut world = World3D::new(1, 1, 3);
+            world.set_material(0, 0, 0, VoxelMaterial::Rock);
+            world.set_material(0, 0, 2, VoxelMaterial::Soil);
+            let mut state = SimulationStateBuilder::new().world(world).build().unwrap();
+            state.gods.clear();
+            let rate = state.physics_rules.heat_diffusion_rate;
+            if let Some(delta) = delta {
+                assert!(apply_action(&mut state, GodAction::ChangePhysics(delta)).is_ok());
+            }
+            // Only the toggled rule changed
+            assert_eq!(state.physics_rules.heat_diffusion_rate, rate);
+            let gravity = state.physics_rules.gravity_enabled;
+            simulate_tick(&mut state);
+            let soil = (0..3).find(|&z| state.world.get(0, 0, z).material == VoxelMaterial::Soil);
+            (gravity, soil)
+        };
+
+        assert_eq!(soil_after_a_tick(None), (true, Some(1)));
+        let off = PhysicsRulesDelta { gravity_enabled: Some(false), ..Default::default() };
+        assert_eq!(soil_after_a_tick(Some(off)), (false, Some(2)));
+    }
+
+    #[test]
+    fn resurrected_life_survives_in_a_dead_world() {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        let mut state = config.initial_state(13);
+        state.populations.clear();
+        state.gods.clear();
+        let species_before = state.species.len();
+
+        // High in the sky, for a species that does not exist: the action finds
+        // ground below and creates a species suited to it
+        let (x, y, z) = (8, 8, 7);
+        let action = GodAction::Resurrect { x, y, z, species_id: 999, size: 200 };
+        let effect = apply_action(&mut state, action).unwrap();
+        assert_eq!(effect.populations_affected, 1);
+        assert_eq!(state.populations.len(), 1);
+        assert_eq!(state.species.len(), species_before + 1);
+        let pop = &state.populations[0];
+        assert!(state.world.get(pop.x, pop.y, pop.z).material.is_habitable());
+        assert!(state.species.get(pop.species_id).is_some());
+
+        for _ in 0..50 {
+            simulate_tick(&mut state);
+        }
+        let biomass: u64 = state.populations.iter().map(|pop| pop.size as u64).sum();
+        assert!(biomass > 0, "the resurrected life died out");
+    }
+
+    #[test]
+    fn wider_catastrophes_reach_further_and_fade_with_distance() {
+        use crate::biology::{Population, SpeciesRegistry};
+
+        // A meteor of intensity 100 over the middle of a block of air at 0 °C,
+        // with populations 0, 2, 4 and 6 voxels east of the epicenter
+        let strike = |radius: f32| {
+            let mut world = World3D::new(15, 15, 15);
+            for voxel in &mut world.voxels {
+                voxel.temperature = 0.0;
+            }
+            let mut species = SpeciesRegistry::new();
+            species.create(&mut StdRng::seed_from_u64(0));
+            let populations = [7, 9, 11, 13].iter().map(|&x| Population::new(0, x, 7, 7, 10_000));
+            let mut state = SimulationStateBuilder::new()
+                .world(world)
+                .species(species)
+                .populations(populations.collect())
+                .build()
+                .unwrap();
+            let meteor = GodAction::SpawnCatastrophe {
+                kind: CatastropheKind::Meteor,
+                x: 7,
+                y: 7,
+                z: 7,
+                intensity: 100.0,
+                radius,
+            };
+            let effect = apply_action(&mut state, meteor).unwrap();
+            let heat: Vec<f32> = (7..15).map(|x| state.world.get(x, 7, 7).temperature).collect();
+            let survivors: Vec<u32> = state.populations.iter().map(|pop| pop.size).collect();
+            (effect.voxels_modified, heat, survivors)
+        };
+
+        let (narrow, _, _) = strike(2.0);
+        let (wide, heat, survivors) = strike(5.0);
+        assert!(wide > 5 * narrow, "{} vs {} voxels", wide, narrow);
+        // Heat and losses fall off with distance, and stop past the radius
+        assert!(heat[..6].windows(2).all(|w| w[0] > w[1] && w[1] > 0.0), "{:?}", heat);
+        assert_eq!(heat[6..], [0.0, 0.0]);
+        assert!(survivors.windows(2).all(|w| w[0] < w[1]), "{:?}", survivors);
+        assert_eq!(survivors[3], 10_000);
+    }
 }
@@ -0,0 +1,13 @@
+pub mod biology;
+pub mod civilization;
+pub mod config;
+pub mod events;
+#[cfg(test)]
+mod fixtures;
+pub mod god;
+pub mod physics;
+pub mod render;
+pub mod script;
+pub mod spatial;
+pub mod time_sim;
+pub mod world3d;
@@ -1,112 +1,85 @@
-mod biology;
-mod civilization;
-mod god;
-mod physics;
-mod render;
-mod time_sim;
-mod world3d;
-
-use biology::{Population, Species};
-use god::{GodAction, GodState};
-use physics::PhysicsRules;
-use time_sim::{Multiverse, SimulationState};
-use world3d::World3D;
+use rand::Rng;
+use temporal_god_sim_3d::config::{run_simulation_with, SimulationConfig};
+use temporal_god_sim_3d::god::{self, GodAction};
+use temporal_god_sim_3d::render;
 
 fn main() {
     println!("=== TEMPORAL GOD SIMULATION 3D ===\n");
 
-    // Configuration
-    const WORLD_WIDTH: u32 = 64;
-    const WORLD_HEIGHT: u32 = 64;
-    const WORLD_DEPTH: u32 = 32;
-    const NUM_TICKS: u64 = 1000;
+    // Display settings; everything else lives in the SimulationConfig
     const PRINT_INTERVAL: u64 = 50;
 
-    // Initialize world
-    println!("Generating 3D voxel world ({}x{}x{})...", WORLD_WIDTH, WORLD_HEIGHT, WORLD_DEPTH);
-    let world = World3D::generate_basic_world(WORLD_WIDTH, WORLD_HEIGHT, WORLD_DEPTH);
-
-    // Initialize physics
-    let physics_rules = PhysicsRules::default();
-
-    // Initialize species
-    println!("Creating initial species...");
-    let species = vec![
-        Species::new(0),
-        Species::new(1),
-        Species::new(2),
-    ];
-
-    // Initialize populations (seed life in various locations)
-    println!("Seeding initial populations...");
-    let mut populations = Vec::new();
-
-    for i in 0..5 {
-        let x = 10 + i * 10;
-        let y = 10 + i * 8;
-        let z = WORLD_DEPTH * 6 / 10; // Mid-upper level
-
-        populations.push(Population::new(i % 3, x, y, z, 50 + i * 20));
-    }
-
-    // Initialize God
-    println!("Awakening the God AI...");
-    let god_state = GodState::default();
-
-    // Create initial simulation state
-    let initial_state = SimulationState::new(
-        world,
-        physics_rules,
-        species,
-        populations,
-        god_state,
+    // The same seed (first argument) replays the same run
+    let seed: u64 = match std::env::args().nth(1) {
+        Some(arg) => arg.parse().unwrap_or_else(|_| {
+            eprintln!("Invalid seed '{}', expected an unsigned integer", arg);
+            std::process::exit(2);
+        }),
+        None => rand::thread_rng().gen(),
+    };
+    println!("Seed: {}", seed);
+    let config = SimulationConfig {
+        seed: Some(seed),
+        ..SimulationConfig::default()
+    };
+
+    println!("Generating 3D voxel world ({}x{}x{})...", config.width, config.height, config.depth);
+    println!(
+        "Creating {} species, seeding {} populations and awakening the God AI...",
+        config.species, config.seed_populations
     );
+    println!("Starting simulation for {} ticks...\n", config.ticks);
+
+    let mut last_printed = None;
+    let multiverse = run_simulation_with(&config, |multiverse| {
+        let (tick, state) = match multiverse.current_state() {
+            Some(state) => (multiverse.get_tick(), state),
+            None => return,
+        };
+
+        // Print initial state
+        if tick == 0 {
+            render::print_summary(0, state, &[GodAction::None]);
+            render::print_world_slice(state, config.depth / 2, true);
+            last_printed = Some(state.clone());
+            return;
+        }
 
-    // Create multiverse with initial timeline
-    println!("Creating the multiverse...\n");
-    let mut multiverse = Multiverse::new(initial_state);
-
-    // Print initial state
-    if let Some(state) = multiverse.current_state() {
-        render::print_summary(0, state, &GodAction::None);
-        render::print_world_slice(state, WORLD_DEPTH / 2);
-    }
-
-    // Main simulation loop
-    println!("Starting simulation for {} ticks...\n", NUM_TICKS);
-
-    for tick in 1..=NUM_TICKS {
-        // Get current state and clone it for modification
-        let current_state = multiverse.current_state().unwrap().clone();
-        let mut new_state = current_state;
-
-        // Simulate one tick
-        time_sim::simulate_tick(&mut new_state);
-
-        // Store the new state
-        multiverse.push_state(new_state);
+        // Chronicle of the tick's notable events
+        for line in render::narrate(&state.events, state) {
+            println!("[tick {}] {}", tick, line);
+        }
 
         // Print periodic updates
         if tick % PRINT_INTERVAL == 0 {
-            if let Some(state) = multiverse.current_state() {
-                let last_action = god::step_god(&mut state.clone());
-                render::print_summary(tick, state, &last_action);
+            let last_actions = god::preview_actions(state);
+            match &last_printed {
+                Some(previous) => render::print_summary_delta(tick, previous, state, &last_actions),
+                None => render::print_summary(tick, state, &last_actions),
+            }
 
-                // Optionally show a world slice every few intervals
-                if tick % (PRINT_INTERVAL * 4) == 0 {
-                    render::print_world_slice(state, WORLD_DEPTH / 2);
-                }
+            // Optionally show a world slice every few intervals
+            if tick % (PRINT_INTERVAL * 4) == 0 {
+                render::print_world_slice(state, config.depth / 2, true);
             }
+
+            last_printed = Some(state.clone());
         }
+    });
+
+    // Nothing changed anymore: the run stopped early
+    if multiverse.get_tick() < config.ticks {
+        println!("Equilibrium reached at tick {}, stopping early.", multiverse.get_tick());
     }
 
     // Final report
     println!("\n=== SIMULATION COMPLETE ===\n");
     if let Some(final_state) = multiverse.current_state() {
+        render::print_surface_map(final_state, false);
         render::print_detailed_report(final_state);
     }
 
-    println!("Total ticks simulated: {}", NUM_TICKS);
+    println!("Total ticks simulated: {}", multiverse.get_tick());
     println!("Timeline states stored: {}", multiverse.current_timeline().len());
     println!("\nThe simulation has ended. The God AI rests.");
 }
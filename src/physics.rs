@@ -1,10 +1,59 @@
-use crate::world3d::{World3D, VoxelMaterial};
+use crate::world3d::{DiffusionStencil, NeighborSet, VoxelMaterial, World3D};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+// How heat diffusion treats the faces of the world
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BoundaryCondition {
+    // No exchange across the edge: edge voxels average fewer neighbors
+    Insulated,
+    // Outside the world is a reservoir held at this temperature
+    Fixed(f32),
+    // The sides wrap around (x and y); top and bottom stay insulated
+    Periodic,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PhysicsRules {
     pub gravity_enabled: bool,
     pub heat_diffusion_rate: f32,
     pub cooling_rate: f32,
+    pub diffusion_stencil: DiffusionStencil,
+    pub boundary: BoundaryCondition,
+    pub rock_melting_point: f32,
+    // Lava turns back to rock below this (a little under the melting point,
+    // so a voxel hovering at the threshold does not flip every tick)
+    pub lava_solidifying_point: f32,
+    // Water freezes below this; ice melts above `ice_melting_point`
+    pub water_freezing_point: f32,
+    pub ice_melting_point: f32,
+    // Degrees per tick added by sunlight to sky-exposed surfaces (0 = no sun)
+    pub solar_heating: f32,
+    // Degrees per tick per unit of tech_level * population that civilizations
+    // release into their voxel and its neighbors (0 = no urban heat)
+    pub urban_heat: f32,
+    // Surface water above this temperature can evaporate
+    pub evaporation_point: f32,
+    // Chance per tick and per degree above the evaporation point (0 = never)
+    pub evaporation_rate: f32,
+    // Heat diffusion passes per tick, each with a proportional share of the
+    // rate (0 = as many as needed to stay below STABLE_DIFFUSION_RATE)
+    pub diffusion_substeps: u32,
+    // Storms strike random surface voxels with lightning
+    pub storms_enabled: bool,
+    // Chance per tick of a lightning strike while storms are enabled
+    pub lightning_chance: f32,
+    // Let loose material fall all the way down in a single tick instead of
+    // one layer per tick
+    pub settle_fully: bool,
+    // Ticks per day-night cycle (0 = no cycle: constant sun and ambient). The
+    // sun shines during the first half of each day, and the ambient
+    // temperature swings by up to `diurnal_amplitude` degrees, fully at the
+    // top of the world and less and less with depth.
+    pub day_length: u64,
+    pub diurnal_amplitude: f32,
+    // The sea rises by one layer every this many ticks (0 = a steady sea)
+    pub sea_rise_interval: u64,
 }
 
 impl Default for PhysicsRules {
@@ -13,93 +62,895 @@ impl Default for PhysicsRules {
             gravity_enabled: true,
             heat_diffusion_rate: 0.1,
             cooling_rate: 0.02,
+            diffusion_stencil: DiffusionStencil::Orthogonal,
+            boundary: BoundaryCondition::Insulated,
+            rock_melting_point: 1200.0,
+            lava_solidifying_point: 1100.0,
+            water_freezing_point: 0.0,
+            ice_melting_point: 2.0,
+            solar_heating: 0.0,
+            urban_heat: 0.0,
+            evaporation_point: 35.0,
+            evaporation_rate: 0.0,
+            diffusion_substeps: 0,
+            storms_enabled: false,
+            lightning_chance: 0.05,
+            settle_fully: false,
+            day_length: 0,
+            diurnal_amplitude: 5.0,
+            sea_rise_interval: 0,
+        }
+    }
+}
+
+// Above this rate per pass, a voxel can overshoot the mean of its neighbors
+// and temperatures oscillate from tick to tick
+pub const STABLE_DIFFUSION_RATE: f32 = 0.5;
+
+// Degrees added to the voxel a lightning bolt strikes
+const LIGHTNING_HEAT: f32 = 300.0;
+
+// Temperature `apply_cooling` pulls toward, without a day-night cycle
+const AMBIENT_TEMP: f32 = 20.0;
+
+impl PhysicsRules {
+    // Fraction of full sunlight at `tick`: a half sine over the first half of
+    // each day, none at night (always 1 without a day-night cycle)
+    pub fn daylight(&self, tick: u64) -> f32 {
+        self.time_of_day(tick).map_or(1.0, |phase| phase.sin().max(0.0))
+    }
+
+    // Ambient temperature at `tick` for a voxel at height `z` in a world
+    // `depth` voxels deep; the swing fades quadratically with depth
+    pub fn ambient_temperature(&self, tick: u64, z: u32, depth: u32) -> f32 {
+        let phase = match self.time_of_day(tick) {
+            Some(phase) => phase,
+            None => return AMBIENT_TEMP,
+        };
+        let height = if depth > 1 { z as f32 / (depth - 1) as f32 } else { 1.0 };
+        AMBIENT_TEMP + self.diurnal_amplitude * phase.sin() * height * height
+    }
+
+    // Angle of the sun, in radians from dawn
+    fn time_of_day(&self, tick: u64) -> Option<f32> {
+        if self.day_length == 0 {
+            return None;
+        }
+        let fraction = (tick % self.day_length) as f32 / self.day_length as f32;
+        Some(fraction * std::f32::consts::TAU)
+    }
+
+    // Number of diffusion passes actually run each tick
+    pub fn effective_diffusion_substeps(&self) -> u32 {
+        if self.diffusion_substeps > 0 {
+            self.diffusion_substeps
+        } else {
+            (self.heat_diffusion_rate / STABLE_DIFFUSION_RATE).ceil().max(1.0) as u32
         }
     }
 }
 
-pub fn apply_physics(world: &mut World3D, rules: &PhysicsRules) {
+// A world needs at least one voxel for physics to do anything; gravity also
+// needs two layers. Smaller or inconsistent worlds are left untouched.
+// `tick` sets the time of day (see `PhysicsRules::day_length`).
+pub fn apply_physics(world: &mut World3D, rules: &PhysicsRules, tick: u64, rng: &mut impl Rng) {
+    let size = world.width as usize * world.height as usize * world.depth as usize;
+    if size == 0 || world.voxels.len() != size {
+        return;
+    }
+
     apply_heat_diffusion(world, rules);
-    apply_cooling(world, rules);
+    apply_solar_heating(world, rules, tick);
+    apply_cooling(world, rules, tick);
+    apply_phase_transitions(world, rules);
+    apply_evaporation(world, rules, rng);
+    apply_lightning(world, rules, rng);
 
     if rules.gravity_enabled {
-        apply_simple_gravity(world);
+        // Nothing can fall further than the world is deep, which bounds the passes
+        let passes = if rules.settle_fully { world.depth.max(1) } else { 1 };
+        for _ in 0..passes {
+            if !apply_simple_gravity(world) {
+                break;
+            }
+        }
+    }
+}
+
+// Conductance between two voxels: the harmonic mean of their conductivities,
+// so a poor conductor on either side throttles the exchange
+fn conductance(a: VoxelMaterial, b: VoxelMaterial) -> f32 {
+    let (ka, kb) = (a.conductivity(), b.conductivity());
+    if ka + kb <= 0.0 {
+        0.0
+    } else {
+        2.0 * ka * kb / (ka + kb)
+    }
+}
+
+// Temperatures as integer thousandths of a degree, for the fixed-point
+// diffusion of the `deterministic-physics` feature
+pub fn to_millidegrees(temperature: f32) -> i32 {
+    (temperature * 1000.0).round() as i32
+}
+
+pub fn from_millidegrees(millidegrees: i32) -> f32 {
+    millidegrees as f32 / 1000.0
+}
+
+// Heat a voxel exchanges with its neighbors during one diffusion pass
+#[derive(Default)]
+struct Exchange {
+    #[cfg(not(feature = "deterministic-physics"))]
+    weighted: f32,
+    // Millidegrees times conductance in thousandths: integer sums do not
+    // depend on evaluation order or platform float quirks
+    #[cfg(feature = "deterministic-physics")]
+    weighted: i64,
+    count: u32,
+}
+
+impl Exchange {
+    #[cfg(not(feature = "deterministic-physics"))]
+    fn add(&mut self, current: f32, neighbor: f32, conductance: f32) {
+        self.weighted += (neighbor - current) * conductance;
+        self.count += 1;
+    }
+
+    #[cfg(not(feature = "deterministic-physics"))]
+    fn apply(&self, current: f32, rate: f32) -> f32 {
+        current + self.weighted / self.count as f32 * rate
+    }
+
+    #[cfg(feature = "deterministic-physics")]
+    fn add(&mut self, current: f32, neighbor: f32, conductance: f32) {
+        let diff = to_millidegrees(neighbor) as i64 - to_millidegrees(current) as i64;
+        self.weighted += diff * (conductance * 1000.0).round() as i64;
+        self.count += 1;
+    }
+
+    #[cfg(feature = "deterministic-physics")]
+    fn apply(&self, current: f32, rate: f32) -> f32 {
+        // Rate in millionths; the division rounds toward zero
+        let rate = (rate * 1_000_000.0).round() as i64;
+        let delta = self.weighted * rate / (self.count as i64 * 1_000_000_000);
+        from_millidegrees(to_millidegrees(current) + delta as i32)
     }
 }
 
 fn apply_heat_diffusion(world: &mut World3D, rules: &PhysicsRules) {
-    let mut temp_buffer = vec![0.0; world.voxels.len()];
+    let substeps = rules.effective_diffusion_substeps();
+    let rate = rules.heat_diffusion_rate / substeps as f32;
+    let mut current = vec![0.0; world.voxels.len()];
+    let mut next = vec![0.0; world.voxels.len()];
+    for _ in 0..substeps {
+        diffusion_pass(world, rules, rate, &mut current, &mut next);
+    }
+}
 
-    // Copy current temperatures
-    for (i, voxel) in world.voxels.iter().enumerate() {
-        temp_buffer[i] = voxel.temperature;
+// Every voxel's new temperature depends only on the temperatures at the start
+// of the pass, so with the `parallel` feature voxels are computed concurrently
+// (with identical results)
+fn diffusion_pass(
+    world: &mut World3D,
+    rules: &PhysicsRules,
+    rate: f32,
+    current: &mut [f32],
+    next: &mut [f32],
+) {
+    for (temperature, voxel) in current.iter_mut().zip(&world.voxels) {
+        *temperature = voxel.temperature;
     }
 
-    // Diffuse heat to neighbors
-    for z in 0..world.depth {
-        for y in 0..world.height {
-            for x in 0..world.width {
-                let idx = world.index(x, y, z);
-                let current_temp = temp_buffer[idx];
-
-                let mut neighbor_count = 0;
-                let mut neighbor_temp_sum = 0.0;
-
-                // Check 6 neighbors (up, down, left, right, front, back)
-                let neighbors = [
-                    (x as i32 - 1, y as i32, z as i32),
-                    (x as i32 + 1, y as i32, z as i32),
-                    (x as i32, y as i32 - 1, z as i32),
-                    (x as i32, y as i32 + 1, z as i32),
-                    (x as i32, y as i32, z as i32 - 1),
-                    (x as i32, y as i32, z as i32 + 1),
-                ];
-
-                for (nx, ny, nz) in neighbors.iter() {
-                    if world.is_valid(*nx, *ny, *nz) {
-                        let n_idx = world.index(*nx as u32, *ny as u32, *nz as u32);
-                        neighbor_temp_sum += temp_buffer[n_idx];
-                        neighbor_count += 1;
+    let snapshot: &World3D = world;
+    let current: &[f32] = current;
+    let diffuse = |(idx, temperature): (usize, &mut f32)| {
+        *temperature = diffused_temperature(snapshot, rules, rate, current, idx);
+    };
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        next.par_iter_mut().enumerate().for_each(diffuse);
+    }
+    #[cfg(not(feature = "parallel"))]
+    next.iter_mut().enumerate().for_each(diffuse);
+
+    for (voxel, &temperature) in world.voxels.iter_mut().zip(next.iter()) {
+        voxel.temperature = temperature;
+    }
+}
+
+// Temperature of voxel `idx` after exchanging heat with its neighbors
+fn diffused_temperature(
+    world: &World3D,
+    rules: &PhysicsRules,
+    rate: f32,
+    temperatures: &[f32],
+    idx: usize,
+) -> f32 {
+    let (width, height) = (world.width as usize, world.height as usize);
+    let (x, y, z) = (idx % width, idx / width % height, idx / (width * height));
+    let current_temp = temperatures[idx];
+    let material = world.voxels[idx].material;
+
+    let mut exchange = Exchange::default();
+
+    // Check neighbors according to the stencil (6 faces or the full 26)
+    for &(dx, dy, dz) in rules.diffusion_stencil.offsets() {
+        let (nx, ny, nz) = (x as i32 + dx, y as i32 + dy, z as i32 + dz);
+        let neighbor = if world.is_valid(nx, ny, nz) {
+            Some((nx as u32, ny as u32, nz as u32))
+        } else {
+            match rules.boundary {
+                BoundaryCondition::Insulated => None,
+                BoundaryCondition::Fixed(temperature) => {
+                    exchange.add(current_temp, temperature, material.conductivity());
+                    None
+                }
+                BoundaryCondition::Periodic => {
+                    if nz >= 0 && nz < world.depth as i32 {
+                        let wx = nx.rem_euclid(world.width as i32) as u32;
+                        let wy = ny.rem_euclid(world.height as i32) as u32;
+                        Some((wx, wy, nz as u32))
+                    } else {
+                        None
                     }
                 }
+            }
+        };
+
+        if let Some((nx, ny, nz)) = neighbor {
+            let n_idx = world.index(nx, ny, nz);
+            let k = conductance(material, world.voxels[n_idx].material);
+            exchange.add(current_temp, temperatures[n_idx], k);
+        }
+    }
+
+    // Capacities are at least 1, so this never exceeds the configured rate
+    // and cannot destabilize the diffusion
+    if exchange.count > 0 {
+        exchange.apply(current_temp, rate / material.heat_capacity())
+    } else {
+        current_temp
+    }
+}
 
-                if neighbor_count > 0 {
-                    let avg_neighbor_temp = neighbor_temp_sum / neighbor_count as f32;
-                    let new_temp = current_temp +
-                        (avg_neighbor_temp - current_temp) * rules.heat_diffusion_rate;
-                    world.voxels[idx].temperature = new_temp;
+fn apply_cooling(world: &mut World3D, rules: &PhysicsRules, tick: u64) {
+    let layer = world.width as usize * world.height as usize;
+    let depth = world.depth;
+    for (z, voxels) in world.voxels.chunks_mut(layer).enumerate() {
+        let ambient = rules.ambient_temperature(tick, z as u32, depth);
+        for voxel in voxels {
+            let diff = ambient - voxel.temperature;
+            voxel.temperature += diff * rules.cooling_rate;
+        }
+    }
+}
+
+// Sunlight warms the voxels that see the sky (see World3D::has_sky_above):
+// the first solid surface of each column and the water above it, losing half
+// its strength per water layer; anything under a solid roof stays dark
+fn apply_solar_heating(world: &mut World3D, rules: &PhysicsRules, tick: u64) {
+    const WATER_ATTENUATION: f32 = 0.5;
+
+    let heating = rules.solar_heating * rules.daylight(tick);
+    if heating == 0.0 {
+        return;
+    }
+
+    for y in 0..world.height {
+        for x in 0..world.width {
+            let mut exposure = 1.0;
+            for z in (0..world.depth).rev() {
+                let idx = world.index(x, y, z);
+                let material = world.voxels[idx].material;
+                if material == VoxelMaterial::Air {
+                    continue;
+                }
+                if !world.has_sky_above(x, y, z) {
+                    break;
                 }
+                world.voxels[idx].temperature += heating * exposure;
+                exposure *= WATER_ATTENUATION;
+            }
+        }
+    }
+}
+
+// Materials change state when their temperature crosses a threshold
+// (set_material_at also updates their density)
+fn apply_phase_transitions(world: &mut World3D, rules: &PhysicsRules) {
+    for idx in 0..world.voxels.len() {
+        let voxel = &world.voxels[idx];
+        let t = voxel.temperature;
+        let next = match voxel.material {
+            VoxelMaterial::Rock if t >= rules.rock_melting_point => VoxelMaterial::Lava,
+            VoxelMaterial::Lava if t < rules.lava_solidifying_point => VoxelMaterial::Rock,
+            VoxelMaterial::Water if t < rules.water_freezing_point => VoxelMaterial::Ice,
+            VoxelMaterial::Ice if t > rules.ice_melting_point => VoxelMaterial::Water,
+            _ => continue,
+        };
+        world.set_material_at(idx, next);
+    }
+}
+
+// Hot surface water slowly turns to air, so lakes dry up from the top down
+fn apply_evaporation(world: &mut World3D, rules: &PhysicsRules, rng: &mut impl Rng) {
+    if rules.evaporation_rate <= 0.0 {
+        return;
+    }
+
+    for y in 0..world.height {
+        for x in 0..world.width {
+            let top = (0..world.depth)
+                .rev()
+                .map(|z| world.index(x, y, z))
+                .find(|&idx| world.voxels[idx].material != VoxelMaterial::Air);
+            let idx = match top {
+                Some(idx) => idx,
+                None => continue,
+            };
+
+            let voxel = &world.voxels[idx];
+            if voxel.material != VoxelMaterial::Water {
+                continue;
+            }
+            let excess = voxel.temperature - rules.evaporation_point;
+            if excess > 0.0 && rng.gen::<f32>() < rules.evaporation_rate * excess {
+                world.set_material_at(idx, VoxelMaterial::Air);
             }
         }
     }
 }
 
-fn apply_cooling(world: &mut World3D, rules: &PhysicsRules) {
-    const AMBIENT_TEMP: f32 = 20.0;
+fn apply_lightning(world: &mut World3D, rules: &PhysicsRules, rng: &mut impl Rng) {
+    if !rules.storms_enabled || world.width == 0 || world.height == 0 {
+        return;
+    }
+    if rng.gen::<f32>() >= rules.lightning_chance {
+        return;
+    }
 
-    for voxel in world.voxels.iter_mut() {
-        let diff = AMBIENT_TEMP - voxel.temperature;
-        voxel.temperature += diff * rules.cooling_rate;
+    let x = rng.gen_range(0..world.width);
+    let y = rng.gen_range(0..world.height);
+    if let Some(z) = world.surface_height_at(x, y) {
+        world.get_mut(x, y, z).temperature += LIGHTNING_HEAT;
     }
 }
 
-fn apply_simple_gravity(world: &mut World3D) {
-    // Very simple: if a loose material (Soil, Organic) has Air below it, swap them
+// One top-down pass; returns whether anything fell
+fn apply_simple_gravity(world: &mut World3D) -> bool {
+    let mut moved = false;
+    if world.depth < 2 {
+        return moved;
+    }
+    // Very simple: if a loose material (Soil, Organic, Water) has Air below it, swap them
     for z in (1..world.depth).rev() {
         for y in 0..world.height {
             for x in 0..world.width {
                 let current = world.get(x, y, z).material;
                 let below = world.get(x, y, z - 1).material;
 
-                let is_loose = matches!(current, VoxelMaterial::Soil | VoxelMaterial::Organic(_));
+                let is_loose = matches!(
+                    current,
+                    VoxelMaterial::Soil | VoxelMaterial::Organic(_) | VoxelMaterial::Water
+                );
                 let is_air_below = matches!(below, VoxelMaterial::Air);
+                let current_idx = world.index(x, y, z);
 
                 if is_loose && is_air_below {
                     // Swap the two voxels
-                    let current_idx = world.index(x, y, z);
                     let below_idx = world.index(x, y, z - 1);
                     world.voxels.swap(current_idx, below_idx);
+                    moved = true;
+                } else if current == VoxelMaterial::Water {
+                    // Blocked water spills sideways, toward a drop
+                    if let Some((sx, sy, sz)) = spill_target(world, x, y, z) {
+                        let target_idx = world.index(sx, sy, sz);
+                        world.voxels.swap(current_idx, target_idx);
+                        moved = true;
+                    }
                 }
             }
         }
     }
+    moved
+}
+
+// Air voxel beside (x, y, z), at the same level, with Air below it. Water only
+// spills where it can then fall, so every move ends lower and settling stops
+// once no drop is left (puddles on flat ground stay put). The first direction
+// tried rotates with the position, so spills do not all lean the same way.
+fn spill_target(world: &World3D, x: u32, y: u32, z: u32) -> Option<(u32, u32, u32)> {
+    let sides: Vec<(u32, u32, u32)> = world
+        .neighbors(x, y, z, NeighborSet::Orthogonal)
+        .filter(|&(_, _, nz)| nz == z)
+        .collect();
+    let start = (x + y + z) as usize % sides.len().max(1);
+    sides.iter().cycle().skip(start).take(sides.len()).copied().find(|&(sx, sy, sz)| {
+        world.get(sx, sy, sz).material == VoxelMaterial::Air
+            && world.get(sx, sy, sz - 1).material == VoxelMaterial::Air
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solar_heating_skips_voxels_under_a_roof() {
+        // Two soil voxels side by side; only the first is under a rock ceiling
+        let mut world = World3D::new(2, 1, 3);
+        for x in 0..2 {
+            world.set_material(x, 0, 0, VoxelMaterial::Soil);
+            world.get_mut(x, 0, 0).temperature = 20.0;
+        }
+        world.set_material(0, 0, 2, VoxelMaterial::Rock);
+        let rules = PhysicsRules { solar_heating: 1.0, ..Default::default() };
+
+        apply_solar_heating(&mut world, &rules, 0);
+        assert_eq!(world.get(0, 0, 0).temperature, 20.0);
+        assert!(world.get(1, 0, 0).temperature > 20.0);
+    }
+
+    #[test]
+    fn surfaces_follow_the_day_while_deep_rock_stays_steady() {
+        // A column of rock under two layers of air, all at the ambient temperature
+        let mut world = World3D::new(1, 1, 10);
+        for z in 0..8 {
+            world.set_material(0, 0, z, VoxelMaterial::Rock);
+        }
+        for voxel in &mut world.voxels {
+            voxel.temperature = 20.0;
+        }
+        let rules = PhysicsRules {
+            gravity_enabled: false,
+            solar_heating: 2.0,
+            day_length: 24,
+            ..Default::default()
+        };
+        let mut rng = rand::thread_rng();
+
+        // Let the first day settle the column, then watch the second one
+        for tick in 0..24 {
+            apply_physics(&mut world, &rules, tick, &mut rng);
+        }
+        let (mut surface, mut deep) = (Vec::new(), Vec::new());
+        for tick in 24..48 {
+            apply_physics(&mut world, &rules, tick, &mut rng);
+            surface.push(world.get(0, 0, 7).temperature);
+            deep.push(world.get(0, 0, 0).temperature);
+        }
+        let range = |temperatures: &[f32]| {
+            let max = temperatures.iter().copied().fold(f32::MIN, f32::max);
+            let min = temperatures.iter().copied().fold(f32::MAX, f32::min);
+            (min, max)
+        };
+        let (surface_min, surface_max) = range(&surface);
+        let (deep_min, deep_max) = range(&deep);
+
+        // Warming through the day, cooling again through the night
+        let warmest = surface.iter().position(|&t| t == surface_max).unwrap();
+        assert!(warmest > 0 && warmest < 12, "warmest at hour {}", warmest);
+        assert!(surface_max - surface[0] > 5.0);
+        assert!(surface_max - surface[23] > 5.0);
+        assert!(surface_max - surface_min > 5.0);
+        assert!(deep_max - deep_min < 0.1);
+    }
+
+    #[test]
+    fn degenerate_worlds_do_not_panic() {
+        let rules = PhysicsRules {
+            solar_heating: 1.0,
+            evaporation_rate: 1.0,
+            storms_enabled: true,
+            lightning_chance: 1.0,
+            settle_fully: true,
+            day_length: 4,
+            ..Default::default()
+        };
+        let mut rng = rand::thread_rng();
+        for (width, height, depth) in [(0, 0, 0), (1, 1, 1), (4, 4, 0), (0, 3, 3), (1, 1, 2)] {
+            let mut world = World3D::new(width, height, depth);
+            if let Some(voxel) = world.voxels.first_mut() {
+                voxel.set_material(VoxelMaterial::Water);
+                voxel.temperature = 80.0;
+            }
+            world.refresh_material_counts();
+            for tick in 0..4 {
+                apply_physics(&mut world, &rules, tick, &mut rng);
+            }
+            assert_eq!(world.voxels.len(), (width * height * depth) as usize);
+        }
+    }
+
+    #[test]
+    fn falling_water_settles_lower_without_losing_volume() {
+        // A rock basin one voxel wide with a two-voxel air pocket, and a column
+        // of three water voxels above it
+        let mut world = World3D::new(3, 1, 6);
+        for x in 0..3 {
+            world.set_material(x, 0, 0, VoxelMaterial::Rock);
+        }
+        for z in 1..3 {
+            world.set_material(0, 0, z, VoxelMaterial::Rock);
+            world.set_material(2, 0, z, VoxelMaterial::Rock);
+        }
+        for z in 3..6 {
+            world.set_material(1, 0, z, VoxelMaterial::Water);
+        }
+        let water_heights = |world: &World3D| -> Vec<u32> {
+            let mut heights = Vec::new();
+            for z in 0..world.depth {
+                for x in 0..world.width {
+                    if world.get(x, 0, z).material == VoxelMaterial::Water {
+                        heights.push(z);
+                    }
+                }
+            }
+            heights
+        };
+        assert_eq!(water_heights(&world), vec![3, 4, 5]);
+
+        let mut passes = 0;
+        while apply_simple_gravity(&mut world) {
+            passes += 1;
+            assert!(passes < 20, "water kept moving");
+        }
+        // The pocket fills and the rest rests on top of it
+        assert_eq!(water_heights(&world), vec![1, 2, 3]);
+    }
+
+    // Temperature reached at the far end of a row of six voxels, after
+    // `passes` quick diffusion passes from a source held at 100 degrees at the
+    // other end, through a four-voxel bridge of `bridge`
+    fn far_end_temperature(bridge: VoxelMaterial, passes: usize) -> f32 {
+        let mut world = World3D::new(6, 1, 1);
+        world.set_material(0, 0, 0, VoxelMaterial::Rock);
+        world.set_material(5, 0, 0, VoxelMaterial::Rock);
+        for x in 1..5 {
+            world.set_material(x, 0, 0, bridge);
+        }
+        for voxel in &mut world.voxels {
+            voxel.temperature = 0.0;
+        }
+        let rules = PhysicsRules { heat_diffusion_rate: 0.5, ..Default::default() };
+        for _ in 0..passes {
+            world.get_mut(0, 0, 0).temperature = 100.0;
+            apply_heat_diffusion(&mut world, &rules);
+        }
+        world.get(5, 0, 0).temperature
+    }
+
+    #[test]
+    fn heat_crosses_water_faster_than_rock() {
+        let through_water = far_end_temperature(VoxelMaterial::Water, 20);
+        let through_rock = far_end_temperature(VoxelMaterial::Rock, 20);
+        assert!(through_rock > 0.01, "rock {}", through_rock);
+        assert!(
+            through_water > through_rock * 1.5,
+            "water {} vs rock {}",
+            through_water,
+            through_rock
+        );
+    }
+
+    #[test]
+    fn rock_surrounded_by_sustained_lava_melts() {
+        // A rock voxel at the heart of a lava block kept at 1500 degrees, deep
+        // enough underground not to cool toward the ambient temperature
+        let mut world = World3D::new(3, 3, 3);
+        for idx in 0..world.voxels.len() {
+            world.set_material_at(idx, VoxelMaterial::Lava);
+        }
+        world.set_material(1, 1, 1, VoxelMaterial::Rock);
+        world.get_mut(1, 1, 1).temperature = 20.0;
+        let rules =
+            PhysicsRules { gravity_enabled: false, cooling_rate: 0.0, ..Default::default() };
+        let mut rng = rand::thread_rng();
+
+        let mut melted_at = None;
+        for tick in 0..200 {
+            for voxel in &mut world.voxels {
+                if voxel.material == VoxelMaterial::Lava {
+                    voxel.temperature = 1500.0;
+                }
+            }
+            apply_physics(&mut world, &rules, tick, &mut rng);
+            if world.get(1, 1, 1).material == VoxelMaterial::Lava {
+                melted_at = Some(tick);
+                break;
+            }
+        }
+        let tick = melted_at.expect("the rock never melted");
+        // Not at once: it takes a while to heat past the melting point
+        assert!(tick > 10, "melted at tick {}", tick);
+    }
+
+    // Water voxels left of a one-layer lake on a rock bed after `ticks` ticks
+    // of a heatwave holding everything at `temperature`
+    fn lake_after(temperature: f32, ticks: u64) -> u32 {
+        use rand::SeedableRng;
+
+        let mut world = World3D::new(4, 4, 3);
+        for y in 0..4 {
+            for x in 0..4 {
+                world.set_material(x, y, 0, VoxelMaterial::Rock);
+                world.set_material(x, y, 1, VoxelMaterial::Water);
+            }
+        }
+        let rules = PhysicsRules { evaporation_rate: 0.01, ..Default::default() };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        for tick in 0..ticks {
+            for voxel in &mut world.voxels {
+                voxel.temperature = temperature;
+            }
+            apply_physics(&mut world, &rules, tick, &mut rng);
+        }
+        world.material_count(VoxelMaterial::Water)
+    }
+
+    #[test]
+    fn hot_shallow_lakes_dry_up() {
+        assert_eq!(lake_after(20.0, 100), 16);
+        let hot = lake_after(45.0, 100);
+        assert!(hot < 8, "{} water voxels left", hot);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_diffusion_matches_sequential() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        let mut world = World3D::generate_basic_world(12, 10, 8);
+        for voxel in &mut world.voxels {
+            voxel.temperature = rng.gen_range(-20.0..1500.0);
+        }
+        let rules = PhysicsRules {
+            heat_diffusion_rate: 0.9,
+            diffusion_stencil: DiffusionStencil::Full,
+            boundary: BoundaryCondition::Fixed(10.0),
+            ..Default::default()
+        };
+
+        // The plain loop, one voxel after the other
+        let mut expected: Vec<f32> = world.voxels.iter().map(|v| v.temperature).collect();
+        let substeps = rules.effective_diffusion_substeps();
+        let rate = rules.heat_diffusion_rate / substeps as f32;
+        for _ in 0..substeps {
+            let current = expected.clone();
+            for (idx, temperature) in expected.iter_mut().enumerate() {
+                *temperature = diffused_temperature(&world, &rules, rate, &current, idx);
+            }
+        }
+
+        apply_heat_diffusion(&mut world, &rules);
+        let actual: Vec<f32> = world.voxels.iter().map(|v| v.temperature).collect();
+        assert_eq!(actual, expected);
+    }
+
+    // Millidegrees of the hot voxel and of the whole world after each of
+    // `passes` diffusion passes from a lava pocket in a block of rock
+    #[cfg(feature = "deterministic-physics")]
+    fn fixed_point_trace(passes: usize) -> (Vec<(i32, i64)>, Vec<i32>) {
+        let mut world = World3D::new(6, 6, 6);
+        for idx in 0..world.voxels.len() {
+            world.set_material_at(idx, VoxelMaterial::Rock);
+            world.voxels[idx].temperature = 15.0 + (idx % 7) as f32 * 0.37;
+        }
+        world.set_material(3, 3, 3, VoxelMaterial::Lava);
+        world.get_mut(3, 3, 3).temperature = 1234.567;
+
+        let rules = PhysicsRules::default();
+        let mut trace = Vec::new();
+        for _ in 0..passes {
+            apply_heat_diffusion(&mut world, &rules);
+            let total: i64 =
+                world.voxels.iter().map(|v| to_millidegrees(v.temperature) as i64).sum();
+            trace.push((to_millidegrees(world.get(3, 3, 3).temperature), total));
+        }
+        let temperatures = world.voxels.iter().map(|v| to_millidegrees(v.temperature)).collect();
+        (trace, temperatures)
+    }
+
+    #[cfg(feature = "deterministic-physics")]
+    #[test]
+    fn fixed_point_diffusion_is_repeatable_and_matches_its_golden_trace() {
+        let (trace, temperatures) = fixed_point_trace(5);
+        assert_eq!(fixed_point_trace(5), (trace.clone(), temperatures));
+        let golden = [
+            (1211721, 4697107),
+            (1189375, 4697108),
+            (1167517, 4697110),
+            (1146135, 4697115),
+            (1125219, 4697119),
+        ];
+        assert_eq!(trace, golden);
+    }
+
+    // Hottest voxel on the faces of a 7³ block of rock whose center is held at
+    // `core` degrees for 3000 diffusion passes
+    fn hottest_face_voxel(boundary: BoundaryCondition, core: f32) -> f32 {
+        let mut world = World3D::new(7, 7, 7);
+        for idx in 0..world.voxels.len() {
+            world.set_material_at(idx, VoxelMaterial::Rock);
+            world.voxels[idx].temperature = 0.0;
+        }
+        let rules = PhysicsRules { heat_diffusion_rate: 0.5, boundary, ..Default::default() };
+        for _ in 0..3000 {
+            world.get_mut(3, 3, 3).temperature = core;
+            apply_heat_diffusion(&mut world, &rules);
+        }
+        world
+            .voxels
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| {
+                let (x, y, z) = (idx % 7, idx / 7 % 7, idx / 49);
+                [x, y, z].iter().any(|&c| c == 0 || c == 6)
+            })
+            .map(|(_, voxel)| voxel.temperature)
+            .fold(f32::MIN, f32::max)
+    }
+
+    #[test]
+    fn fixed_edges_stay_cold_around_a_hot_core() {
+        for core in [100.0, 10_000.0] {
+            // Insulated faces end up warming toward the core...
+            let insulated = hottest_face_voxel(BoundaryCondition::Insulated, core);
+            assert!(insulated > core * 0.3, "insulated faces at {} for a {} core", insulated, core);
+            // ...while faces held against a 0 °C reservoir stay at a few percent of it
+            let fixed = hottest_face_voxel(BoundaryCondition::Fixed(0.0), core);
+            assert!(fixed < core * 0.05, "fixed faces at {} for a {} core", fixed, core);
+        }
+    }
+
+    // Temperature difference between two air voxels, 100 °C apart at first,
+    // after each of eight ticks of diffusion at rate 1.5
+    fn air_gap_trace(diffusion_substeps: u32) -> Vec<f32> {
+        let mut world = World3D::new(2, 1, 1);
+        world.get_mut(0, 0, 0).temperature = 100.0;
+        world.get_mut(1, 0, 0).temperature = 0.0;
+        let rules =
+            PhysicsRules { heat_diffusion_rate: 1.5, diffusion_substeps, ..Default::default() };
+        (0..8)
+            .map(|_| {
+                apply_heat_diffusion(&mut world, &rules);
+                world.get(0, 0, 0).temperature - world.get(1, 0, 0).temperature
+            })
+            .collect()
+    }
+
+    #[test]
+    fn substeps_keep_fast_diffusion_from_oscillating() {
+        // In one pass each voxel overshoots the other: the difference flips sign every tick
+        let single = air_gap_trace(1);
+        assert!(single.windows(2).all(|w| w[0] * w[1] < 0.0), "{:?}", single);
+        // Split into passes below the stable rate, it shrinks toward zero without
+        // ever changing sign
+        let split = air_gap_trace(0);
+        assert!(split[0] > 0.0 && split[0] < 100.0, "{:?}", split);
+        assert!(split.windows(2).all(|w| w[1] >= 0.0 && w[1] <= w[0]), "{:?}", split);
+        assert!(split[7] < 0.01, "{:?}", split);
+    }
+
+    // Temperatures of an 8×8 organic plain at 20 °C, one layer of air above it,
+    // after 100 ticks of lightning drawn from `seed`
+    fn struck_plain(storms_enabled: bool, seed: u64) -> Vec<f32> {
+        use rand::SeedableRng;
+
+        let mut world = World3D::new(8, 8, 2);
+        for y in 0..8 {
+            for x in 0..8 {
+                world.set_material(x, y, 0, VoxelMaterial::Organic(50));
+            }
+        }
+        for voxel in &mut world.voxels {
+            voxel.temperature = 20.0;
+        }
+        let rules = PhysicsRules { storms_enabled, ..Default::default() };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        for _ in 0..100 {
+            apply_lightning(&mut world, &rules, &mut rng);
+        }
+        world.voxels.iter().map(|v| v.temperature).collect()
+    }
+
+    #[test]
+    fn seeded_storms_strike_the_surface_reproducibly() {
+        let struck = struck_plain(true, 11);
+        let strikes: f32 = struck.iter().map(|t| (t - 20.0) / LIGHTNING_HEAT).sum();
+        // A 5% chance per tick gives a handful of strikes in 100 ticks
+        assert!((1.0..=15.0).contains(&strikes), "{} strikes", strikes);
+        // Only the organic layer is hit, never the air above it
+        assert!(struck[64..].iter().all(|&t| t == 20.0));
+        assert!(struck[..64].iter().all(|&t| (t - 20.0) % LIGHTNING_HEAT == 0.0));
+        // The same seed strikes the same places; calm weather strikes nowhere
+        assert_eq!(struck_plain(true, 11), struck);
+        assert!(struck_plain(false, 11).iter().all(|&t| t == 20.0));
+    }
+
+    #[test]
+    fn full_settling_drops_a_tall_loose_column_in_one_tick() {
+        // A rock floor, five layers of air, then five loose voxels stacked above them
+        let column = [
+            VoxelMaterial::Soil,
+            VoxelMaterial::Organic(3),
+            VoxelMaterial::Water,
+            VoxelMaterial::Soil,
+            VoxelMaterial::Organic(7),
+        ];
+        let after_one_tick = |settle_fully: bool| {
+            let mut world = World3D::new(1, 1, 11);
+            world.set_material(0, 0, 0, VoxelMaterial::Rock);
+            for (z, &material) in (6..11).zip(&column) {
+                world.set_material(0, 0, z, material);
+            }
+            for voxel in &mut world.voxels {
+                voxel.temperature = 20.0;
+            }
+            let rules = PhysicsRules { settle_fully, ..Default::default() };
+            apply_physics(&mut world, &rules, 0, &mut rand::thread_rng());
+            (0..11).map(|z| world.get(0, 0, z).material).collect::<Vec<_>>()
+        };
+
+        // Settled: the column rests on the floor in its original order
+        let settled = after_one_tick(true);
+        assert_eq!(settled[1..6], column);
+        assert!(settled[6..].iter().all(|&m| m == VoxelMaterial::Air), "{:?}", settled);
+        // A single top-down pass only lets the bottom voxel fall; the rest of the
+        // column stays up
+        let single = after_one_tick(false);
+        assert_eq!(single[1], column[0]);
+        assert_eq!(single[7..], column[1..]);
+    }
+
+    #[test]
+    fn rock_melts_past_its_melting_point_and_sets_again_once_cooled() {
+        let rules = PhysicsRules::default();
+        let mut world = World3D::new(1, 1, 1);
+        world.set_material(0, 0, 0, VoxelMaterial::Rock);
+        let phase_at = |world: &mut World3D, temperature: f32| {
+            world.get_mut(0, 0, 0).temperature = temperature;
+            apply_phase_transitions(world, &rules);
+            let voxel = world.get(0, 0, 0);
+            assert_eq!(voxel.density, voxel.material.default_density());
+            voxel.material
+        };
+
+        assert_eq!(phase_at(&mut world, rules.rock_melting_point - 1.0), VoxelMaterial::Rock);
+        assert_eq!(phase_at(&mut world, rules.rock_melting_point + 100.0), VoxelMaterial::Lava);
+        // Lava only sets below its solidifying point, under the melting point
+        let between = (rules.rock_melting_point + rules.lava_solidifying_point) / 2.0;
+        assert_eq!(phase_at(&mut world, between), VoxelMaterial::Lava);
+        let cooled = rules.lava_solidifying_point - 100.0;
+        assert_eq!(phase_at(&mut world, cooled), VoxelMaterial::Rock);
+    }
+
+    #[test]
+    fn water_heats_more_slowly_than_air_under_the_same_gradient() {
+        // A lone voxel at 0 degrees, every face held at 100 degrees
+        let rules = PhysicsRules {
+            boundary: BoundaryCondition::Fixed(100.0),
+            ..Default::default()
+        };
+        let warmed = |material: VoxelMaterial| {
+            let mut world = World3D::new(1, 1, 1);
+            world.set_material(0, 0, 0, material);
+            world.get_mut(0, 0, 0).temperature = 0.0;
+            (0..5)
+                .map(|_| {
+                    apply_heat_diffusion(&mut world, &rules);
+                    world.get(0, 0, 0).temperature
+                })
+                .collect::<Vec<f32>>()
+        };
+
+        let water = warmed(VoxelMaterial::Water);
+        let air = warmed(VoxelMaterial::Air);
+        for (w, a) in water.iter().zip(&air) {
+            assert!(0.0 < *w && w < a && *a <= 100.0, "water {:?}, air {:?}", water, air);
+        }
+    }
 }
@@ -1,123 +1,1360 @@
-use crate::god::GodAction;
-use crate::time_sim::SimulationState;
-use crate::world3d::VoxelMaterial;
+use crate::biology::Diet;
+use crate::civilization::Civilization;
+use crate::events::SimEvent;
+use crate::god::{CatastropheKind, GodAction};
+use crate::time_sim::{SimulationState, Timeline, TimelineInfo};
+use crate::world3d::{TempStats, Voxel, VoxelMaterial, MATERIAL_KINDS};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
-pub fn print_summary(tick: u64, state: &SimulationState, last_god_action: &GodAction) {
-    println!("\n========== TICK {} ==========", tick);
+// Destination for rendered text, one line at a time
+pub trait RenderSink {
+    fn write_line(&mut self, line: &str);
+}
+
+// Writes straight to the terminal
+pub struct StdoutSink;
+
+impl RenderSink for StdoutSink {
+    fn write_line(&mut self, line: &str) {
+        println!("{}", line);
+    }
+}
+
+// Collects the lines, e.g. to inspect or save them later
+impl RenderSink for Vec<String> {
+    fn write_line(&mut self, line: &str) {
+        self.push(line.to_string());
+    }
+}
+
+pub fn print_summary(tick: u64, state: &SimulationState, last_god_actions: &[GodAction]) {
+    write_summary(&mut StdoutSink, tick, state, last_god_actions);
+}
+
+pub fn write_summary(
+    sink: &mut dyn RenderSink,
+    tick: u64,
+    state: &SimulationState,
+    last_god_actions: &[GodAction],
+) {
+    write_summary_with_delta(sink, tick, state, last_god_actions, None);
+}
+
+fn write_summary_with_delta(
+    sink: &mut dyn RenderSink,
+    tick: u64,
+    state: &SimulationState,
+    last_god_actions: &[GodAction],
+    delta: Option<&SummaryDelta>,
+) {
+    sink.write_line("");
+    sink.write_line(&format!("========== TICK {} ==========", tick));
+
+    let metrics = collect_metrics(state);
 
     // Civilizations
-    let num_civs = state.civilizations.len();
-    println!("Civilizations: {}", num_civs);
+    let num_civs = metrics.civ_count;
+    sink.write_line(&format!("Civilizations: {}", num_civs));
 
     if num_civs > 0 {
-        let avg_tech: f32 =
-            state.civilizations.iter().map(|c| c.tech_level).sum::<f32>() / num_civs as f32;
-        let total_civ_pop: u32 = state.civilizations.iter().map(|c| c.population).sum();
-        println!("  Avg Tech Level: {:.2}", avg_tech);
-        println!("  Total Civ Population: {}", total_civ_pop);
+        sink.write_line(&format!("  Avg Tech Level: {:.2}", metrics.avg_tech));
+        sink.write_line(&format!("  Total Civ Population: {}", metrics.civ_population));
 
         for civ in state.civilizations.iter().take(3) {
-            println!(
+            sink.write_line(&format!(
                 "  - {} at ({},{},{}) pop:{} tech:{:.2} agg:{:.2} spirit:{:.2}",
                 civ.name, civ.x, civ.y, civ.z, civ.population, civ.tech_level, civ.aggression, civ.spirituality
-            );
+            ));
         }
         if num_civs > 3 {
-            println!("  ... and {} more", num_civs - 3);
+            sink.write_line(&format!("  ... and {} more", num_civs - 3));
         }
     }
 
     // Biology
-    let num_pops = state.populations.len();
-    let total_biomass: u32 = state.populations.iter().map(|p| p.size).sum();
-    println!("Populations: {} (Total Biomass: {})", num_pops, total_biomass);
-
-    // God
-    println!(
-        "God State: curiosity:{:.2} benevolence:{:.2} cruelty:{:.2} boredom:{:.2}",
-        state.god_state.curiosity,
-        state.god_state.benevolence,
-        state.god_state.cruelty,
-        state.god_state.boredom
-    );
-    println!("Last God Action: {:?}", last_god_action);
+    sink.write_line(&format!(
+        "Populations: {} (Total Biomass: {})",
+        metrics.population_count, metrics.total_biomass
+    ));
+
+    // Gods
+    for (i, god) in state.gods.iter().enumerate() {
+        let label = if state.gods.len() > 1 {
+            format!("God #{}", i)
+        } else {
+            "God".to_string()
+        };
+        sink.write_line(&format!(
+            "{} State: curiosity:{:.2} benevolence:{:.2} cruelty:{:.2} boredom:{:.2}",
+            label, god.curiosity, god.benevolence, god.cruelty, god.boredom
+        ));
+        match last_god_actions.get(i) {
+            Some(action) => sink.write_line(&format!("Last {} Action: {:?}", label, action)),
+            None => sink.write_line(&format!("Last {} Action: None", label)),
+        }
+    }
 
     // Physics
-    println!(
+    sink.write_line(&format!(
         "Physics: heat_diff:{:.3} cooling:{:.3}",
         state.physics_rules.heat_diffusion_rate, state.physics_rules.cooling_rate
-    );
+    ));
 
-    println!("==============================\n");
+    // Trend since the previous report
+    if let Some(delta) = delta {
+        sink.write_line(&format!(
+            "Since last report: {:+} civ pop, {:+} biomass, {:+.2} avg tech, climate {}",
+            delta.civ_population,
+            delta.biomass,
+            delta.avg_tech,
+            trend_arrow(delta.climate_stability)
+        ));
+    }
+
+    sink.write_line("==============================");
+    sink.write_line("");
 }
 
-pub fn print_world_slice(state: &SimulationState, z_level: u32) {
-    if z_level >= state.world.depth {
-        println!("Invalid z level: {}", z_level);
-        return;
+// Headline numbers of a state as plain data, with no printing: the printed
+// reports, the gods' world summary and timeline comparisons all build on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldMetrics {
+    pub tick: u64,
+    pub civ_count: usize,
+    pub civ_population: u64,
+    // 0 without civilizations
+    pub avg_tech: f32,
+    pub population_count: usize,
+    pub total_biomass: u64,
+    // Voxels of each material, in VoxelMaterial::KINDS order
+    pub materials: [u32; MATERIAL_KINDS],
+    pub temperature: TempStats,
+    // 1 for a uniform temperature, falling toward 0 as its variance grows
+    pub climate_stability: f32,
+    // See World3D::mean_surface_fertility
+    pub surface_fertility: f32,
+}
+
+pub fn collect_metrics(state: &SimulationState) -> WorldMetrics {
+    let civ_count = state.civilizations.len();
+    let avg_tech = if civ_count > 0 {
+        state.civilizations.iter().map(|c| c.tech_level).sum::<f32>() / civ_count as f32
+    } else {
+        0.0
+    };
+    let temperature = state.world.temperature_stats();
+
+    WorldMetrics {
+        tick: state.tick,
+        civ_count,
+        civ_population: state.civilizations.iter().map(|c| c.population as u64).sum(),
+        avg_tech,
+        population_count: state.populations.len(),
+        total_biomass: state.populations.iter().map(|p| p.size as u64).sum(),
+        materials: VoxelMaterial::KINDS.map(|material| state.world.material_count(material)),
+        temperature,
+        climate_stability: 1.0 / (1.0 + temperature.variance / 100.0),
+        surface_fertility: state.world.mean_surface_fertility(),
+    }
+}
+
+// A compact row of metrics for one tick, small enough to keep for every tick
+// of a run (unlike whole states)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricRow {
+    pub tick: u64,
+    pub total_biomass: u64,
+    pub civ_count: u32,
+    pub civ_population: u64,
+    pub avg_tech: f32,
+    pub avg_temperature: f32,
+    pub climate_stability: f32,
+    pub surface_fertility: f32,
+}
+
+impl From<&WorldMetrics> for MetricRow {
+    fn from(metrics: &WorldMetrics) -> Self {
+        Self {
+            tick: metrics.tick,
+            total_biomass: metrics.total_biomass,
+            civ_count: metrics.civ_count as u32,
+            civ_population: metrics.civ_population,
+            avg_tech: metrics.avg_tech,
+            avg_temperature: metrics.temperature.mean,
+            climate_stability: metrics.climate_stability,
+            surface_fertility: metrics.surface_fertility,
+        }
+    }
+}
+
+// Time series of MetricRows, one per recorded state, e.g. for plotting a run.
+// Recording the initial state too makes ticks + 1 rows for a run of `ticks`.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRecorder {
+    rows: Vec<MetricRow>,
+}
+
+impl MetricsRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, state: &SimulationState) {
+        self.rows.push(MetricRow::from(&collect_metrics(state)));
+    }
+
+    pub fn rows(&self) -> &[MetricRow] {
+        &self.rows
+    }
+
+    pub fn into_rows(self) -> Vec<MetricRow> {
+        self.rows
+    }
+
+    // One CSV line per recorded row, under a header line
+    pub fn to_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(
+            out,
+            "tick,total_biomass,civ_count,civ_population,avg_tech,avg_temperature,\
+             climate_stability,surface_fertility"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                row.tick,
+                row.total_biomass,
+                row.civ_count,
+                row.civ_population,
+                row.avg_tech,
+                row.avg_temperature,
+                row.climate_stability,
+                row.surface_fertility
+            )?;
+        }
+        out.flush()
+    }
+}
+
+pub struct SummaryDelta {
+    pub civ_population: i64,
+    pub biomass: i64,
+    pub avg_tech: f32,
+    pub climate_stability: f32,
+}
+
+pub fn compute_summary_delta(
+    previous: &SimulationState,
+    current: &SimulationState,
+) -> SummaryDelta {
+    let prev = collect_metrics(previous);
+    let curr = collect_metrics(current);
+
+    SummaryDelta {
+        civ_population: curr.civ_population as i64 - prev.civ_population as i64,
+        biomass: curr.total_biomass as i64 - prev.total_biomass as i64,
+        avg_tech: curr.avg_tech - prev.avg_tech,
+        climate_stability: curr.climate_stability - prev.climate_stability,
     }
+}
+
+fn trend_arrow(delta: f32) -> &'static str {
+    const EPSILON: f32 = 1e-4;
+    if delta > EPSILON {
+        "↑"
+    } else if delta < -EPSILON {
+        "↓"
+    } else {
+        "→"
+    }
+}
 
-    println!("\n--- World Slice at Z={} ---", z_level);
+// Same as print_summary, plus the change since the previously printed state
+pub fn print_summary_delta(
+    tick: u64,
+    previous: &SimulationState,
+    state: &SimulationState,
+    last_god_actions: &[GodAction],
+) {
+    write_summary_delta(&mut StdoutSink, tick, previous, state, last_god_actions);
+}
+
+pub fn write_summary_delta(
+    sink: &mut dyn RenderSink,
+    tick: u64,
+    previous: &SimulationState,
+    state: &SimulationState,
+    last_god_actions: &[GodAction],
+) {
+    let delta = compute_summary_delta(previous, state);
+    write_summary_with_delta(sink, tick, state, last_god_actions, Some(&delta));
+}
 
-    for y in (0..state.world.height).rev() {
-        for x in 0..state.world.width {
-            let voxel = state.world.get(x, y, z_level);
-            let char = match voxel.material {
-                VoxelMaterial::Air => '.',
-                VoxelMaterial::Rock => '#',
-                VoxelMaterial::Soil => ':',
-                VoxelMaterial::Water => '~',
-                VoxelMaterial::Lava => '*',
-                VoxelMaterial::Ice => 'i',
-                VoxelMaterial::Organic(_) => 'o',
+pub fn print_timeline_table(timelines: &[TimelineInfo]) {
+    write_timeline_table(&mut StdoutSink, timelines);
+}
+
+pub fn write_timeline_table(sink: &mut dyn RenderSink, timelines: &[TimelineInfo]) {
+    sink.write_line(&format!(
+        "{:>4}  {:>6}  {:>9}  {:>7}  {:>5}  {:>10}",
+        "id", "parent", "fork tick", "length", "civs", "biomass"
+    ));
+    for info in timelines {
+        let parent = match info.parent_id {
+            Some(id) => id.to_string(),
+            None => "-".to_string(),
+        };
+        sink.write_line(&format!(
+            "{:>4}  {:>6}  {:>9}  {:>7}  {:>5}  {:>10}",
+            info.id, parent, info.fork_tick, info.length, info.civ_count, info.biomass
+        ));
+    }
+}
+
+// One CSV row per civilization alive at each tick of `timeline`, with the
+// fertility of the soil it stands on; a collapsed civilization simply has no
+// rows after its collapse
+pub fn write_civ_timeseries_csv(timeline: &Timeline, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "tick,civ_id,population,tech_level,aggression,spirituality,at_war,fertility")?;
+    for tick in timeline.first_index..timeline.len() {
+        let state = match timeline.get_state(tick) {
+            Some(state) => state,
+            None => continue,
+        };
+        for civ in &state.civilizations {
+            let at_war = state
+                .civilizations
+                .iter()
+                .any(|other| other.id != civ.id && civ.is_hostile_to(other, &state.world));
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                tick,
+                civ.id,
+                civ.population,
+                civ.tech_level,
+                civ.aggression,
+                civ.spirituality,
+                at_war,
+                state.world.get(civ.x, civ.y, civ.z).fertility
+            )?;
+        }
+    }
+    out.flush()
+}
+
+// A chronicle of the tick: one short sentence per event, e.g. "The God, grown
+// bored, blessed the Astraians #3". Names and places are looked up in `state`.
+pub fn narrate(events: &[SimEvent], state: &SimulationState) -> Vec<String> {
+    let civ_name = |id: u32| match state.civilizations.iter().find(|c| c.id == id) {
+        Some(civ) => format!("the {}", civ.name),
+        None => format!("civilization #{}", id),
+    };
+
+    events
+        .iter()
+        .filter_map(|event| match event {
+            SimEvent::GodIntervention { god, action, reason, outcome } => {
+                let who = if *god == 0 { "The God".to_string() } else { format!("God #{}", god) };
+                let mood = match reason.split(',').next().unwrap_or("") {
+                    "bored" => ", grown bored,",
+                    "benevolent" => ", in a benevolent mood,",
+                    "curious" => ", out of curiosity,",
+                    cause if cause.starts_with("cruel") => ", in its cruelty,",
+                    _ => "",
+                };
+                let effect = match outcome {
+                    Ok(effect) => effect,
+                    Err(err) => {
+                        return Some(format!("{}{} tried to intervene, but {}", who, mood, err))
+                    }
+                };
+                let deed = match action {
+                    GodAction::BlessCivilization { civ_id, .. } => {
+                        format!("blessed {}", civ_name(*civ_id))
+                    }
+                    GodAction::AnswerPrayer { civ_id } => {
+                        format!("answered the prayers of {}", civ_name(*civ_id))
+                    }
+                    GodAction::ChangePhysics(_) => "reshaped the laws of nature".to_string(),
+                    GodAction::SpawnCatastrophe { kind, x, y, .. } => {
+                        let toll = match effect.population_killed {
+                            0 => String::new(),
+                            killed => format!(", killing {}", killed),
+                        };
+                        let region = compass_region(state, *x, *y);
+                        match kind {
+                            CatastropheKind::Meteor => format!("devastated {}{}", region, toll),
+                            CatastropheKind::Plague => {
+                                format!("sent a plague upon {}{}", region, toll)
+                            }
+                            CatastropheKind::Flood => format!("flooded {}{}", region, toll),
+                        }
+                    }
+                    GodAction::RaiseSeaLevel { layers } => {
+                        let toll = match effect.population_killed {
+                            0 => String::new(),
+                            killed => format!(", drowning {}", killed),
+                        };
+                        format!("raised the seas by {} layers{}", layers, toll)
+                    }
+                    GodAction::Resurrect { x, y, size, .. } => {
+                        let region = compass_region(state, *x, *y);
+                        format!("breathed life back into {} ({} creatures)", region, size)
+                    }
+                    GodAction::None => return None,
+                };
+                Some(format!("{}{} {}", who, mood, deed))
+            }
+            SimEvent::MissingSpecies { species_id, population_lost } => Some(format!(
+                "{} creatures of the unknown species #{} vanished",
+                population_lost, species_id
+            )),
+            SimEvent::Speciation { parent_id, species_id, x, y, .. } => Some(format!(
+                "A new species (#{}) branched off species #{} in {}",
+                species_id,
+                parent_id,
+                compass_region(state, *x, *y)
+            )),
+            SimEvent::SeaLevelRise { sea_level, voxels_flooded, population_lost } => {
+                let toll = match population_lost {
+                    0 => String::new(),
+                    lost => format!(", drowning {}", lost),
+                };
+                Some(format!(
+                    "The sea rose to layer {}, flooding {} voxels{}",
+                    sea_level, voxels_flooded, toll
+                ))
+            }
+        })
+        .collect()
+}
+
+// "the north-eastern lands", "the heartland"... (north is +y, east is +x)
+fn compass_region(state: &SimulationState, x: u32, y: u32) -> String {
+    let (width, height, _) = state.world.dimensions();
+    let third = |v: u32, size: u32| (v as u64 * 3 / size.max(1) as u64).min(2);
+    let north_south = ["south", "", "north"][third(y, height) as usize];
+    let east_west = ["west", "", "east"][third(x, width) as usize];
+    match (north_south, east_west) {
+        ("", "") => "the heartland".to_string(),
+        (ns, "") | ("", ns) => format!("the {}ern lands", ns),
+        (ns, ew) => format!("the {}-{}ern lands", ns, ew),
+    }
+}
+
+fn material_glyph(material: VoxelMaterial) -> char {
+    match material {
+        VoxelMaterial::Air => '.',
+        VoxelMaterial::Rock => '#',
+        VoxelMaterial::Soil => ':',
+        VoxelMaterial::Water => '~',
+        VoxelMaterial::Lava => '*',
+        VoxelMaterial::Ice => 'i',
+        VoxelMaterial::Organic(_) => 'o',
+    }
+}
+
+// Shared color map for image exports
+pub fn material_color(material: VoxelMaterial) -> [u8; 3] {
+    match material {
+        VoxelMaterial::Air => [20, 24, 40],
+        VoxelMaterial::Rock => [110, 110, 110],
+        VoxelMaterial::Soil => [140, 100, 60],
+        VoxelMaterial::Water => [40, 90, 200],
+        VoxelMaterial::Lava => [230, 80, 20],
+        VoxelMaterial::Ice => [200, 230, 250],
+        VoxelMaterial::Organic(density) => [30, 120u8.saturating_add(density / 2), 40],
+    }
+}
+
+// Temperatures over which hot voxels start to glow, and reach full glow
+const GLOW_START: f32 = 100.0;
+const GLOW_FULL: f32 = 1000.0;
+
+// Soil fertility mapped to the full tint (pale barren soil to dark loam)
+const FERTILITY_TINT_FULL: f32 = 1.5;
+
+// Material color, shifted toward a red-orange glow as the voxel heats past
+// GLOW_START; cooler voxels keep their plain material color. Soil is tinted
+// by its fertility, from pale (barren) to dark (rich).
+pub fn voxel_color(voxel: &Voxel) -> [u8; 3] {
+    const GLOW: [u8; 3] = [255, 110, 30];
+    const BARREN: [u8; 3] = [190, 160, 120];
+    const RICH: [u8; 3] = [70, 50, 25];
+    let base = if voxel.material == VoxelMaterial::Soil {
+        let richness = (voxel.fertility / FERTILITY_TINT_FULL).clamp(0.0, 1.0);
+        blend_color(BARREN, RICH, richness)
+    } else {
+        material_color(voxel.material)
+    };
+    let glow = ((voxel.temperature - GLOW_START) / (GLOW_FULL - GLOW_START)).clamp(0.0, 1.0);
+    blend_color(base, GLOW, glow)
+}
+
+// `from` moved a fraction `t` (0 to 1) of the way toward `to`
+fn blend_color(from: [u8; 3], to: [u8; 3], t: f32) -> [u8; 3] {
+    let mut color = [0; 3];
+    for (channel, (&from, &to)) in color.iter_mut().zip(from.iter().zip(&to)) {
+        *channel = (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    }
+    color
+}
+
+// One pixel per voxel of the z-level, north (high y) at the top like the text
+// slices, with hot voxels glowing (see `voxel_color`). A z-level outside the
+// world is a dimension mismatch error.
+#[cfg(feature = "image")]
+pub fn render_slice_to_png(
+    state: &SimulationState,
+    z_level: u32,
+    path: impl AsRef<std::path::Path>,
+) -> image::ImageResult<()> {
+    use image::error::{ImageError, ParameterError, ParameterErrorKind};
+
+    let world = &state.world;
+    if z_level >= world.depth {
+        let kind = ParameterErrorKind::DimensionMismatch;
+        return Err(ImageError::Parameter(ParameterError::from_kind(kind)));
+    }
+    let image = image::RgbImage::from_fn(world.width, world.height, |x, row| {
+        let y = world.height - 1 - row;
+        image::Rgb(voxel_color(world.get(x, y, z_level)))
+    });
+    image.save(path)
+}
+
+// Every z-level to `dir/slice_{z:03}.png`, rendered in parallel: a flipbook of the world
+#[cfg(feature = "image")]
+pub fn render_all_slices_to_pngs(
+    state: &SimulationState,
+    dir: impl AsRef<std::path::Path>,
+) -> image::ImageResult<()> {
+    use rayon::prelude::*;
+
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    (0..state.world.depth).into_par_iter().try_for_each(|z| {
+        render_slice_to_png(state, z, dir.join(format!("slice_{:03}.png", z)))
+    })
+}
+
+// Top-down view: each (x, y) shows the material of its topmost non-air voxel.
+// With `shade_by_height`, dry land shows its surface height as a digit 0-9
+// (relative to the world depth) instead, while water and empty columns keep their glyph.
+pub fn surface_map_lines(state: &SimulationState, shade_by_height: bool) -> Vec<String> {
+    let world = &state.world;
+    let mut lines = Vec::with_capacity(world.height as usize);
+
+    for y in (0..world.height).rev() {
+        let mut line = String::with_capacity(world.width as usize);
+        for x in 0..world.width {
+            let glyph = match world.surface_height_at(x, y) {
+                Some(z) => {
+                    let material = world.get(x, y, z).material;
+                    if shade_by_height && material != VoxelMaterial::Water {
+                        let level = z * 10 / world.depth.max(1);
+                        char::from_digit(level.min(9), 10).unwrap_or('9')
+                    } else {
+                        material_glyph(material)
+                    }
+                }
+                None => material_glyph(VoxelMaterial::Air),
             };
-            print!("{}", char);
+            line.push(glyph);
         }
-        println!();
+        lines.push(line);
     }
-    println!("----------------------------\n");
+
+    lines
 }
 
-pub fn print_detailed_report(state: &SimulationState) {
-    println!("\n========== DETAILED REPORT ==========");
+pub fn print_surface_map(state: &SimulationState, shade_by_height: bool) {
+    write_surface_map(&mut StdoutSink, state, shade_by_height);
+}
 
-    // World statistics
-    let mut material_counts = std::collections::HashMap::new();
-    let mut temp_sum = 0.0;
+pub fn write_surface_map(
+    sink: &mut dyn RenderSink,
+    state: &SimulationState,
+    shade_by_height: bool,
+) {
+    sink.write_line("");
+    sink.write_line("--- Surface Map ---");
+    for line in surface_map_lines(state, shade_by_height) {
+        sink.write_line(&line);
+    }
+    sink.write_line("-------------------");
+    sink.write_line("");
+}
+
+// Shading of column sides in the isometric view, from the ground up
+const RELIEF_RAMP: &[u8] = b"_-=+xX&%";
+
+// Isometric projection of the surface: column (x, y) with its top at height z
+// lands at screen column x - y (shifted to start at 0) and row (x + y) / 2 - z,
+// so +x runs down-right, +y up-right and height straight up. Columns are
+// painted back to front; each shows its top voxel's material glyph above
+// sides shaded by height. Lines are cut at `max_width` characters.
+pub fn isometric_lines(state: &SimulationState, max_width: usize) -> Vec<String> {
+    let world = &state.world;
+    let (width, height, depth) = world.dimensions();
+    let (width, height, depth) = (width as usize, height as usize, depth as usize);
+    if width == 0 || height == 0 || depth == 0 {
+        return Vec::new();
+    }
+
+    let columns = width + height - 1;
+    let rows = (width + height - 2) / 2 + depth;
+    let mut canvas = vec![vec![' '; columns]; rows];
+    let mut order: Vec<(usize, usize)> =
+        (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).collect();
+    order.sort_by_key(|&(x, y)| x + y);
+
+    for (x, y) in order {
+        let top = match world.surface_height_at(x as u32, y as u32) {
+            Some(z) => z as usize,
+            None => continue,
+        };
+        let column = x + (height - 1) - y;
+        let ground_row = (x + y) / 2 + depth - 1;
+        for z in 0..=top {
+            let glyph = if z == top {
+                material_glyph(world.get(x as u32, y as u32, z as u32).material)
+            } else {
+                RELIEF_RAMP[z * RELIEF_RAMP.len() / depth] as char
+            };
+            canvas[ground_row - z][column] = glyph;
+        }
+    }
+
+    canvas
+        .into_iter()
+        .map(|row| row.into_iter().take(max_width).collect::<String>().trim_end().to_string())
+        .collect()
+}
+
+pub fn print_isometric(state: &SimulationState) {
+    write_isometric(&mut StdoutSink, state);
+}
+
+// Isometric view cut to the terminal width (COLUMNS, 80 if unset)
+pub fn write_isometric(sink: &mut dyn RenderSink, state: &SimulationState) {
+    let max_width = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).unwrap_or(80);
+    sink.write_line("");
+    sink.write_line("--- Isometric View ---");
+    for line in isometric_lines(state, max_width) {
+        sink.write_line(&line);
+    }
+    sink.write_line("----------------------");
+    sink.write_line("");
+}
+
+// Axis a world slice is cut across: Z slices are horizontal layers (viewed from
+// above, north up), X and Y slices are vertical cross-sections (sky up)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    // (position along the axis, slice column, slice row) of a voxel
+    fn project(self, x: u32, y: u32, z: u32) -> (u32, u32, u32) {
+        match self {
+            Axis::X => (x, y, z),
+            Axis::Y => (y, x, z),
+            Axis::Z => (z, x, y),
+        }
+    }
+
+    // Voxel at `column` and `row` of the slice at `index`
+    fn unproject(self, index: u32, column: u32, row: u32) -> (u32, u32, u32) {
+        match self {
+            Axis::X => (index, column, row),
+            Axis::Y => (column, index, row),
+            Axis::Z => (column, row, index),
+        }
+    }
+
+    fn name(self) -> char {
+        match self {
+            Axis::X => 'X',
+            Axis::Y => 'Y',
+            Axis::Z => 'Z',
+        }
+    }
+}
+
+pub fn print_world_slice(state: &SimulationState, z_level: u32, show_life: bool) {
+    write_world_slice(&mut StdoutSink, state, z_level, show_life);
+}
+
+pub fn print_world_slice_axis(state: &SimulationState, axis: Axis, index: u32, show_life: bool) {
+    write_world_slice_axis(&mut StdoutSink, state, axis, index, show_life);
+}
+
+pub fn print_world_slice_scaled(
+    state: &SimulationState,
+    z_level: u32,
+    show_life: bool,
+    max_cols: Option<u32>,
+    max_rows: Option<u32>,
+) {
+    write_world_slice_scaled(&mut StdoutSink, state, z_level, show_life, max_cols, max_rows);
+}
+
+// Marker for the combined population size on a voxel
+fn population_glyph(size: u32) -> char {
+    match size {
+        0..=99 => '+',
+        100..=999 => '%',
+        _ => '@',
+    }
+}
+
+// With `show_life`, voxels holding populations show a marker scaled by their
+// combined size, and civilizations show the uppercase initial of their name at
+// their center and the lowercase initial over the rest of their footprint
+// (a civilization wins over populations on the same voxel)
+pub fn write_world_slice(
+    sink: &mut dyn RenderSink,
+    state: &SimulationState,
+    z_level: u32,
+    show_life: bool,
+) {
+    write_world_slice_scaled(sink, state, z_level, show_life, None, None);
+}
+
+pub fn write_world_slice_axis(
+    sink: &mut dyn RenderSink,
+    state: &SimulationState,
+    axis: Axis,
+    index: u32,
+    show_life: bool,
+) {
+    write_world_slice_axis_scaled(sink, state, axis, index, show_life, None, None);
+}
+
+// Precedence of overlay markers when several share a downsampled block
+fn overlay_rank(marker: char) -> u8 {
+    match marker {
+        c if c.is_ascii_uppercase() => 4,
+        c if c.is_ascii_lowercase() => 3,
+        '@' => 2,
+        '%' => 1,
+        _ => 0,
+    }
+}
+
+// Same slice, shrunk to at most `max_cols` x `max_rows` characters when the
+// world is larger: each character then stands for a block of voxels and shows
+// its most common material, or its most notable life marker if it has any
+pub fn write_world_slice_scaled(
+    sink: &mut dyn RenderSink,
+    state: &SimulationState,
+    z_level: u32,
+    show_life: bool,
+    max_cols: Option<u32>,
+    max_rows: Option<u32>,
+) {
+    write_world_slice_axis_scaled(sink, state, Axis::Z, z_level, show_life, max_cols, max_rows);
+}
+
+// Slice across any axis, with the same glyphs, markers and downsampling
+pub fn write_world_slice_axis_scaled(
+    sink: &mut dyn RenderSink,
+    state: &SimulationState,
+    axis: Axis,
+    index: u32,
+    show_life: bool,
+    max_cols: Option<u32>,
+    max_rows: Option<u32>,
+) {
+    let (width, height, depth) = state.world.dimensions();
+    let (length, width, height) = axis.project(width, height, depth);
+    if index >= length {
+        let name = axis.name().to_ascii_lowercase();
+        sink.write_line(&format!("Invalid {} level: {}", name, index));
+        return;
+    }
+
+    let mut overlay: HashMap<(u32, u32), char> = HashMap::new();
+    if show_life {
+        let mut biomass: HashMap<(u32, u32), u32> = HashMap::new();
+        for pop in &state.populations {
+            let (level, column, row) = axis.project(pop.x, pop.y, pop.z);
+            if level == index {
+                *biomass.entry((column, row)).or_insert(0) += pop.size;
+            }
+        }
+        for (position, size) in biomass {
+            overlay.insert(position, population_glyph(size));
+        }
+        let initial = |civ: &Civilization| civ.name.chars().next().unwrap_or('C');
+        for civ in &state.civilizations {
+            let radius = civ.footprint_radius() as u32;
+            let (_, column, row) = axis.project(civ.x, civ.y, civ.z);
+            let columns = column.saturating_sub(radius)..=column.saturating_add(radius);
+            let rows = row.saturating_sub(radius)..=row.saturating_add(radius);
+            for column in columns.filter(|&c| c < width) {
+                for row in rows.clone().filter(|&r| r < height) {
+                    let (x, y, z) = axis.unproject(index, column, row);
+                    if civ.covers(x, y, z, &state.world) {
+                        overlay.insert((column, row), initial(civ).to_ascii_lowercase());
+                    }
+                }
+            }
+        }
+        for civ in &state.civilizations {
+            let (level, column, row) = axis.project(civ.x, civ.y, civ.z);
+            if level == index {
+                overlay.insert((column, row), initial(civ).to_ascii_uppercase());
+            }
+        }
+    }
+
+    sink.write_line("");
+    sink.write_line(&format!("--- World Slice at {}={} ---", axis.name(), index));
 
-    for voxel in &state.world.voxels {
-        *material_counts
-            .entry(format!("{:?}", voxel.material))
-            .or_insert(0) += 1;
-        temp_sum += voxel.temperature;
+    // Voxels per character along each axis (1 when the world fits)
+    let block = |size: u32, max: Option<u32>| match max {
+        Some(max) if max > 0 && size > max => (size - 1) / max + 1,
+        _ => 1,
+    };
+    let block_x = block(width, max_cols);
+    let block_y = block(height, max_rows);
+    let glyph_at = |bx: u32, by: u32| {
+        let columns = bx * block_x..((bx + 1) * block_x).min(width);
+        let rows = by * block_y..((by + 1) * block_y).min(height);
+        let mut marker: Option<char> = None;
+        let mut counts: Vec<(char, u32)> = Vec::new();
+        for row in rows {
+            for column in columns.clone() {
+                match (overlay.get(&(column, row)), marker) {
+                    (Some(&m), Some(best)) if overlay_rank(m) <= overlay_rank(best) => {}
+                    (Some(&m), _) => marker = Some(m),
+                    (None, _) => {}
+                }
+                let (x, y, z) = axis.unproject(index, column, row);
+                let glyph = material_glyph(state.world.get(x, y, z).material);
+                match counts.iter_mut().find(|(g, _)| *g == glyph) {
+                    Some((_, n)) => *n += 1,
+                    None => counts.push((glyph, 1)),
+                }
+            }
+        }
+        // Ties go to the material seen first
+        let majority = counts.iter().rev().max_by_key(|&&(_, n)| n).map_or(' ', |&(g, _)| g);
+        marker.unwrap_or(majority)
+    };
+
+    // Partial blocks at the far edges still get a character
+    let blocks = |size: u32, block: u32| if size == 0 { 0 } else { (size - 1) / block + 1 };
+    let columns = blocks(width, block_x);
+    let rows = blocks(height, block_y);
+    for by in (0..rows).rev() {
+        let line: String = (0..columns).map(|bx| glyph_at(bx, by)).collect();
+        sink.write_line(&line);
     }
+    sink.write_line("----------------------------");
+    sink.write_line("");
+}
+
+pub fn print_detailed_report(state: &SimulationState) {
+    write_detailed_report(&mut StdoutSink, state);
+}
+
+pub fn write_detailed_report(sink: &mut dyn RenderSink, state: &SimulationState) {
+    sink.write_line("");
+    sink.write_line("========== DETAILED REPORT ==========");
 
-    let avg_temp = temp_sum / state.world.voxels.len() as f32;
+    // World statistics
+    let metrics = collect_metrics(state);
+    let temps = metrics.temperature;
 
-    println!("World: {}x{}x{}", state.world.width, state.world.height, state.world.depth);
-    println!("Average Temperature: {:.2}°C", avg_temp);
-    println!("Material Distribution:");
-    for (material, count) in material_counts {
-        println!("  {}: {}", material, count);
+    let (width, height, depth) = state.world.dimensions();
+    sink.write_line(&format!("World: {}x{}x{}", width, height, depth));
+    sink.write_line(&format!(
+        "Temperature: avg {:.2}°C, min {:.2}°C, max {:.2}°C",
+        temps.mean, temps.min, temps.max
+    ));
+    let stats = state.world.world_stats(4);
+    sink.write_line(&format!(
+        "Surface: {:.1}% land, {:.1}% water, {:.1}% ice, {} habitable voxels",
+        stats.land_fraction * 100.0,
+        stats.water_fraction * 100.0,
+        stats.ice_fraction * 100.0,
+        stats.habitable_surface
+    ));
+    let bands: Vec<String> =
+        stats.band_temperatures.iter().map(|t| format!("{:.1}°C", t)).collect();
+    sink.write_line(&format!("Surface temperature by latitude (S to N): {}", bands.join(" | ")));
+    sink.write_line("Material Distribution:");
+    for (material, count) in VoxelMaterial::KINDS.into_iter().zip(metrics.materials) {
+        if count > 0 {
+            let name = match material {
+                VoxelMaterial::Organic(_) => "Organic".to_string(),
+                other => format!("{:?}", other),
+            };
+            sink.write_line(&format!("  {}: {}", name, count));
+        }
     }
 
     // Species info
-    println!("\nSpecies: {}", state.species.len());
-    for species in &state.species {
-        println!(
-            "  Species #{}: metabolism:{:.2} repro:{:.2} mobility:{:.2} pref_temp:{:.2}",
-            species.id, species.metabolism, species.reproduction_rate, species.mobility, species.preferred_temperature
-        );
+    sink.write_line("");
+    sink.write_line(&format!("Species: {}", state.species.len()));
+    for species in state.species.iter() {
+        sink.write_line(&format!(
+            "  Species #{}: metabolism:{:.2} repro:{:.2} mobility:{:.2} pref_temp:{:.2}±{:.1} salinity:{:.2}-{:.2}",
+            species.id, species.metabolism, species.reproduction_rate, species.mobility, species.preferred_temperature,
+            species.thermal_tolerance, species.salinity_tolerance.0, species.salinity_tolerance.1
+        ));
+        if let Diet::Consumer { prey_species } = species.diet {
+            sink.write_line(&format!("    preys on species #{}", prey_species));
+        }
+        if let Some(range) = state.species_range(species.id) {
+            let (min, max, c) = (range.min, range.max, range.centroid);
+            sink.write_line(&format!(
+                "    range: {} voxels, biomass {}, ({},{},{})-({},{},{}), centroid ({:.1},{:.1},{:.1})",
+                range.occupied_voxels, range.biomass, min.0, min.1, min.2, max.0, max.1, max.2,
+                c.0, c.1, c.2
+            ));
+        }
     }
 
     // Civilizations
-    println!("\nCivilizations: {}", state.civilizations.len());
+    sink.write_line("");
+    sink.write_line(&format!("Civilizations: {}", state.civilizations.len()));
     for civ in &state.civilizations {
-        println!(
-            "  {}: pop:{} tech:{:.2} aggression:{:.2} spirituality:{:.2} at ({},{},{})",
-            civ.name, civ.population, civ.tech_level, civ.aggression, civ.spirituality, civ.x, civ.y, civ.z
-        );
+        sink.write_line(&format!(
+            "  {}: pop:{} tech:{:.2} aggression:{:.2} spirituality:{:.2} culture:{} at ({},{},{})",
+            civ.name,
+            civ.population,
+            civ.tech_level,
+            civ.aggression,
+            civ.spirituality,
+            civ.culture_label(),
+            civ.x,
+            civ.y,
+            civ.z
+        ));
     }
 
-    println!("=====================================\n");
+    // Divine interventions
+    sink.write_line("");
+    let counts: Vec<String> = state
+        .god_action_counts()
+        .iter()
+        .map(|(kind, count)| format!("{}: {}", kind, count))
+        .collect();
+    sink.write_line(&format!(
+        "Divine interventions: {} ({})",
+        state.god_action_log.len(),
+        counts.join(", ")
+    ));
+
+    sink.write_line("=====================================");
+    sink.write_line("");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biology::{Population, SpeciesRegistry};
+    use crate::god::GodState;
+    use crate::time_sim::SimulationStateBuilder;
+    use crate::world3d::World3D;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Two soil columns under air, at 10, 20, 30 and 40 degrees, with a
+    // civilization and a population on each
+    fn two_column_state() -> SimulationState {
+        let mut world = World3D::new(2, 1, 2);
+        for x in 0..2 {
+            world.set_material(x, 0, 0, VoxelMaterial::Soil);
+            world.get_mut(x, 0, 0).fertility = 0.25 + 0.5 * x as f32;
+        }
+        for (voxel, temperature) in world.voxels.iter_mut().zip([10.0, 20.0, 30.0, 40.0]) {
+            voxel.temperature = temperature;
+        }
+        let mut species = SpeciesRegistry::new();
+        species.create(&mut StdRng::seed_from_u64(0));
+        let mut civilizations = vec![Civilization::new(0, 0, 0, 1, 300)];
+        civilizations.push(Civilization::new(1, 1, 0, 1, 100));
+        civilizations[0].tech_level = 1.0;
+        civilizations[1].tech_level = 2.0;
+        SimulationStateBuilder::new()
+            .world(world)
+            .species(species)
+            .populations(vec![Population::new(0, 0, 0, 0, 40), Population::new(0, 1, 0, 0, 2)])
+            .civilizations(civilizations)
+            .seed(0)
+            .tick(7)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn metrics_match_hand_computed_values() {
+        let metrics = collect_metrics(&two_column_state());
+        assert_eq!(metrics.tick, 7);
+        assert_eq!((metrics.civ_count, metrics.civ_population), (2, 400));
+        assert_eq!(metrics.avg_tech, 1.5);
+        assert_eq!((metrics.population_count, metrics.total_biomass), (2, 42));
+        assert_eq!(metrics.materials, [2, 0, 2, 0, 0, 0, 0]);
+        assert_eq!((metrics.temperature.min, metrics.temperature.max), (10.0, 40.0));
+        assert_eq!((metrics.temperature.mean, metrics.temperature.variance), (25.0, 125.0));
+        assert!((metrics.climate_stability - 1.0 / 2.25).abs() < 1e-6);
+        assert_eq!(metrics.surface_fertility, 0.5);
+    }
+
+    #[test]
+    fn summary_deltas_compare_two_states() {
+        let previous = two_column_state();
+        let mut current = previous.clone();
+        current.civilizations[0].population = 350;
+        current.civilizations[1].tech_level = 4.0;
+        current.populations[1].size = 10;
+        for voxel in &mut current.world.voxels {
+            voxel.temperature = 25.0;
+        }
+
+        let delta = compute_summary_delta(&previous, &current);
+        assert_eq!((delta.civ_population, delta.biomass), (50, 8));
+        assert_eq!(delta.avg_tech, 1.0);
+        assert!((delta.climate_stability - (1.0 - 1.0 / 2.25)).abs() < 1e-6);
+
+        let mut lines = Vec::new();
+        write_summary_delta(&mut lines, 7, &previous, &current, &[]);
+        let trend = "Since last report: +50 civ pop, +8 biomass, +1.00 avg tech, climate ↑";
+        assert!(lines.iter().any(|line| line == trend), "{:#?}", lines);
+    }
+
+    #[test]
+    fn surface_map_shows_water_over_water_and_ground_over_land() {
+        // Bottom row: a lake over rock, soil, bare rock; the top row is empty
+        let mut world = World3D::new(3, 2, 3);
+        world.set_material(0, 0, 0, VoxelMaterial::Rock);
+        world.set_material(0, 0, 1, VoxelMaterial::Water);
+        world.set_material(1, 0, 0, VoxelMaterial::Rock);
+        world.set_material(1, 0, 1, VoxelMaterial::Soil);
+        world.set_material(2, 0, 0, VoxelMaterial::Rock);
+        let state = SimulationStateBuilder::new().world(world).build().unwrap();
+        assert_eq!(surface_map_lines(&state, false), vec!["...", "~:#"]);
+        // Shading by height leaves water alone
+        assert_eq!(surface_map_lines(&state, true), vec!["...", "~30"]);
+    }
+
+    #[test]
+    fn summaries_render_into_a_buffer() {
+        let mut state = two_column_state();
+        state.gods = vec![GodState::benevolent()];
+        let mut lines: Vec<String> = Vec::new();
+        write_summary(&mut lines, 7, &state, &[GodAction::RaiseSeaLevel { layers: 1 }]);
+
+        assert_eq!(lines.first().map(String::as_str), Some(""));
+        assert_eq!(lines[1], "========== TICK 7 ==========");
+        let expected = [
+            "Civilizations: 2",
+            "  Avg Tech Level: 1.50",
+            "  Total Civ Population: 400",
+            "Populations: 2 (Total Biomass: 42)",
+            "God State: curiosity:0.50 benevolence:0.90 cruelty:0.05 boredom:0.00",
+            "Last God Action: RaiseSeaLevel { layers: 1 }",
+        ];
+        for line in expected {
+            assert!(lines.iter().any(|l| l == line), "missing {:?} in {:#?}", line, lines);
+        }
+        let civ_lines: Vec<&String> = lines.iter().filter(|l| l.starts_with("  - ")).collect();
+        assert_eq!(civ_lines.len(), 2);
+        assert!(civ_lines[0].contains("at (0,0,1) pop:300 tech:1.00"), "{}", civ_lines[0]);
+        assert_eq!(lines[lines.len() - 2], "==============================");
+    }
+
+    #[test]
+    fn slices_mark_civilizations_where_they_stand() {
+        let mut world = World3D::new(6, 4, 2);
+        for y in 0..4 {
+            for x in 0..6 {
+                world.set_material(x, y, 0, VoxelMaterial::Soil);
+            }
+        }
+        let mut civ = Civilization::new(0, 4, 1, 0, 100);
+        civ.name = "Vexisians".to_string();
+        let mut species = SpeciesRegistry::new();
+        species.create(&mut StdRng::seed_from_u64(0));
+        let state = SimulationStateBuilder::new()
+            .world(world)
+            .species(species)
+            .populations(vec![Population::new(0, 1, 2, 0, 50)])
+            .civilizations(vec![civ])
+            .build()
+            .unwrap();
+
+        let mut lines = Vec::new();
+        write_world_slice(&mut lines, &state, 0, true);
+        // Rows run from y = 3 at the top down to y = 0, after a blank line and the title
+        let grid: Vec<&str> = lines[2..6].iter().map(String::as_str).collect();
+        assert_eq!(grid, ["::::::", ":+::::", "::::V:", "::::::"]);
+
+        let mut plain = Vec::new();
+        write_world_slice(&mut plain, &state, 0, false);
+        assert!(plain[2..6].iter().all(|row| row == "::::::"), "{:#?}", plain);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn flipbooks_hold_one_image_per_z_level() {
+        let world = World3D::generate_basic_world(5, 3, 8);
+        let state = SimulationStateBuilder::new().world(world).build().unwrap();
+        let dir = std::env::temp_dir().join(format!("flipbook_{}", std::process::id()));
+        render_all_slices_to_pngs(&state, &dir).unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let expected: Vec<String> = (0..8).map(|z| format!("slice_{:03}.png", z)).collect();
+        assert_eq!(names, expected);
+        for name in &names {
+            let image = image::open(dir.join(name)).unwrap();
+            assert_eq!((image.width(), image.height()), (5, 3));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn collapsed_civilizations_leave_the_timeseries_at_their_collapse() {
+        use crate::fixtures::CANONICAL;
+        use crate::time_sim::Multiverse;
+
+        let mut multiverse = Multiverse::new(CANONICAL.initial_state());
+        for _ in 0..5 {
+            multiverse.step().unwrap();
+        }
+        // Civilization 1 is reduced to a handful at tick 5 and collapses on tick 6
+        let latest = multiverse.current_timeline_mut().states.back_mut().unwrap();
+        latest.civilizations.iter_mut().find(|civ| civ.id == 1).unwrap().population = 10;
+        for _ in 0..5 {
+            multiverse.step().unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("civ_series_{}.csv", std::process::id()));
+        write_civ_timeseries_csv(multiverse.current_timeline(), &path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("tick,civ_id,population"));
+        let ticks_of = |id: u64| -> Vec<u64> {
+            csv.lines()
+                .skip(1)
+                .map(|line| {
+                    let fields: Vec<u64> =
+                        line.split(',').take(2).map(|field| field.parse().unwrap()).collect();
+                    (fields[0], fields[1])
+                })
+                .filter(|&(_, civ_id)| civ_id == id)
+                .map(|(tick, _)| tick)
+                .collect()
+        };
+        assert_eq!(ticks_of(0), (0..=10).collect::<Vec<_>>());
+        assert_eq!(ticks_of(1), (0..=5).collect::<Vec<_>>());
+        assert!(lines.all(|line| line.split(',').count() == 8));
+    }
+
+    #[test]
+    fn spikes_tower_over_the_plain_in_the_isometric_view() {
+        // A 5 × 5 rock plain, optionally with a soil-capped spike six voxels
+        // above it in the middle
+        let view = |spike: bool| {
+            let mut world = World3D::new(5, 5, 8);
+            for y in 0..5 {
+                for x in 0..5 {
+                    world.set_material(x, y, 0, VoxelMaterial::Rock);
+                }
+            }
+            if spike {
+                for z in 1..6 {
+                    world.set_material(2, 2, z, VoxelMaterial::Rock);
+                }
+                world.set_material(2, 2, 6, VoxelMaterial::Soil);
+            }
+            let state = SimulationStateBuilder::new().world(world).build().unwrap();
+            isometric_lines(&state, 80)
+        };
+        let first_row = |lines: &[String]| lines.iter().position(|line| !line.trim().is_empty());
+
+        // Flat ground starts at the row of the deepest layer, depth - 1, for
+        // the nearest corner; the middle of the plain sits two rows lower
+        let plain = view(false);
+        assert_eq!(first_row(&plain), Some(7));
+        // The spike's top is six rows above that, at row 3, alone in the middle column
+        let spiked = view(true);
+        assert_eq!(first_row(&spiked), Some(3));
+        let top = material_glyph(VoxelMaterial::Soil).to_string();
+        assert_eq!(spiked[3], format!("    {}", top));
+        for row in &spiked[3..7] {
+            assert_eq!(row.trim().chars().count(), 1, "{:?}", spiked);
+            assert_eq!(row.len() - row.trim_start().len(), 4, "{:?}", spiked);
+        }
+    }
+
+    #[test]
+    fn known_events_read_as_a_chronicle() {
+        use crate::god::{ActionEffect, ActionError};
+
+        let state = two_column_state();
+        let intervention = |god: usize, action: GodAction, reason: &str, outcome| {
+            SimEvent::GodIntervention { god, action, reason: reason.to_string(), outcome }
+        };
+        let killed = ActionEffect { population_killed: 12, ..Default::default() };
+        let meteor = GodAction::SpawnCatastrophe {
+            kind: CatastropheKind::Meteor,
+            x: 0,
+            y: 0,
+            z: 0,
+            intensity: 1.0,
+            radius: 1.0,
+        };
+        let missing = ActionError::UnknownCivilization(9);
+        let events = [
+            intervention(
+                0,
+                GodAction::BlessCivilization { civ_id: 0, tech_boost: 2.0 },
+                "bored, blessing #0",
+                Ok(ActionEffect::default()),
+            ),
+            intervention(1, meteor, "cruel amid 2 wars, meteor", Ok(killed)),
+            intervention(0, GodAction::None, "content", Ok(ActionEffect::default())),
+            intervention(
+                0,
+                GodAction::AnswerPrayer { civ_id: 9 },
+                "benevolent, answering",
+                Ok(ActionEffect::default()),
+            ),
+            intervention(
+                0,
+                GodAction::AnswerPrayer { civ_id: 9 },
+                "curious, trying",
+                Err(missing.clone()),
+            ),
+            SimEvent::Speciation { parent_id: 0, species_id: 2, x: 1, y: 0, z: 0 },
+            SimEvent::SeaLevelRise { sea_level: 1, voxels_flooded: 2, population_lost: 0 },
+            SimEvent::MissingSpecies { species_id: 5, population_lost: 40 },
+        ];
+
+        // On this 2 × 1 world, x = 0 lies in the south-west and x = 1 in the south
+        let expected = [
+            format!("The God, grown bored, blessed the {}", state.civilizations[0].name),
+            "God #1, in its cruelty, devastated the south-western lands, killing 12".to_string(),
+            "The God, in a benevolent mood, answered the prayers of civilization #9".to_string(),
+            format!("The God, out of curiosity, tried to intervene, but {}", missing),
+            "A new species (#2) branched off species #0 in the southern lands".to_string(),
+            "The sea rose to layer 1, flooding 2 voxels".to_string(),
+            "40 creatures of the unknown species #5 vanished".to_string(),
+        ];
+        assert_eq!(narrate(&events, &state), expected);
+        // Same events, same chronicle
+        assert_eq!(narrate(&events, &state), narrate(&events, &state));
+    }
+
+    #[test]
+    fn wide_slices_shrink_to_their_majority_materials() {
+        // 128 columns, 3 rows deep: water over a rock row in the west half,
+        // rock speckled with soil in the east half
+        let mut world = World3D::new(128, 3, 1);
+        for x in 0..128 {
+            for y in 0..3 {
+                let material = match (x < 64, y) {
+                    (true, 0) => VoxelMaterial::Rock,
+                    (true, _) => VoxelMaterial::Water,
+                    (false, 0) if x % 2 == 0 => VoxelMaterial::Soil,
+                    (false, _) => VoxelMaterial::Rock,
+                };
+                world.set_material(x, y, 0, material);
+            }
+        }
+        let state = SimulationStateBuilder::new().world(world).build().unwrap();
+
+        let mut lines: Vec<String> = Vec::new();
+        write_world_slice_scaled(&mut lines, &state, 0, false, Some(64), Some(1));
+        let rows: Vec<&String> = lines.iter().filter(|line| line.chars().count() == 64).collect();
+        assert_eq!(rows.len(), 1, "{:?}", lines);
+        // Each character stands for a 2×3 block, read as its commonest material
+        let water = material_glyph(VoxelMaterial::Water).to_string();
+        let rock = material_glyph(VoxelMaterial::Rock).to_string();
+        assert_eq!(*rows[0], water.repeat(32) + &rock.repeat(32));
+
+        // Worlds that fit keep their full resolution
+        let mut lines: Vec<String> = Vec::new();
+        write_world_slice_scaled(&mut lines, &state, 0, false, Some(200), None);
+        assert_eq!(lines.iter().filter(|line| line.chars().count() == 128).count(), 3);
+    }
+
+    #[test]
+    fn x_and_y_slices_are_vertical_cross_sections() {
+        // A 3×2×4 world: rock floor, soil at x = 0, a water layer at y = 1,
+        // air above; a population sits on the soil at (0, 1, 1)
+        let mut world = World3D::new(3, 2, 4);
+        for x in 0..3 {
+            for y in 0..2 {
+                world.set_material(x, y, 0, VoxelMaterial::Rock);
+                if x == 0 {
+                    world.set_material(x, y, 1, VoxelMaterial::Soil);
+                }
+                if y == 1 {
+                    world.set_material(x, y, 2, VoxelMaterial::Water);
+                }
+            }
+        }
+        let mut species = SpeciesRegistry::new();
+        species.create(&mut StdRng::seed_from_u64(0));
+        let state = SimulationStateBuilder::new()
+            .world(world)
+            .species(species)
+            .populations(vec![Population::new(0, 0, 1, 1, 40)])
+            .build()
+            .unwrap();
+        let slice = |axis: Axis, index: u32| {
+            let mut lines: Vec<String> = Vec::new();
+            write_world_slice_axis(&mut lines, &state, axis, index, true);
+            lines
+        };
+        // Rows from the sky down, one glyph per material
+        let rows = |rows: &[&[VoxelMaterial]]| -> Vec<String> {
+            rows.iter().map(|row| row.iter().map(|&m| material_glyph(m)).collect()).collect()
+        };
+        let (a, r, s, w) = (
+            VoxelMaterial::Air,
+            VoxelMaterial::Rock,
+            VoxelMaterial::Soil,
+            VoxelMaterial::Water,
+        );
+
+        // At x = 0 the columns run along y, and the population shows on its soil
+        let mut expected = rows(&[&[a, a], &[a, w], &[s, s], &[r, r]]);
+        expected[2] = format!("{}+", material_glyph(s));
+        assert_eq!(slice(Axis::X, 0)[1], "--- World Slice at X=0 ---");
+        assert_eq!(slice(Axis::X, 0)[2..6], expected[..]);
+        // At y = 1 the columns run along x, under the water layer
+        let mut expected = rows(&[&[a, a, a], &[w, w, w], &[s, a, a], &[r, r, r]]);
+        expected[2] = format!("+{}{}", material_glyph(a), material_glyph(a));
+        assert_eq!(slice(Axis::Y, 1)[1], "--- World Slice at Y=1 ---");
+        assert_eq!(slice(Axis::Y, 1)[2..6], expected[..]);
+        // At y = 0 there is no water, and no life
+        let expected = rows(&[&[a, a, a], &[a, a, a], &[s, a, a], &[r, r, r]]);
+        assert_eq!(slice(Axis::Y, 0)[2..6], expected[..]);
+
+        assert_eq!(slice(Axis::X, 3), ["Invalid x level: 3"]);
+        assert_eq!(slice(Axis::Y, 2), ["Invalid y level: 2"]);
+    }
 }
@@ -0,0 +1,327 @@
+use crate::events::SimEvent;
+use crate::god::{
+    apply_action, CatastropheKind, GodAction, PhysicsRulesDelta, DEFAULT_CATASTROPHE_RADIUS,
+};
+use crate::time_sim::SimulationState;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Recorded god actions, saved as one human-editable line per action:
+//
+//   # tick action arguments...
+//   12 change_physics <heat_diffusion_delta> <cooling_rate_delta> [rule=value...]
+//   40 catastrophe <x> <y> <z> <intensity> [radius] [meteor|plague|flood]
+//   55 bless <civ_id> <tech_boost>
+//   61 answer_prayer <civ_id>
+//   70 resurrect <x> <y> <z> <species_id> <size>
+//   80 raise_sea <layers>
+//
+// The optional change_physics rules are `gravity` and `storms` (true/false)
+// and `melting_point`, `solar_heating`, `urban_heat`, `evaporation_point`,
+// `evaporation_rate` and `lightning_chance` (deltas).
+#[derive(Debug, Clone, Default)]
+pub struct GodActionLog {
+    pub entries: Vec<(u64, GodAction)>,
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(err) => write!(f, "i/o error: {}", err),
+            ScriptError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl From<io::Error> for ScriptError {
+    fn from(err: io::Error) -> Self {
+        ScriptError::Io(err)
+    }
+}
+
+impl GodActionLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Record what the gods did at `tick`; idle gods are not recorded
+    pub fn record(&mut self, tick: u64, actions: &[GodAction]) {
+        for action in actions {
+            if !matches!(action, GodAction::None) {
+                self.entries.push((tick, action.clone()));
+            }
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# tick action arguments...\n");
+        for (tick, action) in &self.entries {
+            let line = match action {
+                GodAction::ChangePhysics(delta) => {
+                    let mut line = format!(
+                        "{} change_physics {} {}",
+                        tick, delta.heat_diffusion_delta, delta.cooling_rate_delta
+                    );
+                    for (key, value) in physics_options(delta) {
+                        line.push_str(&format!(" {}={}", key, value));
+                    }
+                    line
+                }
+                GodAction::SpawnCatastrophe { kind, x, y, z, intensity, radius } => format!(
+                    "{} catastrophe {} {} {} {} {} {}",
+                    tick,
+                    x,
+                    y,
+                    z,
+                    intensity,
+                    radius,
+                    kind.name()
+                ),
+                GodAction::BlessCivilization { civ_id, tech_boost } => {
+                    format!("{} bless {} {}", tick, civ_id, tech_boost)
+                }
+                GodAction::AnswerPrayer { civ_id } => format!("{} answer_prayer {}", tick, civ_id),
+                GodAction::Resurrect { x, y, z, species_id, size } => {
+                    format!("{} resurrect {} {} {} {} {}", tick, x, y, z, species_id, size)
+                }
+                GodAction::RaiseSeaLevel { layers } => format!("{} raise_sea {}", tick, layers),
+                GodAction::None => continue,
+            };
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let mut log = GodActionLog::new();
+
+        for (i, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| ScriptError::Parse {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let tick: u64 = fields[0].parse().map_err(|_| error("invalid tick"))?;
+            let args = &fields[1..];
+
+            let num = |index: usize| -> Result<f32, ScriptError> {
+                args.get(index)
+                    .ok_or_else(|| error("missing argument"))?
+                    .parse()
+                    .map_err(|_| error("invalid number"))
+            };
+            let int = |index: usize| -> Result<u32, ScriptError> {
+                args.get(index)
+                    .ok_or_else(|| error("missing argument"))?
+                    .parse()
+                    .map_err(|_| error("invalid integer"))
+            };
+
+            let action = match args.first().copied() {
+                Some("change_physics") => {
+                    let mut delta = PhysicsRulesDelta {
+                        heat_diffusion_delta: num(1)?,
+                        cooling_rate_delta: num(2)?,
+                        ..Default::default()
+                    };
+                    for option in args.iter().skip(3) {
+                        set_physics_option(&mut delta, option).map_err(|message| error(&message))?;
+                    }
+                    GodAction::ChangePhysics(delta)
+                }
+                Some("catastrophe") => GodAction::SpawnCatastrophe {
+                    kind: match args.get(6) {
+                        Some(name) => CatastropheKind::from_name(name)
+                            .ok_or_else(|| error("unknown catastrophe kind"))?,
+                        None => CatastropheKind::Meteor,
+                    },
+                    x: int(1)?,
+                    y: int(2)?,
+                    z: int(3)?,
+                    intensity: num(4)?,
+                    radius: match args.get(5) {
+                        Some(_) => num(5)?,
+                        None => DEFAULT_CATASTROPHE_RADIUS,
+                    },
+                },
+                Some("bless") => GodAction::BlessCivilization {
+                    civ_id: int(1)?,
+                    tech_boost: num(2)?,
+                },
+                Some("answer_prayer") => GodAction::AnswerPrayer { civ_id: int(1)? },
+                Some("resurrect") => GodAction::Resurrect {
+                    x: int(1)?,
+                    y: int(2)?,
+                    z: int(3)?,
+                    species_id: int(4)?,
+                    size: int(5)?,
+                },
+                Some("raise_sea") => GodAction::RaiseSeaLevel { layers: int(1)? },
+                Some(other) => return Err(error(&format!("unknown action '{}'", other))),
+                None => return Err(error("missing action")),
+            };
+            log.entries.push((tick, action));
+        }
+
+        Ok(log)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ScriptError> {
+        fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+}
+
+// Replays a recorded log: every action is applied at the tick it was recorded
+pub struct ScriptedGod {
+    log: GodActionLog,
+}
+
+impl ScriptedGod {
+    pub fn new(mut log: GodActionLog) -> Self {
+        log.entries.sort_by_key(|(tick, _)| *tick);
+        Self { log }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        Ok(Self::new(GodActionLog::load(path)?))
+    }
+
+    pub fn actions_at(&self, tick: u64) -> impl Iterator<Item = &GodAction> + '_ {
+        // Entries are sorted by tick: binary search for this tick's run
+        let entries = &self.log.entries;
+        let start = entries.partition_point(|(t, _)| *t < tick);
+        let end = entries.partition_point(|(t, _)| *t <= tick);
+        entries[start..end].iter().map(|(_, action)| action)
+    }
+
+    // Apply this tick's scripted actions to the state, logging them as events
+    pub fn step(&self, state: &mut SimulationState, tick: u64) -> Vec<GodAction> {
+        let actions: Vec<GodAction> = self.actions_at(tick).cloned().collect();
+        for action in &actions {
+            let outcome = apply_action(state, action.clone());
+            state.events.push(SimEvent::GodIntervention {
+                god: 0,
+                action: action.clone(),
+                reason: format!("scripted for tick {}", tick),
+                outcome,
+            });
+        }
+        actions
+    }
+}
+
+// The optional rules of a physics delta that are set, as `key=value` pairs
+fn physics_options(delta: &PhysicsRulesDelta) -> Vec<(&'static str, String)> {
+    let flags = [("gravity", delta.gravity_enabled), ("storms", delta.storms_enabled)];
+    let deltas = [
+        ("melting_point", delta.rock_melting_point_delta),
+        ("solar_heating", delta.solar_heating_delta),
+        ("urban_heat", delta.urban_heat_delta),
+        ("evaporation_point", delta.evaporation_point_delta),
+        ("evaporation_rate", delta.evaporation_rate_delta),
+        ("lightning_chance", delta.lightning_chance_delta),
+    ];
+    let flags = flags.into_iter().filter_map(|(k, v)| v.map(|v| (k, v.to_string())));
+    let deltas = deltas.into_iter().filter_map(|(k, v)| v.map(|v| (k, v.to_string())));
+    flags.chain(deltas).collect()
+}
+
+fn set_physics_option(delta: &mut PhysicsRulesDelta, option: &str) -> Result<(), String> {
+    let (key, value) = option
+        .split_once('=')
+        .ok_or_else(|| format!("expected rule=value, got '{}'", option))?;
+    let flag = || value.parse::<bool>().map_err(|_| format!("invalid flag '{}'", value));
+    let number = || value.parse::<f32>().map_err(|_| format!("invalid number '{}'", value));
+    match key {
+        "gravity" => delta.gravity_enabled = Some(flag()?),
+        "storms" => delta.storms_enabled = Some(flag()?),
+        "melting_point" => delta.rock_melting_point_delta = Some(number()?),
+        "solar_heating" => delta.solar_heating_delta = Some(number()?),
+        "urban_heat" => delta.urban_heat_delta = Some(number()?),
+        "evaporation_point" => delta.evaporation_point_delta = Some(number()?),
+        "evaporation_rate" => delta.evaporation_rate_delta = Some(number()?),
+        "lightning_chance" => delta.lightning_chance_delta = Some(number()?),
+        _ => return Err(format!("unknown physics rule '{}'", key)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::CANONICAL;
+    use crate::god::{
+        build_world_summary, choose_action_with_reason, ActionEffect, ActionError, GodState,
+    };
+    use crate::time_sim::simulate_tick_with_injector;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    type Effects = Vec<(u64, Result<ActionEffect, ActionError>)>;
+
+    // Collect the outcomes of this tick's interventions, idle ones aside
+    fn collect_effects(state: &SimulationState, effects: &mut Effects) {
+        for event in &state.events {
+            if let SimEvent::GodIntervention { action, outcome, .. } = event {
+                if !matches!(action, GodAction::None) {
+                    effects.push((state.tick, outcome.clone()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn replayed_logs_reproduce_the_recorded_run() {
+        // A bored god acting every tick, with its own RNG so that the state's
+        // RNG drives the world the same way in both runs
+        let mut state = CANONICAL.initial_state();
+        let mut god = GodState::builder().cruelty(0.8).build();
+        let mut god_rng = StdRng::seed_from_u64(7);
+        let mut log = GodActionLog::new();
+        let mut recorded = Vec::new();
+        for _ in 0..30 {
+            simulate_tick_with_injector(&mut state, &mut |tick, state| {
+                god.boredom = 1.0;
+                let summary = build_world_summary(state);
+                let (action, _) = choose_action_with_reason(&mut god, &summary, &mut god_rng);
+                log.record(tick, std::slice::from_ref(&action));
+                Some(action)
+            });
+            collect_effects(&state, &mut recorded);
+        }
+        assert!(recorded.len() >= 20, "only {} interventions", recorded.len());
+
+        // The injector stands in for an idle god; the script does the rest
+        let scripted = ScriptedGod::new(GodActionLog::parse(&log.to_text()).unwrap());
+        let mut replay = CANONICAL.initial_state();
+        let mut replayed = Vec::new();
+        for _ in 0..30 {
+            simulate_tick_with_injector(&mut replay, &mut |_, _| Some(GodAction::None));
+            let tick = replay.tick;
+            scripted.step(&mut replay, tick);
+            collect_effects(&replay, &mut replayed);
+        }
+        assert_eq!(replayed, recorded);
+    }
+}
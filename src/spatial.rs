@@ -0,0 +1,169 @@
+use crate::world3d::World3D;
+use std::collections::HashMap;
+
+// Entities bucketed by cubic cells of `cell_size` voxels, so a radius query
+// only looks at the entities of the cells around its center instead of all of
+// them. Entities are identified by their insertion index (e.g. their index in
+// the slice the grid was built from).
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: u32,
+    cells: HashMap<(u32, u32, u32), Vec<usize>>,
+    positions: Vec<(u32, u32, u32)>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: u32) -> Self {
+        Self {
+            cell_size: cell_size.max(1),
+            cells: HashMap::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn build(cell_size: u32, positions: impl IntoIterator<Item = (u32, u32, u32)>) -> Self {
+        let mut grid = Self::new(cell_size);
+        for position in positions {
+            grid.insert(position);
+        }
+        grid
+    }
+
+    // Add an entity at `position`; returns its index
+    pub fn insert(&mut self, position: (u32, u32, u32)) -> usize {
+        let index = self.positions.len();
+        self.positions.push(position);
+        self.cells.entry(self.cell_of(position)).or_default().push(index);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // Indices, in increasing order, of the entities at most `radius` voxels
+    // from `center`, measured like World3D::distance (so honoring wrapping)
+    pub fn query_radius(
+        &self,
+        world: &World3D,
+        center: (u32, u32, u32),
+        radius: f32,
+    ) -> Vec<usize> {
+        if radius < 0.0 || radius.is_nan() {
+            return Vec::new();
+        }
+        let reach = radius.min(u32::MAX as f32) as u32;
+        let xs = self.axis_cells(center.0, reach, world.width, world.wrapping);
+        let ys = self.axis_cells(center.1, reach, world.height, world.wrapping);
+        let zs = self.axis_cells(center.2, reach, world.depth, world.wrapping);
+
+        let within = |&i: &usize| world.distance(center, self.positions[i]) <= radius;
+        let mut found = Vec::new();
+        for &cx in &xs {
+            for &cy in &ys {
+                for &cz in &zs {
+                    if let Some(bucket) = self.cells.get(&(cx, cy, cz)) {
+                        found.extend(bucket.iter().copied().filter(within));
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    fn cell_of(&self, (x, y, z): (u32, u32, u32)) -> (u32, u32, u32) {
+        (x / self.cell_size, y / self.cell_size, z / self.cell_size)
+    }
+
+    // Cells along one axis holding a coordinate within `reach` of `c`
+    fn axis_cells(&self, c: u32, reach: u32, size: u32, wrapping: bool) -> Vec<u32> {
+        if size == 0 {
+            return Vec::new();
+        }
+        let (first, last) = if wrapping && reach.saturating_mul(2) < size {
+            // Walk around the edge: the span may wrap past 0 or past size - 1
+            let first = (c as u64 + size as u64 - reach as u64) % size as u64;
+            let last = (c as u64 + reach as u64) % size as u64;
+            (first as u32, last as u32)
+        } else if wrapping {
+            (0, size - 1)
+        } else {
+            (c.saturating_sub(reach), c.saturating_add(reach).min(size - 1))
+        };
+
+        let (first_cell, last_cell) = (first / self.cell_size, last / self.cell_size);
+        if first <= last {
+            (first_cell..=last_cell).collect()
+        } else {
+            // Wrapped span: from `first` to the edge, then from 0 to `last`
+            let edge_cell = (size - 1) / self.cell_size;
+            let mut cells: Vec<u32> = (0..=last_cell).chain(first_cell..=edge_cell).collect();
+            cells.sort_unstable();
+            cells.dedup();
+            cells
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Indices of `positions` within `radius` of `center`, checking every one
+    fn brute_force(
+        world: &World3D,
+        positions: &[(u32, u32, u32)],
+        center: (u32, u32, u32),
+        radius: f32,
+    ) -> Vec<usize> {
+        (0..positions.len()).filter(|&i| world.distance(center, positions[i]) <= radius).collect()
+    }
+
+    #[test]
+    fn radius_queries_find_exactly_the_entities_in_range() {
+        let positions = [
+            (0, 0, 0),
+            (1, 0, 0),
+            (3, 4, 0),
+            (5, 5, 5),
+            (9, 9, 9),
+            (19, 0, 0),
+            (10, 19, 2),
+            (10, 10, 0),
+            (10, 10, 0),
+            (13, 14, 0),
+        ];
+        let mut world = World3D::new(20, 20, 10);
+        let grid = SpatialGrid::build(4, positions);
+        assert_eq!(grid.len(), positions.len());
+
+        // By hand: (3, 4, 0) and (13, 14, 0) lie right on the edge of the radius
+        assert_eq!(grid.query_radius(&world, (0, 0, 0), 5.0), [0, 1, 2]);
+        assert_eq!(grid.query_radius(&world, (10, 10, 0), 5.0), [7, 8, 9]);
+        assert!(grid.query_radius(&world, (0, 0, 0), -1.0).is_empty());
+        // Across the edge of a wrapping world, (19, 0, 0) is next door
+        world.wrapping = true;
+        assert_eq!(grid.query_radius(&world, (0, 0, 0), 1.0), [0, 1, 5]);
+
+        for wrapping in [false, true] {
+            world.wrapping = wrapping;
+            for center in [(0, 0, 0), (10, 0, 1), (19, 19, 9), (6, 12, 4)] {
+                for radius in [0.0, 1.0, 2.5, 6.0, 11.0, 40.0] {
+                    assert_eq!(
+                        grid.query_radius(&world, center, radius),
+                        brute_force(&world, &positions, center, radius),
+                        "center {:?}, radius {}, wrapping {}",
+                        center,
+                        radius,
+                        wrapping
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,72 +1,1117 @@
-use crate::biology::{Population, Species};
-use crate::civilization::Civilization;
-use crate::god::GodState;
+use crate::biology::{BiologyRules, Population, Species, SpeciesError, SpeciesRegistry};
+use crate::civilization::{next_civ_id, Civilization, FoundingSites, NameGenerator};
+use crate::events::SimEvent;
+use crate::god::{ActionError, ActionLog, GodAction, GodState};
 use crate::physics::PhysicsRules;
-use crate::world3d::World3D;
+use crate::render::collect_metrics;
+use crate::world3d::{StableHasher, Voxel, VoxelMaterial, World3D, MATERIAL_KINDS};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem::size_of;
+use std::path::Path;
+use std::sync::Arc;
 
-#[derive(Clone)]
+// Serializable, events aside (see `save_to_path`)
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SimulationState {
+    // Number of ticks simulated to reach this state
+    pub tick: u64,
     pub world: World3D,
-    pub physics_rules: PhysicsRules,
-    pub species: Vec<Species>,
+    // Rarely modified, so shared between clones until one of them changes it
+    // (use Arc::make_mut to modify)
+    pub physics_rules: Arc<PhysicsRules>,
+    pub biology_rules: BiologyRules,
+    pub species: Arc<SpeciesRegistry>,
     pub populations: Vec<Population>,
     pub civilizations: Vec<Civilization>,
-    pub god_state: GodState,
+    // The pantheon: each god takes a turn every tick, in order
+    pub gods: Vec<GodState>,
+    // Every intervention (GodAction::None excluded) with the tick it happened on
+    pub god_action_log: ActionLog,
+    // Events of the most recent tick
+    #[serde(skip)]
+    pub events: Vec<SimEvent>,
+    // Where civilizations were founded or collapsed, to pace refounding
+    pub founding_sites: FoundingSites,
+    // Names new civilizations
+    pub names: NameGenerator,
+    // Source of all randomness during ticks; it is cloned (and saved) along
+    // with the state, so re-simulating from a rewound or reloaded state replays
+    // the same future. Same generator as StdRng, whose state cannot be saved.
+    pub rng: ChaCha12Rng,
 }
 
 impl SimulationState {
     pub fn new(
         world: World3D,
         physics_rules: PhysicsRules,
-        species: Vec<Species>,
+        species: SpeciesRegistry,
         populations: Vec<Population>,
         god_state: GodState,
     ) -> Self {
+        Self::assemble_unchecked(
+            SimulationStateBuilder::new()
+                .world(world)
+                .physics_rules(physics_rules)
+                .species(species)
+                .populations(populations)
+                .god_state(god_state),
+        )
+    }
+
+    // Same as `new`, with the state's RNG seeded: two states built from the
+    // same inputs and seed evolve identically
+    pub fn new_seeded(
+        seed: u64,
+        world: World3D,
+        physics_rules: PhysicsRules,
+        species: SpeciesRegistry,
+        populations: Vec<Population>,
+        god_state: GodState,
+    ) -> Self {
+        Self::assemble_unchecked(
+            SimulationStateBuilder::new()
+                .world(world)
+                .physics_rules(physics_rules)
+                .species(species)
+                .populations(populations)
+                .god_state(god_state)
+                .seed(seed),
+        )
+    }
+
+    // Unknown species only get a warning here, unlike with the builder's `build`
+    fn assemble_unchecked(builder: SimulationStateBuilder) -> Self {
+        let state = builder.assemble();
+        if let Err(err) = state.check_species() {
+            eprintln!("Warning: {} (its populations will die out on the first tick)", err);
+        }
+        state
+    }
+
+    // Copy `other`'s civilizations and populations onto spots that are free
+    // here (civs get fresh ids, species missing here are registered)
+    fn absorb_life(&mut self, other: &SimulationState) {
+        for civ in &other.civilizations {
+            let occupied = self
+                .civilizations
+                .iter()
+                .any(|c| (c.x, c.y, c.z) == (civ.x, civ.y, civ.z));
+            if !occupied {
+                let mut civ = civ.clone();
+                civ.id = next_civ_id(&self.civilizations);
+                self.civilizations.push(civ);
+            }
+        }
+
+        let occupied: Vec<(u32, u32, u32)> =
+            self.populations.iter().map(|p| (p.x, p.y, p.z)).collect();
+        for pop in &other.populations {
+            if occupied.contains(&(pop.x, pop.y, pop.z)) {
+                continue;
+            }
+            if self.species.get(pop.species_id).is_none() {
+                if let Some(species) = other.species.get(pop.species_id) {
+                    let _ = Arc::make_mut(&mut self.species).insert(species.clone());
+                }
+            }
+            self.populations.push(pop.clone());
+        }
+    }
+
+    // Number of logged interventions of each kind (see `GodAction::kind`)
+    pub fn god_action_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for (_, action) in self.god_action_log.iter() {
+            *counts.entry(action.kind()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // Populations whose voxel is within `radius` of `center`, honoring wrapping
+    pub fn populations_within(
+        &self,
+        center: (u32, u32, u32),
+        radius: f32,
+    ) -> impl Iterator<Item = &Population> + '_ {
+        self.populations
+            .iter()
+            .filter(move |p| self.world.distance(center, (p.x, p.y, p.z)) <= radius)
+    }
+
+    // Civilizations centered within `radius` of `center`, honoring wrapping
+    pub fn civilizations_within(
+        &self,
+        center: (u32, u32, u32),
+        radius: f32,
+    ) -> impl Iterator<Item = &Civilization> + '_ {
+        self.civilizations
+            .iter()
+            .filter(move |c| self.world.distance(center, (c.x, c.y, c.z)) <= radius)
+    }
+
+    // Flood the world up to its sea level (see `World3D::flood_to_sea_level`);
+    // populations and civilizations on the flooded voxels lose DROWNING_LOSS of
+    // their numbers. Returns the voxels flooded and the lives lost.
+    pub fn flood_to_sea_level(&mut self) -> (u32, u32) {
+        let flooded: HashSet<(u32, u32, u32)> =
+            self.world.flood_to_sea_level().into_iter().collect();
+        if flooded.is_empty() {
+            return (0, 0);
+        }
+
+        let mut lost = 0;
+        self.populations.retain_mut(|pop| {
+            if flooded.contains(&(pop.x, pop.y, pop.z)) {
+                let drowned = ((pop.size as f32 * DROWNING_LOSS).ceil() as u32).min(pop.size);
+                pop.size -= drowned;
+                lost += drowned;
+            }
+            pop.size > 0
+        });
+        for civ in self.civilizations.iter_mut() {
+            if flooded.contains(&(civ.x, civ.y, civ.z)) {
+                let drowned = (civ.population as f32 * DROWNING_LOSS) as u32;
+                civ.population -= drowned;
+                lost += drowned;
+            }
+        }
+        (flooded.len() as u32, lost)
+    }
+
+    // What an agent standing at `center` perceives within `radius` voxels
+    // (a cube, clamped to the world unless it wraps)
+    pub fn local_observation(&self, center: (u32, u32, u32), radius: u32) -> LocalObs {
+        let inside = |x: u32, y: u32, z: u32| {
+            let (dx, dy, dz) = self.world.separation(center, (x, y, z));
+            dx.max(dy).max(dz) <= radius
+        };
+
+        let mut counts = [0u32; MATERIAL_KINDS];
+        let mut temperature_sum = 0.0f64;
+        let mut nutrient_total = 0.0f32;
+        let mut voxels = 0u32;
+        for (x, y, z) in self.world.cube_around(center, radius) {
+            let voxel = self.world.get(x, y, z);
+            counts[voxel.material.kind_index()] += 1;
+            temperature_sum += voxel.temperature as f64;
+            nutrient_total += voxel.nutrients;
+            voxels += 1;
+        }
+
+        let mut obs = LocalObs {
+            mean_temperature: if voxels > 0 {
+                (temperature_sum / voxels as f64) as f32
+            } else {
+                0.0
+            },
+            nutrient_total,
+            dominant_material: VoxelMaterial::Air,
+            ..Default::default()
+        };
+        let dominant = counts.iter().enumerate().max_by_key(|&(i, &n)| (n, usize::MAX - i));
+        if let Some((kind, _)) = dominant {
+            obs.dominant_material = VoxelMaterial::KINDS[kind];
+        }
+        for pop in self.populations.iter().filter(|p| inside(p.x, p.y, p.z)) {
+            obs.population_count += 1;
+            obs.biomass += pop.size as u64;
+        }
+        obs.civ_count = self.civilizations.iter().filter(|c| inside(c.x, c.y, c.z)).count() as u32;
+        obs
+    }
+
+    // Where a species lives, or None if it has no population left
+    pub fn species_range(&self, species_id: u32) -> Option<RangeInfo> {
+        let mut positions = Vec::new();
+        let mut biomass = 0u64;
+        let mut weighted = [0.0f64; 3];
+        let mut min = (u32::MAX, u32::MAX, u32::MAX);
+        let mut max = (0, 0, 0);
+        for pop in self.populations.iter().filter(|p| p.species_id == species_id) {
+            positions.push((pop.x, pop.y, pop.z));
+            biomass += pop.size as u64;
+            weighted[0] += pop.x as f64 * pop.size as f64;
+            weighted[1] += pop.y as f64 * pop.size as f64;
+            weighted[2] += pop.z as f64 * pop.size as f64;
+            min = (min.0.min(pop.x), min.1.min(pop.y), min.2.min(pop.z));
+            max = (max.0.max(pop.x), max.1.max(pop.y), max.2.max(pop.z));
+        }
+        if positions.is_empty() {
+            return None;
+        }
+        positions.sort_unstable();
+        positions.dedup();
+
+        let centroid = if biomass > 0 {
+            let axis = |sum: f64| (sum / biomass as f64) as f32;
+            (axis(weighted[0]), axis(weighted[1]), axis(weighted[2]))
+        } else {
+            let axis = |lo: u32, hi: u32| (lo as f32 + hi as f32) / 2.0;
+            (axis(min.0, max.0), axis(min.1, max.1), axis(min.2, max.2))
+        };
+        Some(RangeInfo {
+            occupied_voxels: positions.len() as u32,
+            min,
+            max,
+            centroid,
+            biomass,
+        })
+    }
+
+    // Write the whole state, RNG included, as JSON; the events of the last
+    // tick are not saved
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    // Every population must belong to a registered species
+    pub fn check_species(&self) -> Result<(), SpeciesError> {
+        match self.populations.iter().find(|p| self.species.get(p.species_id).is_none()) {
+            Some(pop) => Err(SpeciesError::UnknownId(pop.species_id)),
+            None => Ok(()),
+        }
+    }
+
+    // Place `count` populations on random habitable surface voxels, each of a
+    // species suited to its spot (a new adapted species if none fits and
+    // `BiologyRules::max_species` allows it).
+    // Fertile temperate ground is favored and gets larger populations; deserts
+    // and tundra stay sparse. Returns how many were placed (fewer if habitable
+    // ground is scarce).
+    pub fn seed_life(&mut self, count: usize, rng: &mut impl Rng) -> usize {
+        if self.world.width == 0 || self.world.height == 0 {
+            return 0;
+        }
+
+        let max_attempts = count * 50;
+        let mut placed = 0;
+        for _ in 0..max_attempts {
+            if placed == count {
+                break;
+            }
+
+            let x = rng.gen_range(0..self.world.width);
+            let y = rng.gen_range(0..self.world.height);
+            let z = match self.world.surface_height_at(x, y) {
+                Some(z) => z,
+                None => continue,
+            };
+            let voxel = self.world.get(x, y, z);
+            if !voxel.material.is_habitable() {
+                continue;
+            }
+            let suitability = seeding_suitability(voxel);
+            if rng.gen::<f32>() >= suitability {
+                continue;
+            }
+
+            let fitting: Vec<u32> =
+                self.species.iter().filter(|s| s.suits(voxel)).map(|s| s.id).collect();
+            let species_id = if fitting.is_empty() {
+                let species = Arc::make_mut(&mut self.species);
+                if !species.make_room(self.biology_rules.max_species, &self.populations) {
+                    continue;
+                }
+                species.create_adapted(voxel, rng).id
+            } else {
+                fitting[rng.gen_range(0..fitting.len())]
+            };
+            let size = (rng.gen_range(50..150) as f32 * (0.5 + suitability)) as u32;
+            self.populations.push(Population::new(species_id, x, y, z, size));
+            placed += 1;
+        }
+
+        placed
+    }
+
+    // Found established civilizations at the given (x, y, z, population) specs.
+    // Each position is snapped to the surface of its column, which must be
+    // habitable; nothing is placed unless every spec is valid.
+    pub fn seed_civilizations(
+        &mut self,
+        specs: &[(u32, u32, u32, u32)],
+        rng: &mut impl Rng,
+    ) -> Result<(), SeedError> {
+        let mut sites = Vec::with_capacity(specs.len());
+        for &(x, y, _, population) in specs {
+            let z = self
+                .world
+                .surface_height_at(x, y)
+                .ok_or(SeedError::NoSurface { x, y })?;
+            if !self.world.get(x, y, z).material.is_habitable() {
+                return Err(SeedError::Uninhabitable { x, y, z });
+            }
+            sites.push((x, y, z, population));
+        }
+
+        for (x, y, z, population) in sites {
+            let id = next_civ_id(&self.civilizations);
+            let mut civ = Civilization::random(id, x, y, z, population, rng);
+            self.names.name_civilization(&mut civ);
+            self.civilizations.push(civ);
+        }
+        Ok(())
+    }
+
+    // Hash of the world plus every population and civilization
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.world.hash_into(&mut hasher);
+
+        for pop in &self.populations {
+            for value in [pop.species_id, pop.x, pop.y, pop.z, pop.size] {
+                hasher.write_u32(value);
+            }
+        }
+        for civ in &self.civilizations {
+            for value in [civ.id, civ.x, civ.y, civ.z, civ.population] {
+                hasher.write_u32(value);
+            }
+            hasher.write_quantized(civ.tech_level);
+        }
+
+        hasher.finish()
+    }
+
+    // True if both states hold exactly the same contents, RNG included,
+    // whatever their ticks and events: simulating on from either one gives the
+    // same future. content_hash leaves out the RNG, nutrients, rules, gods...,
+    // so states that hash alike are compared in full, as they would be saved.
+    pub fn same_contents(&self, other: &SimulationState) -> bool {
+        if self.content_hash() != other.content_hash() {
+            return false;
+        }
+        let contents = |state: &SimulationState| {
+            let mut value = serde_json::to_value(state).ok()?;
+            value.as_object_mut()?.remove("tick");
+            Some(value)
+        };
+        match (contents(self), contents(other)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    // Rough heap + inline footprint of this state (voxels and entity vectors)
+    pub fn estimated_memory_bytes(&self) -> usize {
+        size_of::<SimulationState>()
+            + self.world.voxels.len() * size_of::<Voxel>()
+            + self.species.len() * size_of::<Species>()
+            + self.populations.len() * size_of::<Population>()
+            + self
+                .civilizations
+                .iter()
+                .map(|c| size_of::<Civilization>() + c.name.len())
+                .sum::<usize>()
+    }
+}
+
+// How welcoming a voxel is to new life, from 0 to 1: fertile ground near
+// 20°C is ideal, and suitability fades over about ten degrees either way
+fn seeding_suitability(voxel: &Voxel) -> f32 {
+    let fertility = (voxel.fertility / 1.5).clamp(0.0, 1.0);
+    let deviation = (voxel.temperature - 20.0) / 10.0;
+    fertility * (-deviation * deviation / 2.0).exp()
+}
+
+// Spread of one species (see `SimulationState::species_range`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeInfo {
+    pub occupied_voxels: u32,
+    // Bounding box of the occupied voxels, both corners inclusive
+    pub min: (u32, u32, u32),
+    pub max: (u32, u32, u32),
+    // Biomass-weighted mean position
+    pub centroid: (f32, f32, f32),
+    pub biomass: u64,
+}
+
+// Local sensory input for a god or agent (see `SimulationState::local_observation`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalObs {
+    pub mean_temperature: f32,
+    pub nutrient_total: f32,
+    pub population_count: u32,
+    pub biomass: u64,
+    pub civ_count: u32,
+    // Most common material; ties go to the lowest kind (Air first)
+    pub dominant_material: VoxelMaterial,
+}
+
+impl Default for LocalObs {
+    fn default() -> Self {
         Self {
-            world,
-            physics_rules,
-            species,
-            populations,
-            civilizations: Vec::new(),
-            god_state,
+            mean_temperature: 0.0,
+            nutrient_total: 0.0,
+            population_count: 0,
+            biomass: 0,
+            civ_count: 0,
+            dominant_material: VoxelMaterial::Air,
+        }
+    }
+}
+
+// Step-by-step construction of a SimulationState; anything not set keeps its
+// default (no life, default rules, one default god, entropy-seeded RNG)
+#[derive(Default)]
+pub struct SimulationStateBuilder {
+    world: Option<World3D>,
+    physics_rules: PhysicsRules,
+    biology_rules: BiologyRules,
+    species: SpeciesRegistry,
+    populations: Vec<Population>,
+    civilizations: Vec<Civilization>,
+    gods: Vec<GodState>,
+    seed: Option<u64>,
+    tick: u64,
+}
+
+impl SimulationStateBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn world(mut self, world: World3D) -> Self {
+        self.world = Some(world);
+        self
+    }
+
+    pub fn physics_rules(mut self, rules: PhysicsRules) -> Self {
+        self.physics_rules = rules;
+        self
+    }
+
+    pub fn biology_rules(mut self, rules: BiologyRules) -> Self {
+        self.biology_rules = rules;
+        self
+    }
+
+    pub fn species(mut self, species: SpeciesRegistry) -> Self {
+        self.species = species;
+        self
+    }
+
+    pub fn populations(mut self, populations: Vec<Population>) -> Self {
+        self.populations = populations;
+        self
+    }
+
+    pub fn civilizations(mut self, civilizations: Vec<Civilization>) -> Self {
+        self.civilizations = civilizations;
+        self
+    }
+
+    // Add a god to the pantheon
+    pub fn god_state(mut self, god: GodState) -> Self {
+        self.gods.push(god);
+        self
+    }
+
+    // Seed the state's RNG (and through it the civilization names) for a
+    // reproducible run
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn tick(mut self, tick: u64) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    // The state, once its world is set, every population belongs to a
+    // registered species and all life lies inside the world
+    pub fn build(self) -> Result<SimulationState, BuildError> {
+        let world = self.world.as_ref().ok_or(BuildError::MissingWorld)?;
+        let (width, height, depth) = world.dimensions();
+        let in_bounds = |x: u32, y: u32, z: u32| x < width && y < height && z < depth;
+
+        for pop in &self.populations {
+            if self.species.get(pop.species_id).is_none() {
+                return Err(BuildError::Species(SpeciesError::UnknownId(pop.species_id)));
+            }
+            if !in_bounds(pop.x, pop.y, pop.z) {
+                return Err(BuildError::OutOfBounds { x: pop.x, y: pop.y, z: pop.z });
+            }
+        }
+        if let Some(civ) = self.civilizations.iter().find(|c| !in_bounds(c.x, c.y, c.z)) {
+            return Err(BuildError::OutOfBounds { x: civ.x, y: civ.y, z: civ.z });
+        }
+
+        Ok(self.assemble())
+    }
+
+    // The state as configured, without validation
+    fn assemble(self) -> SimulationState {
+        let mut rng = match self.seed {
+            Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+            None => ChaCha12Rng::from_entropy(),
+        };
+        let gods = if self.gods.is_empty() { vec![GodState::random(&mut rng)] } else { self.gods };
+        SimulationState {
+            tick: self.tick,
+            world: self.world.unwrap_or_else(|| World3D::new(0, 0, 0)),
+            physics_rules: Arc::new(self.physics_rules),
+            biology_rules: self.biology_rules,
+            species: Arc::new(self.species),
+            populations: self.populations,
+            civilizations: self.civilizations,
+            gods,
+            god_action_log: ActionLog::new(),
+            events: Vec::new(),
+            founding_sites: FoundingSites::new(),
+            names: NameGenerator::new(rng.gen()),
+            rng,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    MissingWorld,
+    Species(SpeciesError),
+    OutOfBounds { x: u32, y: u32, z: u32 },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingWorld => write!(f, "no world was given"),
+            BuildError::Species(err) => write!(f, "{}", err),
+            BuildError::OutOfBounds { x, y, z } => {
+                write!(f, "({},{},{}) is outside the world", x, y, z)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<SpeciesError> for BuildError {
+    fn from(err: SpeciesError) -> Self {
+        BuildError::Species(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeedError {
+    NoSurface { x: u32, y: u32 },
+    Uninhabitable { x: u32, y: u32, z: u32 },
+}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeedError::NoSurface { x, y } => {
+                write!(f, "column ({},{}) has no surface to settle on", x, y)
+            }
+            SeedError::Uninhabitable { x, y, z } => {
+                write!(f, "surface voxel ({},{},{}) is not habitable", x, y, z)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SeedError {}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Format(serde_json::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "I/O error: {}", err),
+            SnapshotError::Format(err) => write!(f, "invalid snapshot: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(err: serde_json::Error) -> Self {
+        SnapshotError::Format(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineError {
+    MemoryBudgetExceeded { required: usize, budget: usize },
+    UnknownTimeline(u32),
+    // The state at this tick was evicted to save memory
+    EvictedState(u64),
+    // An action injected into the past could not be applied
+    Action(ActionError),
+}
+
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineError::MemoryBudgetExceeded { required, budget } => write!(
+                f,
+                "memory budget exceeded: {} bytes required, budget is {} bytes",
+                required, budget
+            ),
+            TimelineError::UnknownTimeline(id) => write!(f, "no timeline with id {}", id),
+            TimelineError::EvictedState(tick) => {
+                write!(f, "the state at tick {} was evicted", tick)
+            }
+            TimelineError::Action(error) => write!(f, "cannot apply the action: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for TimelineError {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryBudget {
+    pub max_bytes: usize,
+    // Strict budgets refuse new states instead of evicting old ones
+    pub strict: bool,
+}
+
+// Cap on the states a timeline stores: only the `max_states` most recent are
+// kept, plus a keyframe every `keyframe_interval` ticks (0 = none) from which
+// dropped ticks are re-simulated on demand (see `Timeline::reconstruct`).
+// States changed from outside the simulation are always kept as keyframes.
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    pub max_states: usize,
+    pub keyframe_interval: u64,
+}
+
+// Which branch a merge takes its world (terrain, physics, gods) from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeBase {
+    First,
+    Second,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MergePolicy {
+    pub world_from: MergeBase,
+    // Bring over the other branch's civilizations and populations wherever
+    // their spot is free in the base world
+    pub union_life: bool,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        Self {
+            world_from: MergeBase::First,
+            union_life: true,
         }
     }
 }
 
 pub struct Timeline {
     pub id: u32,
-    pub states: Vec<SimulationState>,
+    // Oldest first; the retention cap drops states from the front
+    pub states: VecDeque<SimulationState>,
+    // Logical index of states[0]; older states have been evicted
+    pub first_index: usize,
+    pub last_used: u64,
+    // Root timelines have no parent; forks remember where they branched off
+    pub parent_id: Option<u32>,
+    pub fork_tick: u64,
+    lineage: Vec<(u32, u64)>,
+    // How many consecutive ticks each stored state stands for (see `compact`);
+    // missing entries count as 1
+    run_lengths: VecDeque<usize>,
+    // States kept from before `first_index` (see `Retention`), by logical index
+    keyframes: BTreeMap<usize, SimulationState>,
+    // Indices whose state was changed from outside the simulation (injected
+    // actions, direct edits), so re-simulating the tick before them does not
+    // reproduce it
+    injected: BTreeSet<usize>,
+    // A state re-simulated by `rebuild`, available until the next push
+    rebuilt: Option<SimulationState>,
 }
 
 impl Timeline {
     pub fn new(id: u32, initial_state: SimulationState) -> Self {
         Self {
             id,
-            states: vec![initial_state],
+            states: VecDeque::from([initial_state]),
+            first_index: 0,
+            last_used: 0,
+            parent_id: None,
+            fork_tick: 0,
+            lineage: Vec::new(),
+            run_lengths: VecDeque::from([1]),
+            keyframes: BTreeMap::new(),
+            injected: BTreeSet::new(),
+            rebuilt: None,
         }
     }
 
+    // Branch off `parent` at `fork_tick`, keeping its history up to that tick
+    pub fn forked_from(id: u32, parent: &Timeline, fork_tick: u64) -> Self {
+        let mut lineage = vec![(parent.id, fork_tick)];
+        lineage.extend(parent.lineage.iter().copied());
+
+        let mut forked = Self {
+            id,
+            states: parent.states.clone(),
+            first_index: parent.first_index,
+            last_used: parent.last_used,
+            parent_id: Some(parent.id),
+            fork_tick,
+            lineage,
+            run_lengths: parent.run_lengths.clone(),
+            keyframes: BTreeMap::new(),
+            injected: parent.injected.range(..=fork_tick as usize).copied().collect(),
+            rebuilt: None,
+        };
+        let fork_index = fork_tick as usize;
+        if fork_index < parent.first_index {
+            // Branching off a dropped tick (e.g. rebuilt after a rewind): the
+            // branch's stored history starts at the fork
+            forked.states.clear();
+            forked.run_lengths.clear();
+            forked.first_index = fork_index;
+            if let Some(state) = parent.get_state(fork_index) {
+                forked.states.push_back(state.clone());
+                forked.run_lengths.push_back(1);
+            }
+        } else {
+            forked.truncate(fork_index + 1 - parent.first_index);
+        }
+        forked.keyframes = parent
+            .keyframes
+            .range(..forked.first_index)
+            .map(|(&index, state)| (index, state.clone()))
+            .collect();
+        forked
+    }
+
+    // True once the last two ticks hold identical states: nothing changes anymore
+    pub fn reached_fixed_point(&self) -> bool {
+        let count = self.states.len();
+        if count > 0 && self.run_length(count - 1) > 1 {
+            return true;
+        }
+        count >= 2 && self.states[count - 2].content_hash() == self.states[count - 1].content_hash()
+    }
+
+    // (parent id, fork tick) pairs from the direct parent back to the root
+    pub fn ancestry(&self) -> Vec<(u32, u64)> {
+        self.lineage.clone()
+    }
+
+    // Store `state` at the index of its tick, dropping any later states
+    // (after a rewind, the old future is overwritten)
     pub fn push_state(&mut self, state: SimulationState) {
-        self.states.push(state);
+        let index = state.tick as usize;
+        let rebuilt = self.rebuilt.take();
+        if index <= self.first_index {
+            // Simulated on from a rebuilt state: the stored history restarts
+            // here, keeping the rebuilt state it came from
+            self.states.clear();
+            self.run_lengths.clear();
+            self.first_index = index;
+            if let Some(previous) = rebuilt.filter(|s| s.tick as usize + 1 == index) {
+                self.first_index = previous.tick as usize;
+                self.states.push_back(previous);
+                self.run_lengths.push_back(1);
+            }
+        } else {
+            self.truncate(index - self.first_index);
+        }
+        self.keyframes.split_off(&index);
+        self.injected.split_off(&index);
+        self.states.push_back(state);
+        self.run_lengths.push_back(1);
+    }
+
+    // Record that the state at `index` was changed from outside the
+    // simulation, so the retention cap keeps it instead of re-simulating it
+    pub fn mark_injected(&mut self, index: usize) {
+        self.injected.insert(index);
     }
 
+    // Stored states first, then keyframes and the state from `rebuild`
     pub fn get_state(&self, index: usize) -> Option<&SimulationState> {
-        self.states.get(index)
+        match self.locate(index) {
+            Some((i, _)) => Some(&self.states[i]),
+            None => self.keyframes.get(&index).or_else(|| {
+                self.rebuilt.as_ref().filter(|state| state.tick as usize == index)
+            }),
+        }
     }
 
+    // A compacted run is split first, so the change only affects `index`.
+    // The state counts as injected from then on (see `mark_injected`).
     pub fn get_state_mut(&mut self, index: usize) -> Option<&mut SimulationState> {
-        self.states.get_mut(index)
+        if self.get_state(index).is_some() {
+            self.mark_injected(index);
+        }
+        match self.locate(index) {
+            Some((i, offset)) => {
+                let i = self.split_run(i, offset);
+                self.states.get_mut(i)
+            }
+            None if self.keyframes.contains_key(&index) => self.keyframes.get_mut(&index),
+            None => self.rebuilt.as_mut().filter(|state| state.tick as usize == index),
+        }
     }
 
+    // The state at logical `index`, re-simulated from the nearest earlier
+    // keyframe if it is no longer stored. Injected states are kept as
+    // keyframes, so the replay never has to cross one. None for ticks not
+    // reached yet, or dropped with no keyframe before them.
+    pub fn reconstruct(&self, index: usize) -> Option<SimulationState> {
+        let (start, mut state) = match self.get_state(index) {
+            Some(state) => (index, state.clone()),
+            None if index >= self.first_index => return None,
+            None => {
+                let (&start, keyframe) = self.keyframes.range(..=index).next_back()?;
+                // An injected state evicted under a memory budget is gone for good
+                if self.injected.range(start + 1..=index).next().is_some() {
+                    return None;
+                }
+                (start, keyframe.clone())
+            }
+        };
+        // A compacted run stores its first state for every tick it covers
+        state.tick = start as u64;
+        while state.tick < index as u64 {
+            simulate_tick(&mut state);
+        }
+        Some(state)
+    }
+
+    // Make the state at `index` available to `get_state` again, rebuilding it
+    // with `reconstruct` if it was dropped. False if it cannot be rebuilt.
+    pub fn rebuild(&mut self, index: usize) -> bool {
+        if self.get_state(index).is_some() {
+            return true;
+        }
+        self.rebuilt = self.reconstruct(index);
+        self.rebuilt.is_some()
+    }
+
+    // Drop the oldest stored states beyond the `max_states` most recent,
+    // keeping as keyframes those that cover a multiple of `keyframe_interval`
+    // or an injected state
+    pub fn enforce_retention(&mut self, retention: Retention) {
+        let interval = retention.keyframe_interval as usize;
+        while self.states.len() > retention.max_states.max(1) {
+            let (start, length) = (self.first_index, self.run_length(0));
+            let state = self.states.pop_front().expect("more states than the cap");
+            self.run_lengths.pop_front();
+            self.first_index += length;
+            let periodic = interval > 0 && (interval - start % interval) % interval < length;
+            if periodic || self.injected.range(start..start + length).next().is_some() {
+                self.keyframes.insert(start, state);
+            }
+        }
+    }
+
+    // Logical length, including evicted states
     pub fn len(&self) -> usize {
-        self.states.len()
+        self.first_index + (0..self.states.len()).map(|i| self.run_length(i)).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.states
+            .iter()
+            .chain(self.keyframes.values())
+            .chain(self.rebuilt.iter())
+            .map(|s| s.estimated_memory_bytes())
+            .sum()
+    }
+
+    // Collapse runs of consecutive states with the same contents (see
+    // `SimulationState::same_contents`) into a single stored state. Every index
+    // of a run then resolves to its first state, whose contents (RNG included)
+    // only differ from the dropped ones by their `tick`.
+    pub fn compact(&mut self) {
+        let mut states: VecDeque<SimulationState> = VecDeque::with_capacity(self.states.len());
+        let mut run_lengths: VecDeque<usize> = VecDeque::with_capacity(self.states.len());
+
+        for (i, state) in std::mem::take(&mut self.states).into_iter().enumerate() {
+            let length = self.run_length(i);
+            if let (Some(previous), Some(run)) = (states.back(), run_lengths.back_mut()) {
+                if previous.same_contents(&state) {
+                    *run += length;
+                    continue;
+                }
+            }
+            states.push_back(state);
+            run_lengths.push_back(length);
+        }
+
+        self.states = states;
+        self.run_lengths = run_lengths;
+    }
+
+    fn run_length(&self, i: usize) -> usize {
+        self.run_lengths.get(i).copied().unwrap_or(1)
+    }
+
+    // Stored position holding logical `index`, and the offset of `index` in its run
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        let mut offset = index.checked_sub(self.first_index)?;
+        for i in 0..self.states.len() {
+            let length = self.run_length(i);
+            if offset < length {
+                return Some((i, offset));
+            }
+            offset -= length;
+        }
+        None
+    }
+
+    // Give `offset` within the run stored at `i` its own copy; returns its position
+    fn split_run(&mut self, i: usize, offset: usize) -> usize {
+        let length = self.run_length(i);
+        if length == 1 {
+            return i;
+        }
+        self.run_lengths.resize(self.states.len(), 1);
+
+        // The run keeps its slot for the part before `offset` (if any); the
+        // copies go right after it
+        let mut target = i;
+        if offset > 0 {
+            self.run_lengths[i] = offset;
+            target = i + 1;
+            self.states.insert(target, self.states[i].clone());
+            self.run_lengths.insert(target, 1);
+        } else {
+            self.run_lengths[i] = 1;
+        }
+        if offset + 1 < length {
+            self.states.insert(target + 1, self.states[i].clone());
+            self.run_lengths.insert(target + 1, length - offset - 1);
+        }
+        target
+    }
+
+    // Keep only the first `logical_len` states after `first_index`
+    fn truncate(&mut self, logical_len: usize) {
+        let mut remaining = logical_len;
+        let mut keep = 0;
+        while keep < self.states.len() && remaining > 0 {
+            let length = self.run_length(keep);
+            if length > remaining {
+                self.run_lengths.resize(self.states.len(), 1);
+                self.run_lengths[keep] = remaining;
+            }
+            remaining = remaining.saturating_sub(length);
+            keep += 1;
+        }
+        self.states.truncate(keep);
+        self.run_lengths.resize(keep, 1);
+    }
+
+    // Drop the oldest stored state, never evicting the state at `keep_index`
+    fn evict_oldest(&mut self, keep_index: Option<usize>) -> Option<usize> {
+        if self.states.is_empty() {
+            return None;
+        }
+        let length = self.run_length(0);
+        if let Some(keep) = keep_index {
+            if keep >= self.first_index && keep < self.first_index + length {
+                return None;
+            }
+        }
+        let freed = self.states.pop_front()?.estimated_memory_bytes();
+        self.run_lengths.pop_front();
+        self.first_index += length;
+        Some(freed)
+    }
+}
+
+// Smallest gap in average tech or temperature that counts as a divergence in
+// `Multiverse::diff_timelines` (biomass and civ counts must match exactly)
+pub const DIVERGENCE_EPSILON: f32 = 1e-3;
+
+// Timeline b minus timeline a, at one logical tick index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickDelta {
+    pub index: usize,
+    pub biomass: i64,
+    pub civ_count: i64,
+    pub avg_tech: f32,
+    pub avg_temperature: f32,
+}
+
+impl TickDelta {
+    pub fn zero(index: usize) -> Self {
+        Self {
+            index,
+            biomass: 0,
+            civ_count: 0,
+            avg_tech: 0.0,
+            avg_temperature: 0.0,
+        }
+    }
+
+    pub fn is_divergent(&self) -> bool {
+        self.biomass != 0
+            || self.civ_count != 0
+            || self.avg_tech.abs() > DIVERGENCE_EPSILON
+            || self.avg_temperature.abs() > DIVERGENCE_EPSILON
     }
 }
 
+// Result of `Multiverse::diff_timelines`: one delta per tick index stored in
+// both timelines, and the first index where they diverge (None if they agree)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineDiff {
+    pub a: u32,
+    pub b: u32,
+    pub deltas: Vec<TickDelta>,
+    pub first_divergence: Option<usize>,
+}
+
+// One row of `Multiverse::timeline_summary`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineInfo {
+    pub id: u32,
+    pub parent_id: Option<u32>,
+    pub fork_tick: u64,
+    pub length: usize,
+    // Counts taken from the latest stored state
+    pub civ_count: usize,
+    pub biomass: u64,
+}
+
 pub struct Multiverse {
     pub timelines: Vec<Timeline>,
     pub current_timeline: u32,
     pub current_tick: u64,
+    pub memory_budget: Option<MemoryBudget>,
+    pub retention: Option<Retention>,
+    access_clock: u64,
+    // Highest timeline id handed out so far
+    last_timeline_id: u32,
 }
 
 impl Multiverse {
@@ -76,20 +1121,195 @@ impl Multiverse {
             timelines: vec![timeline],
             current_timeline: 0,
             current_tick: 0,
+            memory_budget: None,
+            retention: None,
+            access_clock: 0,
+            last_timeline_id: 0,
         }
     }
 
+    pub fn with_memory_budget(mut self, budget: MemoryBudget) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.timelines
+            .iter()
+            .map(|t| t.estimated_memory_bytes())
+            .sum()
+    }
+
+    // Branch the current timeline at the current tick and switch to the new branch
+    pub fn fork(&mut self) -> u32 {
+        let id = self.next_timeline_id();
+        let forked = Timeline::forked_from(id, self.current_timeline(), self.current_tick);
+        self.timelines.push(forked);
+        self.current_timeline = id;
+        id
+    }
+
+    // Make timeline `id` current, positioned at its latest tick
+    pub fn switch_timeline(&mut self, id: u32) -> Result<(), TimelineError> {
+        self.access_clock += 1;
+        let clock = self.access_clock;
+        let timeline = self
+            .timelines
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or(TimelineError::UnknownTimeline(id))?;
+        timeline.last_used = clock;
+        self.current_tick = timeline.len().saturating_sub(1) as u64;
+        self.current_timeline = id;
+        Ok(())
+    }
+
+    // Combine the latest states of timelines `a` and `b` into a new timeline and
+    // switch to it. The merged timeline starts at the later of the two ticks.
+    pub fn merge_into_new(
+        &mut self,
+        a: u32,
+        b: u32,
+        policy: MergePolicy,
+    ) -> Result<u32, TimelineError> {
+        // Each timeline with its latest state and tick (a compacted run's state
+        // carries the run's first tick, not its last)
+        let find = |id: u32| {
+            self.timelines
+                .iter()
+                .find(|t| t.id == id)
+                .and_then(|t| t.states.back().map(|state| (t, state, t.len() as u64 - 1)))
+                .ok_or(TimelineError::UnknownTimeline(id))
+        };
+        let (timeline_a, first, first_tick) = find(a)?;
+        let (_, second, second_tick) = find(b)?;
+        let (base, other) = match policy.world_from {
+            MergeBase::First => (first, second),
+            MergeBase::Second => (second, first),
+        };
+
+        let mut merged = base.clone();
+        merged.tick = first_tick.max(second_tick);
+        merged.events.clear();
+        if policy.union_life {
+            merged.absorb_life(other);
+        }
+
+        let mut timeline = Timeline::new(0, merged);
+        timeline.first_index = timeline.states[0].tick as usize;
+        timeline.parent_id = Some(a);
+        timeline.fork_tick = timeline.states[0].tick;
+        // Ancestry follows the first branch; the second one is only mixed in
+        timeline.lineage = vec![(a, timeline.fork_tick)];
+        timeline.lineage.extend(timeline_a.lineage.iter().copied());
+        let id = self.next_timeline_id();
+        timeline.id = id;
+        self.access_clock += 1;
+        timeline.last_used = self.access_clock;
+
+        self.current_tick = timeline.fork_tick;
+        self.timelines.push(timeline);
+        self.current_timeline = id;
+        Ok(id)
+    }
+
+    pub fn try_current_timeline(&self) -> Option<&Timeline> {
+        let id = self.current_timeline;
+        self.timelines.iter().find(|t| t.id == id)
+    }
+
+    pub fn try_current_timeline_mut(&mut self) -> Option<&mut Timeline> {
+        let id = self.current_timeline;
+        self.timelines.iter_mut().find(|t| t.id == id)
+    }
+
+    // Panics if `current_timeline` does not name an existing timeline
     pub fn current_timeline_mut(&mut self) -> &mut Timeline {
-        &mut self.timelines[self.current_timeline as usize]
+        let id = self.current_timeline;
+        self.try_current_timeline_mut()
+            .unwrap_or_else(|| panic!("current timeline {} does not exist", id))
     }
 
+    // Panics if `current_timeline` does not name an existing timeline
     pub fn current_timeline(&self) -> &Timeline {
-        &self.timelines[self.current_timeline as usize]
+        self.try_current_timeline()
+            .unwrap_or_else(|| panic!("current timeline {} does not exist", self.current_timeline))
     }
 
-    pub fn push_state(&mut self, state: SimulationState) {
-        self.current_timeline_mut().push_state(state);
-        self.current_tick += 1;
+    // Ids stay unique even once timelines are removed, the newest included
+    fn next_timeline_id(&mut self) -> u32 {
+        let highest = self.timelines.iter().map(|t| t.id).max().unwrap_or(0);
+        self.last_timeline_id = self.last_timeline_id.max(highest) + 1;
+        self.last_timeline_id
+    }
+
+    pub fn push_state(&mut self, state: SimulationState) -> Result<(), TimelineError> {
+        self.store_state(state, false)
+    }
+
+    // push_state, marking the new state as injected (see `Timeline::mark_injected`)
+    // before the retention cap applies
+    fn store_state(&mut self, state: SimulationState, injected: bool) -> Result<(), TimelineError> {
+        if let Some(budget) = self.memory_budget {
+            let required = self.estimated_memory_bytes() + state.estimated_memory_bytes();
+            if required > budget.max_bytes {
+                if budget.strict {
+                    return Err(TimelineError::MemoryBudgetExceeded {
+                        required,
+                        budget: budget.max_bytes,
+                    });
+                }
+                self.evict_until(required - budget.max_bytes);
+            }
+        }
+
+        self.access_clock += 1;
+        let clock = self.access_clock;
+        let tick = state.tick;
+        let retention = self.retention;
+        let timeline = self.current_timeline_mut();
+        timeline.push_state(state);
+        if injected {
+            timeline.mark_injected(tick as usize);
+        }
+        if let Some(retention) = retention {
+            timeline.enforce_retention(retention);
+        }
+        timeline.last_used = clock;
+        self.current_tick = tick;
+        Ok(())
+    }
+
+    // Evict oldest states, least-recently-used non-current timelines first,
+    // then the current timeline (never its current state)
+    fn evict_until(&mut self, mut to_free: usize) {
+        let current_id = self.current_timeline;
+        let current = match self.timelines.iter().position(|t| t.id == current_id) {
+            Some(current) => current,
+            None => return,
+        };
+        let mut order: Vec<usize> = (0..self.timelines.len()).filter(|&i| i != current).collect();
+        order.sort_by_key(|&i| self.timelines[i].last_used);
+        order.push(current);
+
+        for i in order {
+            let keep = if i == current {
+                Some(self.current_tick as usize)
+            } else {
+                None
+            };
+            while to_free > 0 {
+                match self.timelines[i].evict_oldest(keep) {
+                    Some(freed) => to_free = to_free.saturating_sub(freed),
+                    None => break,
+                }
+            }
+        }
     }
 
     pub fn current_state(&self) -> Option<&SimulationState> {
@@ -103,32 +1323,1373 @@ impl Multiverse {
         timeline.get_state_mut(tick)
     }
 
+    // The current timeline's state at `tick`. Stored ticks are returned as
+    // they are; later ones are re-simulated from the last stored state, which
+    // carries its own RNG, so the replay is deterministic (with the autonomous
+    // gods: injected actions are not replayed). Ticks dropped by the retention
+    // cap are rebuilt from a keyframe; None if `tick` was evicted for good.
+    pub fn replay_to(&self, tick: u64) -> Option<SimulationState> {
+        let timeline = self.current_timeline();
+        let index = tick as usize;
+        if index < timeline.first_index {
+            return timeline.reconstruct(index);
+        }
+        let start = index.min(timeline.len().checked_sub(1)?);
+        let mut state = timeline.get_state(start)?.clone();
+        // A compacted run stores its first state for every tick it covers
+        state.tick = start as u64;
+        while state.tick < tick {
+            simulate_tick(&mut state);
+        }
+        Some(state)
+    }
+
+    // True when the last `window` states up to the current tick are identical
+    // and nothing eventful happened in them: simulating further is pointless
+    pub fn is_at_equilibrium(&self, window: usize) -> bool {
+        let current = self.current_tick as usize;
+        if window == 0 || current + 1 < window {
+            return false;
+        }
+
+        let timeline = self.current_timeline();
+        let mut reference = None;
+        for index in (current + 1 - window)..=current {
+            let state = match timeline.get_state(index) {
+                Some(state) => state,
+                None => return false,
+            };
+            if !state.events.is_empty() {
+                return false;
+            }
+            let hash = state.content_hash();
+            if *reference.get_or_insert(hash) != hash {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // A tick dropped by the retention cap is rebuilt from the nearest keyframe
     pub fn rewind(&mut self, ticks: u64) {
         if ticks > self.current_tick {
             self.current_tick = 0;
         } else {
             self.current_tick -= ticks;
         }
+        let tick = self.current_tick as usize;
+        if let Some(timeline) = self.try_current_timeline_mut() {
+            timeline.rebuild(tick);
+        }
+    }
+
+    // Time travel: go back `ticks` ticks, apply `action` to the state found
+    // there and fork a new timeline from the altered state, leaving the
+    // original history intact. Returns the new timeline's id; it is current,
+    // so stepping forward simulates the altered future. Nothing changes if
+    // the action fails.
+    pub fn rewind_and_apply(
+        &mut self,
+        ticks: u64,
+        action: GodAction,
+    ) -> Result<u32, TimelineError> {
+        let tick = self.current_tick.saturating_sub(ticks);
+        let mut state = self
+            .current_timeline()
+            .reconstruct(tick as usize)
+            .ok_or(TimelineError::EvictedState(tick))?;
+        let effect =
+            crate::god::apply_action(&mut state, action.clone()).map_err(TimelineError::Action)?;
+        if !matches!(action, GodAction::None) {
+            state.god_action_log.push(tick, action.clone());
+        }
+        state.events.push(SimEvent::GodIntervention {
+            god: 0,
+            action,
+            reason: format!("sent back in time to tick {}", tick),
+            outcome: Ok(effect),
+        });
+
+        // Not `rewind`: the state is already rebuilt
+        self.current_tick = tick;
+        let id = self.fork();
+        self.store_state(state, true)?;
+        Ok(id)
     }
 
     pub fn get_tick(&self) -> u64 {
         self.current_tick
     }
+
+    // Compare timelines `a` and `b` tick by tick over the indices both still
+    // store (their overlapping prefix, minus evicted states). Indices whose
+    // states hash the same (see `SimulationState::content_hash`) get a zero
+    // delta without gathering their metrics.
+    pub fn diff_timelines(&self, a: u32, b: u32) -> Result<TimelineDiff, TimelineError> {
+        let find = |id: u32| {
+            self.timelines
+                .iter()
+                .find(|t| t.id == id)
+                .ok_or(TimelineError::UnknownTimeline(id))
+        };
+        let (timeline_a, timeline_b) = (find(a)?, find(b)?);
+
+        let start = timeline_a.first_index.max(timeline_b.first_index);
+        let end = timeline_a.len().min(timeline_b.len());
+        let deltas: Vec<TickDelta> = (start..end)
+            .filter_map(|index| {
+                let (first, second) = (timeline_a.get_state(index)?, timeline_b.get_state(index)?);
+                // Identical contents: no need to gather the metrics
+                if first.content_hash() == second.content_hash() {
+                    return Some(TickDelta::zero(index));
+                }
+                let (first, second) = (collect_metrics(first), collect_metrics(second));
+                Some(TickDelta {
+                    index,
+                    biomass: second.total_biomass as i64 - first.total_biomass as i64,
+                    civ_count: second.civ_count as i64 - first.civ_count as i64,
+                    avg_tech: second.avg_tech - first.avg_tech,
+                    avg_temperature: second.temperature.mean - first.temperature.mean,
+                })
+            })
+            .collect();
+        let first_divergence = deltas.iter().find(|d| d.is_divergent()).map(|d| d.index);
+
+        Ok(TimelineDiff {
+            a,
+            b,
+            deltas,
+            first_divergence,
+        })
+    }
+
+    // Quick overview of every timeline, in id order
+    pub fn timeline_summary(&self) -> Vec<TimelineInfo> {
+        let mut infos: Vec<TimelineInfo> = self
+            .timelines
+            .iter()
+            .map(|timeline| {
+                let last = timeline.states.back();
+                TimelineInfo {
+                    id: timeline.id,
+                    parent_id: timeline.parent_id,
+                    fork_tick: timeline.fork_tick,
+                    length: timeline.len(),
+                    civ_count: last.map_or(0, |s| s.civilizations.len()),
+                    biomass: last.map_or(0, |s| {
+                        s.populations.iter().map(|p| p.size as u64).sum()
+                    }),
+                }
+            })
+            .collect();
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+
+    // Gauges describing the current state, in the Prometheus text exposition
+    // format, for scraping by monitoring tools
+    pub fn metrics_text(&self) -> String {
+        let mut gauges: Vec<(&str, &str, f64)> = vec![
+            ("sim_tick", "Current tick of the current timeline", self.current_tick as f64),
+            ("sim_timelines", "Number of timelines", self.timelines.len() as f64),
+        ];
+        if let Some(state) = self.current_state() {
+            let metrics = collect_metrics(state);
+            gauges.extend([
+                ("sim_civilizations", "Number of civilizations", metrics.civ_count as f64),
+                ("sim_biomass", "Total size of all populations", metrics.total_biomass as f64),
+                ("sim_avg_tech", "Mean civilization tech level", metrics.avg_tech as f64),
+                (
+                    "sim_avg_temperature",
+                    "Mean voxel temperature in degrees Celsius",
+                    metrics.temperature.mean as f64,
+                ),
+            ]);
+        }
+
+        let mut text = String::new();
+        for (name, help, value) in gauges {
+            text.push_str(&format!("# HELP {} {}\n", name, help));
+            text.push_str(&format!("# TYPE {} gauge\n", name));
+            text.push_str(&format!("{} {}\n", name, value));
+        }
+        text
+    }
+
+    // Simulate one tick from the current state and push the result
+    pub fn step(&mut self) -> Result<(), TimelineError> {
+        self.step_with_injector(&mut |_, _| None)
+    }
+
+    pub fn step_with_injector(
+        &mut self,
+        injector: &mut ActionInjector,
+    ) -> Result<(), TimelineError> {
+        let mut state = match self.current_state() {
+            Some(state) => state.clone(),
+            None => return Ok(()),
+        };
+        // A compacted run stores its first state for every tick it covers
+        state.tick = self.current_tick;
+        let mut injected = false;
+        simulate_tick_with_injector(&mut state, &mut |tick, state: &SimulationState| {
+            let action = injector(tick, state);
+            injected |= action.is_some();
+            action
+        });
+        self.store_state(state, injected)
+    }
+
+    pub fn run(&mut self, ticks: u64, injector: &mut ActionInjector) -> Result<(), TimelineError> {
+        for _ in 0..ticks {
+            self.step_with_injector(injector)?;
+        }
+        Ok(())
+    }
+
+    // Lazy, endless stepping: each `next()` simulates one tick (see `Ticks`)
+    pub fn ticks(&mut self) -> Ticks<'_> {
+        Ticks { multiverse: self }
+    }
+}
+
+// Iterator returned by `Multiverse::ticks`. Every item is a snapshot of the
+// state just pushed; iteration stops when a step fails (e.g. a strict memory
+// budget is exceeded) or there is no current state to step from.
+pub struct Ticks<'a> {
+    multiverse: &'a mut Multiverse,
 }
 
+impl Iterator for Ticks<'_> {
+    type Item = SimulationState;
+
+    fn next(&mut self) -> Option<SimulationState> {
+        let tick = self.multiverse.current_tick;
+        self.multiverse.step().ok()?;
+        if self.multiverse.current_tick == tick {
+            return None;
+        }
+        self.multiverse.current_state().cloned()
+    }
+}
+
+// Hook for experiments: called each tick before the autonomous gods with the
+// tick being simulated; returning an action replaces the gods for that tick
+pub type ActionInjector<'a> = dyn FnMut(u64, &SimulationState) -> Option<GodAction> + 'a;
+
 pub fn simulate_tick(state: &mut SimulationState) {
+    advance_world(state);
+
+    // Step the gods (returns the actions taken)
+    let _god_actions = crate::god::step_god(state);
+
+    state.world.ensure_material_counts();
+}
+
+pub fn simulate_tick_with_injector(state: &mut SimulationState, injector: &mut ActionInjector) {
+    advance_world(state);
+
+    match injector(state.tick, state) {
+        Some(action) => {
+            if !matches!(action, GodAction::None) {
+                state.god_action_log.push(state.tick, action.clone());
+            }
+            let outcome = crate::god::apply_action(state, action.clone());
+            state.events.push(SimEvent::GodIntervention {
+                god: 0,
+                action,
+                reason: format!("injected at tick {}", state.tick),
+                outcome,
+            });
+        }
+        None => {
+            crate::god::step_god(state);
+        }
+    }
+
+    state.world.ensure_material_counts();
+}
+
+// Share of a population that branches off into a new species
+const SPECIATION_SPLIT: f32 = 0.1;
+
+// Share of a population or civilization lost when the sea floods its voxel
+const DROWNING_LOSS: f32 = 0.5;
+
+// Raise the sea every `sea_rise_interval` ticks, then flood what it reached
+fn rise_seas(state: &mut SimulationState) {
+    let interval = state.physics_rules.sea_rise_interval;
+    if interval > 0 && state.tick.is_multiple_of(interval) {
+        state.world.sea_level = (state.world.sea_level + 1).min(state.world.depth);
+    }
+    let (voxels_flooded, population_lost) = state.flood_to_sea_level();
+    if voxels_flooded > 0 {
+        state.events.push(SimEvent::SeaLevelRise {
+            sea_level: state.world.sea_level,
+            voxels_flooded,
+            population_lost,
+        });
+    }
+}
+
+// Large populations occasionally bud off a new species that carries their
+// local adaptation. The species registry is only unshared when that happens.
+fn speciate(state: &mut SimulationState) {
+    let rules = &state.biology_rules;
+    let rng = &mut state.rng;
+    let founders: Vec<usize> = (0..state.populations.len())
+        .filter(|&i| {
+            state.populations[i].size >= rules.speciation_min_size
+                && rng.gen::<f32>() < rules.speciation_chance
+        })
+        .collect();
+
+    for i in founders {
+        let species = Arc::make_mut(&mut state.species);
+        if !species.make_room(state.biology_rules.max_species, &state.populations) {
+            break;
+        }
+        let founder = &state.populations[i];
+        let mutant = species.create_mutant(founder.species_id, &founder.drift, &mut state.rng);
+        let child = match mutant {
+            Some(child) => child.id,
+            None => continue,
+        };
+
+        let founder = &mut state.populations[i];
+        let offspring = (founder.size as f32 * SPECIATION_SPLIT) as u32;
+        founder.size -= offspring;
+        let (parent_id, x, y, z) = (founder.species_id, founder.x, founder.y, founder.z);
+        state.populations.push(Population::new(child, x, y, z, offspring));
+        state.events.push(SimEvent::Speciation {
+            parent_id,
+            species_id: child,
+            x,
+            y,
+            z,
+        });
+    }
+}
+
+// Everything in a tick except the gods
+fn advance_world(state: &mut SimulationState) {
+    state.tick += 1;
+    state.events.clear();
+
     // Apply physics
-    crate::physics::apply_physics(&mut state.world, &state.physics_rules);
+    let (rules, tick) = (&state.physics_rules, state.tick);
+    crate::physics::apply_physics(&mut state.world, rules, tick, &mut state.rng);
+    rise_seas(state);
 
     // Step biology
-    crate::biology::step_biology(&mut state.world, &state.species, &mut state.populations);
+    let ledger = crate::biology::step_biology(
+        &mut state.world,
+        &state.biology_rules,
+        &state.species,
+        &mut state.populations,
+        &mut state.rng,
+    );
+    if state.biology_rules.accounting && ledger.discrepancy() != 0 {
+        eprintln!(
+            "Warning: biomass not conserved ({:+} unexplained): {:?}",
+            ledger.discrepancy(),
+            ledger
+        );
+    }
+    for &(species_id, population_lost) in &ledger.missing_species {
+        state.events.push(SimEvent::MissingSpecies {
+            species_id,
+            population_lost,
+        });
+    }
+    speciate(state);
 
     // Maybe spawn new civilizations
-    crate::civilization::maybe_spawn_civilizations(&state.populations, &mut state.civilizations);
+    crate::civilization::maybe_spawn_civilizations(
+        &state.world,
+        &state.populations,
+        &mut state.civilizations,
+        &state.biology_rules,
+        &mut state.founding_sites,
+        &mut state.names,
+        &mut state.rng,
+    );
 
     // Step civilizations
-    crate::civilization::step_civilizations(&state.world, &mut state.civilizations);
+    let collapsed = crate::civilization::step_civilizations(
+        &state.world,
+        &mut state.civilizations,
+        state.biology_rules.max_civ_population,
+        &mut state.names,
+        &mut state.rng,
+    );
+    for civ in collapsed {
+        state.founding_sites.mark((civ.x, civ.y, civ.z), state.biology_rules.founding_cooldown);
+    }
+    crate::civilization::mine_deposits(&mut state.world, &mut state.civilizations);
+    crate::civilization::emit_urban_heat(
+        &mut state.world,
+        &state.civilizations,
+        state.physics_rules.urban_heat,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SimulationConfig;
+    use crate::fixtures::CANONICAL;
+    use crate::god::CatastropheKind;
+
+    // A small seeded world with life, as `run_simulation` would start it
+    fn seeded_state(seed: u64) -> SimulationState {
+        let config = SimulationConfig { width: 16, height: 16, depth: 8, ..Default::default() };
+        config.initial_state(seed)
+    }
+
+    #[test]
+    fn a_rising_sea_floods_dry_lowlands_and_their_life() {
+        // A low soil shore at x = 0 and a soil hill at x = 1, over rock
+        let mut world = World3D::new(2, 1, 4);
+        world.set_material(0, 0, 0, VoxelMaterial::Rock);
+        world.set_material(0, 0, 1, VoxelMaterial::Soil);
+        for z in 0..3 {
+            world.set_material(1, 0, z, VoxelMaterial::Rock);
+        }
+        world.set_material(1, 0, 3, VoxelMaterial::Soil);
+        world.sea_level = 1;
+        assert!(world.flood_to_sea_level().is_empty());
+
+        let mut species = SpeciesRegistry::new();
+        species.create(&mut ChaCha12Rng::seed_from_u64(0));
+        let mut state = SimulationStateBuilder::new()
+            .world(world)
+            .species(species)
+            .populations(vec![Population::new(0, 0, 0, 1, 100), Population::new(0, 1, 0, 3, 100)])
+            .seed(0)
+            .build()
+            .unwrap();
+
+        state.world.sea_level = 2;
+        let (flooded, lost) = state.flood_to_sea_level();
+        assert_eq!(state.world.get(0, 0, 1).material, VoxelMaterial::Water);
+        assert_eq!(state.world.get(1, 0, 1).material, VoxelMaterial::Rock);
+        assert_eq!(flooded, 1);
+        assert_eq!(lost, 50);
+        let sizes: Vec<(u32, u32)> = state.populations.iter().map(|p| (p.x, p.size)).collect();
+        assert_eq!(sizes, [(0, 50), (1, 100)]);
+    }
+
+    #[test]
+    fn replaying_after_a_rewind_reproduces_the_next_state() {
+        let mut multiverse = Multiverse::new(seeded_state(11));
+        for _ in 0..20 {
+            multiverse.step().unwrap();
+        }
+        let original = multiverse.current_timeline().get_state(11).unwrap().content_hash();
+
+        multiverse.rewind(10);
+        multiverse.step().unwrap();
+        assert_eq!(multiverse.current_tick, 11);
+        assert_eq!(multiverse.current_state().unwrap().content_hash(), original);
+    }
+
+    #[test]
+    fn runs_from_the_same_seed_are_identical() {
+        let run = |seed: u64| {
+            let mut state = seeded_state(seed);
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            state.seed_civilizations(&[(4, 4, 0, 2000), (11, 11, 0, 2000)], &mut rng).unwrap();
+            let mut multiverse = Multiverse::new(state);
+            let mut trace = Vec::new();
+            for _ in 0..1000 {
+                multiverse.step().unwrap();
+                trace.push(multiverse.current_state().unwrap().content_hash());
+            }
+            let metrics = collect_metrics(multiverse.current_state().unwrap());
+            (trace, metrics.total_biomass, metrics.civ_count)
+        };
+
+        let (trace, biomass, civ_count) = run(21);
+        assert_eq!(run(21), (trace, biomass, civ_count));
+    }
+
+    #[test]
+    fn saved_states_load_back_identically() {
+        let mut state = seeded_state(4);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(4);
+        state.seed_civilizations(&[(4, 4, 0, 2000)], &mut rng).unwrap();
+        for _ in 0..100 {
+            simulate_tick(&mut state);
+        }
+        state.world.get_mut(0, 0, 0).set_material(VoxelMaterial::Organic(200));
+
+        let path = std::env::temp_dir().join(format!("snapshot_{}.json", std::process::id()));
+        state.save_to_path(&path).unwrap();
+        let mut loaded = SimulationState::load_from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.tick, 100);
+        assert!(!loaded.civilizations.is_empty());
+        assert_eq!(loaded.world.voxels, state.world.voxels);
+        let civs = |state: &SimulationState| serde_json::to_string(&state.civilizations).unwrap();
+        assert_eq!(civs(&loaded), civs(&state));
+        // The RNG is restored too, so both carry on alike
+        simulate_tick(&mut state);
+        simulate_tick(&mut loaded);
+        assert_eq!(loaded.content_hash(), state.content_hash());
+    }
+
+    #[test]
+    fn diffs_find_where_timelines_diverge() {
+        let mut multiverse = Multiverse::new(seeded_state(6));
+        for _ in 0..10 {
+            multiverse.step().unwrap();
+        }
+        // Branch at tick 6 with extra life, and run the branch past the original
+        multiverse.rewind(4);
+        let branch = multiverse.fork();
+        multiverse.current_state_mut().unwrap().populations[0].size += 500;
+        for _ in 0..8 {
+            multiverse.step().unwrap();
+        }
+
+        let diff = multiverse.diff_timelines(0, branch).unwrap();
+        // Only the ticks both store: 0 to 10, not the branch's 11 to 14
+        assert_eq!(diff.deltas.len(), 11);
+        assert_eq!(diff.deltas.last().unwrap().index, 10);
+        assert_eq!(diff.first_divergence, Some(6));
+        assert!(diff.deltas[..6].iter().all(|delta| !delta.is_divergent()));
+        assert!(diff.deltas[6].biomass >= 500);
+    }
+
+    #[test]
+    fn blessings_sent_back_in_time_change_the_future() {
+        let mut multiverse = Multiverse::new(CANONICAL.initial_state());
+        for _ in 0..30 {
+            multiverse.step().unwrap();
+        }
+        let civ_id = multiverse.current_state().unwrap().civilizations[0].id;
+        let tech_at = |multiverse: &Multiverse, timeline: u32, tick: usize| {
+            let timeline = multiverse.timelines.iter().find(|t| t.id == timeline).unwrap();
+            let state = timeline.get_state(tick).unwrap();
+            state.civilizations.iter().find(|c| c.id == civ_id).unwrap().tech_level
+        };
+
+        let bless = GodAction::BlessCivilization { civ_id, tech_boost: 2.0 };
+        let branch = multiverse.rewind_and_apply(10, bless).unwrap();
+        assert_eq!(multiverse.get_tick(), 20);
+        for _ in 0..10 {
+            multiverse.step().unwrap();
+        }
+
+        let diff = multiverse.diff_timelines(0, branch).unwrap();
+        assert_eq!(diff.first_divergence, Some(20));
+        assert_eq!(tech_at(&multiverse, branch, 19), tech_at(&multiverse, 0, 19));
+        for tick in 20..=30 {
+            assert!(tech_at(&multiverse, branch, tick) > tech_at(&multiverse, 0, tick) + 1.0);
+        }
+    }
 
-    // Step god (returns the action taken)
-    let _god_action = crate::god::step_god(state);
+    #[test]
+    fn capped_timelines_rebuild_dropped_ticks_like_a_full_history() {
+        let retention = Retention { max_states: 5, keyframe_interval: 10 };
+        let mut capped = Multiverse::new(seeded_state(21)).with_retention(retention);
+        let mut full = Multiverse::new(seeded_state(21));
+        // The sea rise at tick 13 comes from outside: re-simulating tick 12
+        // from the keyframe at tick 10 would not reproduce it
+        let mut flood = |tick: u64, _: &SimulationState| {
+            (tick == 13).then_some(GodAction::RaiseSeaLevel { layers: 1 })
+        };
+        capped.run(40, &mut flood).unwrap();
+        full.run(40, &mut flood).unwrap();
+
+        let hash_at = |multiverse: &Multiverse, tick: usize| {
+            multiverse.current_timeline().reconstruct(tick).unwrap().content_hash()
+        };
+        assert_eq!(capped.current_timeline().states.len(), 5);
+        assert_eq!(capped.current_timeline().len(), full.current_timeline().len());
+        for tick in 0..=40 {
+            assert_eq!(hash_at(&capped, tick), hash_at(&full, tick), "tick {}", tick);
+        }
+
+        // Rewinding to a dropped tick after the flood, then simulating on
+        capped.rewind(25);
+        full.rewind(25);
+        assert_eq!(capped.current_state().unwrap().content_hash(), hash_at(&full, 15));
+        capped.step().unwrap();
+        full.step().unwrap();
+        assert_eq!(capped.get_tick(), 16);
+        assert_eq!(
+            capped.current_state().unwrap().content_hash(),
+            full.current_state().unwrap().content_hash()
+        );
+    }
+
+    #[test]
+    fn memory_budgets_evict_old_states_but_never_the_current_one() {
+        let single = seeded_state(21).estimated_memory_bytes();
+        let budget = MemoryBudget { max_bytes: 3 * single + single / 2, strict: false };
+        let mut bounded = Multiverse::new(seeded_state(21)).with_memory_budget(budget);
+        let mut full = Multiverse::new(seeded_state(21));
+        bounded.run(10, &mut |_, _| None).unwrap();
+        full.run(10, &mut |_, _| None).unwrap();
+
+        assert!(bounded.estimated_memory_bytes() <= budget.max_bytes);
+        assert!(bounded.current_timeline().get_state(0).is_none());
+        assert_eq!(bounded.get_tick(), 10);
+        let current = bounded.current_state().unwrap();
+        assert_eq!(current.tick, 10);
+        assert!(current.same_contents(full.current_state().unwrap()));
+    }
+
+    #[test]
+    fn strict_memory_budgets_refuse_new_states() {
+        let single = seeded_state(21).estimated_memory_bytes();
+        let budget = MemoryBudget { max_bytes: single + single / 2, strict: true };
+        let mut multiverse = Multiverse::new(seeded_state(21)).with_memory_budget(budget);
+
+        let err = multiverse.step().unwrap_err();
+        assert!(matches!(err, TimelineError::MemoryBudgetExceeded { .. }), "{:?}", err);
+        assert_eq!(multiverse.get_tick(), 0);
+        assert_eq!(multiverse.current_timeline().len(), 1);
+        assert!(multiverse.current_state().is_some());
+    }
+
+    #[test]
+    fn merges_unite_civilizations_at_the_latest_tick() {
+        let mut state = seeded_state(4);
+        state.civilizations = vec![Civilization::new(0, 1, 1, 7, 500)];
+        let mut multiverse = Multiverse::new(state.clone());
+        let branch = multiverse.fork();
+        let founded = Civilization::new(0, 9, 9, 7, 500);
+        multiverse.current_state_mut().unwrap().civilizations.push(founded);
+
+        // Five quiet ticks on the first timeline, compacted into a single state
+        let timeline = &mut multiverse.timelines[0];
+        for tick in 1..=5 {
+            let mut next = state.clone();
+            next.tick = tick;
+            timeline.push_state(next);
+        }
+        timeline.compact();
+        assert_eq!(timeline.states.len(), 1);
+
+        let id = multiverse.merge_into_new(0, branch, MergePolicy::default()).unwrap();
+        assert_eq!(multiverse.get_tick(), 5);
+        let merged = multiverse.current_state().unwrap();
+        assert_eq!(merged.tick, 5);
+        let mut sites: Vec<_> = merged.civilizations.iter().map(|civ| (civ.x, civ.y)).collect();
+        sites.sort();
+        assert_eq!(sites, [(1, 1), (9, 9)]);
+        let ids: BTreeSet<u32> = merged.civilizations.iter().map(|civ| civ.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(multiverse.current_timeline().id, id);
+    }
+
+    #[test]
+    fn identical_states_compact_into_one() {
+        let state = seeded_state(5);
+        let mut timeline = Timeline::new(0, state.clone());
+        for tick in 1..100 {
+            let mut next = state.clone();
+            next.tick = tick;
+            timeline.push_state(next);
+        }
+        let single = state.estimated_memory_bytes();
+        assert_eq!(timeline.estimated_memory_bytes(), 100 * single);
+
+        timeline.compact();
+        assert_eq!(timeline.estimated_memory_bytes(), single);
+        assert_eq!(timeline.len(), 100);
+        for index in 0..100 {
+            assert_eq!(timeline.get_state(index).unwrap().content_hash(), state.content_hash());
+        }
+        assert!(timeline.get_state(99).unwrap().same_contents(&state));
+        assert!(timeline.get_state(100).is_none());
+    }
+
+    #[test]
+    fn compaction_keeps_states_that_only_differ_by_rng() {
+        let state = seeded_state(5);
+        let mut timeline = Timeline::new(0, state.clone());
+        let mut next = state.clone();
+        next.tick = 1;
+        let _: u32 = next.rng.gen();
+        assert_eq!(next.content_hash(), state.content_hash());
+        timeline.push_state(next);
+
+        timeline.compact();
+        assert_eq!(timeline.states.len(), 2);
+    }
+
+    #[test]
+    fn compacted_timelines_replay_the_same_future() {
+        let mut plain = Multiverse::new(seeded_state(9));
+        let mut compacted = Multiverse::new(seeded_state(9));
+        for _ in 0..30 {
+            plain.step().unwrap();
+            compacted.step().unwrap();
+        }
+        compacted.current_timeline_mut().compact();
+
+        plain.rewind(15);
+        compacted.rewind(15);
+        for _ in 0..15 {
+            plain.step().unwrap();
+            compacted.step().unwrap();
+            let (a, b) = (plain.current_state().unwrap(), compacted.current_state().unwrap());
+            assert!(a.same_contents(b), "diverged at tick {}", plain.current_tick);
+        }
+    }
+
+    #[test]
+    fn seeded_life_stands_on_habitable_surface_voxels() {
+        for seed in 0..4 {
+            let mut state = seeded_state(seed);
+            state.populations.clear();
+            let placed = state.seed_life(40, &mut ChaCha12Rng::seed_from_u64(seed));
+            assert!(placed > 0);
+            assert_eq!(state.populations.len(), placed);
+            for pop in &state.populations {
+                assert_eq!(state.world.surface_height_at(pop.x, pop.y), Some(pop.z));
+                let material = state.world.get(pop.x, pop.y, pop.z).material;
+                assert!(material.is_habitable(), "{:?} at {:?}", material, (pop.x, pop.y));
+            }
+        }
+    }
+
+    #[test]
+    fn grandchild_timelines_trace_both_forks_back_to_the_root() {
+        let mut multiverse = Multiverse::new(seeded_state(4));
+        for _ in 0..5 {
+            multiverse.step().unwrap();
+        }
+        let child = multiverse.fork();
+        for _ in 0..3 {
+            multiverse.step().unwrap();
+        }
+        let grandchild_id = multiverse.fork();
+
+        let grandchild = multiverse.current_timeline();
+        assert_eq!(grandchild.id, grandchild_id);
+        assert_eq!((grandchild.parent_id, grandchild.fork_tick), (Some(child), 8));
+        assert_eq!(grandchild.ancestry(), [(child, 8), (0, 5)]);
+        let timeline = |id: u32| multiverse.timelines.iter().find(|t| t.id == id).unwrap();
+        assert_eq!(timeline(child).ancestry(), [(0, 5)]);
+        assert!(timeline(0).ancestry().is_empty());
+        assert_eq!(timeline(0).parent_id, None);
+    }
+
+    #[test]
+    fn lifeless_static_worlds_reach_equilibrium_quickly() {
+        // Solid rock at the ambient temperature, without life, civilizations or gods
+        let mut world = World3D::new(4, 4, 4);
+        for idx in 0..world.voxels.len() {
+            world.set_material_at(idx, VoxelMaterial::Rock);
+            world.voxels[idx].temperature = 20.0;
+        }
+        let mut state = SimulationStateBuilder::new().world(world).seed(0).build().unwrap();
+        state.gods.clear();
+        let mut multiverse = Multiverse::new(state);
+
+        let mut reached = None;
+        for _ in 0..30 {
+            multiverse.step().unwrap();
+            if multiverse.is_at_equilibrium(5) {
+                reached = Some(multiverse.current_tick);
+                break;
+            }
+        }
+        // Detected as soon as the window is full: the initial state already is at rest
+        assert_eq!(reached, Some(4));
+    }
+
+    #[test]
+    fn seeded_civilizations_stand_on_the_surface_with_their_populations() {
+        // A soil hill at x = 0, a soil plain at x = 1 and bare rock at x = 2
+        let mut world = World3D::new(3, 1, 4);
+        for z in 0..3 {
+            world.set_material(0, 0, z, VoxelMaterial::Soil);
+        }
+        world.set_material(1, 0, 0, VoxelMaterial::Soil);
+        world.set_material(2, 0, 0, VoxelMaterial::Rock);
+        let mut state = SimulationStateBuilder::new().world(world).seed(0).build().unwrap();
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+
+        // Requested heights are ignored: each civilization lands on its column's surface
+        state.seed_civilizations(&[(0, 0, 0, 1500), (1, 0, 3, 700)], &mut rng).unwrap();
+        let placed: Vec<_> =
+            state.civilizations.iter().map(|c| (c.id, c.x, c.y, c.z, c.population)).collect();
+        assert_eq!(placed, [(0, 0, 0, 2, 1500), (1, 1, 0, 0, 700)]);
+
+        // One bad spec and nothing is placed
+        let refused = state.seed_civilizations(&[(1, 0, 0, 100), (2, 0, 0, 100)], &mut rng);
+        assert_eq!(refused, Err(SeedError::Uninhabitable { x: 2, y: 0, z: 0 }));
+        assert_eq!(state.civilizations.len(), 2);
+    }
+
+    #[test]
+    fn injected_catastrophes_strike_on_their_tick() {
+        // Two runs of the canonical scenario without gods; one gets a meteor at tick 5
+        let mut start = CANONICAL.initial_state();
+        start.gods.clear();
+        let (x, y, z) = {
+            let civ = &start.civilizations[0];
+            (civ.x, civ.y, civ.z)
+        };
+        let mut plain = Multiverse::new(start.clone());
+        plain.run(10, &mut |_, _| None).unwrap();
+        let mut struck = Multiverse::new(start);
+        let mut calls = Vec::new();
+        struck
+            .run(10, &mut |tick, _| {
+                calls.push(tick);
+                (tick == 5).then_some(GodAction::SpawnCatastrophe {
+                    kind: CatastropheKind::Meteor,
+                    x,
+                    y,
+                    z,
+                    intensity: 20.0,
+                    radius: 3.0,
+                })
+            })
+            .unwrap();
+        assert_eq!(calls, (1..=10).collect::<Vec<_>>());
+
+        let log = struck.current_state().unwrap().god_action_log.to_vec();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].0, 5);
+        let state_at = |multiverse: &Multiverse, tick: usize| {
+            multiverse.current_timeline().get_state(tick).unwrap().clone()
+        };
+        assert!(state_at(&struck, 4).same_contents(&state_at(&plain, 4)));
+        let (hit, spared) = (state_at(&struck, 5), state_at(&plain, 5));
+        assert!(hit.events.iter().any(|event| matches!(
+            event,
+            SimEvent::GodIntervention { outcome: Ok(effect), .. } if effect.voxels_modified > 0
+        )));
+        // The impact melts the ground it lands on
+        assert_eq!(hit.world.get(x, y, z).material, VoxelMaterial::Lava);
+        assert_ne!(spared.world.get(x, y, z).material, VoxelMaterial::Lava);
+        assert!(hit.world.get(x, y, z).temperature > spared.world.get(x, y, z).temperature);
+    }
+
+    #[test]
+    fn states_count_their_own_ticks() {
+        let mut state = seeded_state(2);
+        assert_eq!(state.tick, 0);
+        for calls in 1..=25 {
+            simulate_tick(&mut state);
+            assert_eq!(state.tick, calls);
+        }
+
+        // The multiverse agrees, and a clone keeps counting from where it was taken
+        let mut multiverse = Multiverse::new(seeded_state(2));
+        for _ in 0..12 {
+            multiverse.step().unwrap();
+        }
+        assert_eq!(multiverse.current_state().unwrap().tick, multiverse.current_tick);
+        multiverse.rewind(5);
+        let mut clone = multiverse.current_state().unwrap().clone();
+        assert_eq!(clone.tick, 7);
+        simulate_tick(&mut clone);
+        assert_eq!(clone.tick, 8);
+    }
+
+    #[test]
+    fn timeline_summaries_list_every_branch() {
+        let mut multiverse = Multiverse::new(seeded_state(6));
+        for _ in 0..10 {
+            multiverse.step().unwrap();
+        }
+        // A first branch off the end of the root, four ticks long
+        let first = multiverse.fork();
+        for _ in 0..4 {
+            multiverse.step().unwrap();
+        }
+        // A second one off the root at tick 6, two ticks long
+        multiverse.switch_timeline(0).unwrap();
+        multiverse.rewind(4);
+        let second = multiverse.fork();
+        for _ in 0..2 {
+            multiverse.step().unwrap();
+        }
+
+        let summary = multiverse.timeline_summary();
+        let rows: Vec<_> = summary
+            .iter()
+            .map(|info| (info.id, info.parent_id, info.fork_tick, info.length))
+            .collect();
+        assert_eq!(rows, [(0, None, 0, 11), (first, Some(0), 10, 15), (second, Some(0), 6, 9)]);
+        let latest = multiverse.current_state().unwrap();
+        let last_row = &summary[2];
+        assert_eq!(last_row.civ_count, latest.civilizations.len());
+        let biomass: u64 = latest.populations.iter().map(|p| p.size as u64).sum();
+        assert_eq!(last_row.biomass, biomass);
+    }
+
+    #[test]
+    fn dangling_species_ids_are_reported() {
+        let mut world = World3D::new(2, 1, 1);
+        world.set_material(0, 0, 0, VoxelMaterial::Soil);
+        world.set_material(1, 0, 0, VoxelMaterial::Soil);
+        let mut species = SpeciesRegistry::new();
+        species.create(&mut ChaCha12Rng::seed_from_u64(0));
+        let populations = vec![Population::new(0, 0, 0, 0, 100), Population::new(5, 1, 0, 0, 40)];
+
+        // The builder refuses the state outright
+        let built = SimulationStateBuilder::new()
+            .world(world.clone())
+            .species(species.clone())
+            .populations(populations.clone())
+            .build();
+        assert!(matches!(built, Err(BuildError::Species(SpeciesError::UnknownId(5)))));
+
+        // `new` builds it anyway, and the first tick explains where the population went
+        let rules = PhysicsRules::default();
+        let mut state =
+            SimulationState::new(world, rules, species, populations, GodState::benevolent());
+        assert_eq!(state.check_species(), Err(SpeciesError::UnknownId(5)));
+        state.gods.clear();
+        simulate_tick(&mut state);
+        let missing: Vec<_> = state
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                SimEvent::MissingSpecies { species_id, population_lost } => {
+                    Some((*species_id, *population_lost))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(missing, [(5, 40)]);
+        assert!(state.populations.iter().all(|pop| pop.species_id == 0));
+    }
+
+    #[test]
+    fn pruning_around_the_current_timeline_keeps_it_valid() {
+        let mut multiverse = Multiverse::new(seeded_state(3));
+        multiverse.step().unwrap();
+        let first = multiverse.fork();
+        multiverse.step().unwrap();
+        multiverse.switch_timeline(0).unwrap();
+        let second = multiverse.fork();
+        multiverse.step().unwrap();
+
+        // Keep only the first fork: its id no longer matches its index
+        multiverse.switch_timeline(first).unwrap();
+        multiverse.timelines.retain(|t| t.id == first);
+        assert_eq!(multiverse.timelines.len(), 1);
+        assert_eq!(multiverse.try_current_timeline().map(|t| t.id), Some(first));
+        assert_eq!(multiverse.current_timeline().id, first);
+        assert!(multiverse.current_state().is_some());
+
+        // The timeline keeps running, and a new fork gets a fresh id
+        multiverse.step().unwrap();
+        assert_eq!(multiverse.current_timeline().len(), 4);
+        let third = multiverse.fork();
+        assert!(third > second);
+        assert_eq!(multiverse.current_timeline().id, third);
+    }
+
+    #[test]
+    fn local_observations_match_hand_computed_values() {
+        // Rock with a pond at the origin under a layer of air, two soil mounds
+        // on top; temperature = x + 10y + 100z, one unit of nutrients per ground voxel
+        let mut world = World3D::new(4, 4, 2);
+        for y in 0..4 {
+            for x in 0..4 {
+                let ground =
+                    if (x, y) == (0, 0) { VoxelMaterial::Water } else { VoxelMaterial::Rock };
+                world.set_material(x, y, 0, ground);
+                world.get_mut(x, y, 0).nutrients = 1.0;
+                for z in 0..2 {
+                    world.get_mut(x, y, z).temperature = (x + 10 * y + 100 * z) as f32;
+                }
+            }
+        }
+        world.set_material(1, 1, 1, VoxelMaterial::Soil);
+        world.set_material(2, 2, 1, VoxelMaterial::Soil);
+        let mut species = SpeciesRegistry::new();
+        species.create(&mut ChaCha12Rng::seed_from_u64(0));
+        let populations = vec![
+            Population::new(0, 1, 1, 1, 100),
+            Population::new(0, 2, 2, 1, 50),
+            Population::new(0, 3, 3, 1, 1000),
+        ];
+        let mut state = SimulationState::new(
+            world,
+            PhysicsRules::default(),
+            species,
+            populations,
+            GodState::benevolent(),
+        );
+        state.civilizations =
+            vec![Civilization::new(0, 2, 0, 1, 500), Civilization::new(1, 3, 1, 1, 500)];
+
+        // x, y in 0..=2 and both layers: 18 voxels, 8 of rock, 7 of air
+        let obs = state.local_observation((1, 1, 0), 1);
+        assert_eq!(
+            obs,
+            LocalObs {
+                mean_temperature: 1.0 + 10.0 + 50.0,
+                nutrient_total: 9.0,
+                population_count: 2,
+                biomass: 150,
+                civ_count: 1,
+                dominant_material: VoxelMaterial::Rock,
+            }
+        );
+
+        // Clamped at the corner: x, y in 0..=1, 8 voxels with as much air as rock,
+        // a tie that goes to air
+        let corner = state.local_observation((0, 0, 0), 1);
+        assert_eq!(corner.mean_temperature, 0.5 + 5.0 + 50.0);
+        assert_eq!(corner.nutrient_total, 4.0);
+        assert_eq!((corner.population_count, corner.biomass, corner.civ_count), (1, 100, 0));
+        assert_eq!(corner.dominant_material, VoxelMaterial::Air);
+    }
+
+    #[test]
+    fn metrics_text_exposes_every_gauge_with_a_number() {
+        let mut multiverse = Multiverse::new(CANONICAL.initial_state());
+        for _ in 0..5 {
+            multiverse.step().unwrap();
+        }
+        multiverse.fork();
+        let text = multiverse.metrics_text();
+
+        let mut values = BTreeMap::new();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (name, value) = line.split_once(' ').expect("a sample is a name and a value");
+            let value: f64 = value.parse().unwrap_or_else(|_| panic!("unparseable {:?}", line));
+            assert!(value.is_finite(), "{}", line);
+            values.insert(name, value);
+        }
+        let names = [
+            "sim_tick",
+            "sim_civilizations",
+            "sim_biomass",
+            "sim_avg_tech",
+            "sim_avg_temperature",
+            "sim_timelines",
+        ];
+        assert_eq!(values.len(), names.len(), "{}", text);
+        for name in names {
+            assert!(values.contains_key(name), "{} missing from\n{}", name, text);
+            assert!(text.contains(&format!("# TYPE {} gauge\n", name)), "{}", text);
+        }
+
+        let state = multiverse.current_state().unwrap();
+        assert_eq!(values["sim_tick"], 5.0);
+        assert_eq!(values["sim_timelines"], 2.0);
+        assert_eq!(values["sim_civilizations"], state.civilizations.len() as f64);
+        let biomass: u64 = state.populations.iter().map(|pop| pop.size as u64).sum();
+        assert_eq!(values["sim_biomass"], biomass as f64);
+    }
+
+    #[test]
+    fn clones_share_rules_until_one_of_them_changes_them() {
+        let original = seeded_state(8);
+        let mut clone = original.clone();
+        assert!(Arc::ptr_eq(&original.physics_rules, &clone.physics_rules));
+        assert!(Arc::ptr_eq(&original.species, &clone.species));
+
+        let (gravity, rate) =
+            (original.physics_rules.gravity_enabled, original.physics_rules.cooling_rate);
+        let rules = Arc::make_mut(&mut clone.physics_rules);
+        rules.gravity_enabled = !gravity;
+        rules.cooling_rate = rate + 0.5;
+
+        // The clone got its own copy; the original keeps its rules
+        assert!(!Arc::ptr_eq(&original.physics_rules, &clone.physics_rules));
+        assert_eq!(original.physics_rules.gravity_enabled, gravity);
+        assert_eq!(original.physics_rules.cooling_rate, rate);
+        assert_eq!(clone.physics_rules.gravity_enabled, !gravity);
+        // Species were left alone, so they are still shared
+        assert!(Arc::ptr_eq(&original.species, &clone.species));
+    }
+
+    #[test]
+    fn species_ranges_span_their_populations() {
+        let mut species = SpeciesRegistry::new();
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        species.create(&mut rng);
+        species.create(&mut rng);
+        // Species 0 in three voxels, one of them twice; species 1 elsewhere
+        let populations = vec![
+            Population::new(0, 1, 2, 0, 100),
+            Population::new(0, 5, 2, 3, 300),
+            Population::new(0, 3, 7, 1, 60),
+            Population::new(0, 3, 7, 1, 40),
+            Population::new(1, 7, 0, 0, 1000),
+        ];
+        let state = SimulationState::new(
+            World3D::new(8, 8, 4),
+            PhysicsRules::default(),
+            species,
+            populations,
+            GodState::benevolent(),
+        );
+
+        // Weighted by size: x = (100 + 5 * 300 + 3 * 100) / 500 and so on
+        assert_eq!(
+            state.species_range(0),
+            Some(RangeInfo {
+                occupied_voxels: 3,
+                min: (1, 2, 0),
+                max: (5, 7, 3),
+                centroid: (3.8, 3.0, 2.0),
+                biomass: 500,
+            })
+        );
+        let lone = state.species_range(1).unwrap();
+        assert_eq!((lone.min, lone.max, lone.centroid), ((7, 0, 0), (7, 0, 0), (7.0, 0.0, 0.0)));
+        assert_eq!(state.species_range(2), None);
+    }
+
+    #[test]
+    fn seeded_life_crowds_the_temperate_band() {
+        // Noise terrain cools toward y = 0 and y = 31: compare the eight rows
+        // at each pole with the sixteen in the middle, the same area
+        let world = World3D::generate_with_noise(32, 32, 12, 5);
+        let mut state = SimulationState::new(
+            world,
+            PhysicsRules::default(),
+            SpeciesRegistry::new(),
+            Vec::new(),
+            GodState::benevolent(),
+        );
+        assert_eq!(state.seed_life(80, &mut ChaCha12Rng::seed_from_u64(5)), 80);
+        let biomass = |rows: &dyn Fn(u32) -> bool| -> u64 {
+            state.populations.iter().filter(|pop| rows(pop.y)).map(|pop| pop.size as u64).sum()
+        };
+        let polar = biomass(&|y| !(8..24).contains(&y));
+        let temperate = biomass(&|y| (8..24).contains(&y));
+        assert!(temperate > 3 * polar, "temperate {} vs polar {}", temperate, polar);
+    }
+
+    #[test]
+    fn replays_rebuild_intermediate_ticks_of_a_full_run() {
+        let retention = Retention { max_states: 5, keyframe_interval: 10 };
+        let mut sparse = Multiverse::new(CANONICAL.initial_state()).with_retention(retention);
+        let mut full = Multiverse::new(CANONICAL.initial_state());
+        for _ in 0..30 {
+            sparse.step().unwrap();
+        }
+        for _ in 0..35 {
+            full.step().unwrap();
+        }
+        let stored = |tick: usize| full.current_timeline().get_state(tick).unwrap().content_hash();
+
+        // Dropped ticks between keyframes, the kept tail, and ticks not simulated yet
+        assert!(sparse.current_timeline().get_state(17).is_none());
+        for tick in [0, 3, 10, 17, 26, 30, 33, 35] {
+            let replayed = sparse.replay_to(tick).unwrap();
+            assert_eq!(replayed.tick, tick);
+            assert_eq!(replayed.content_hash(), stored(tick as usize), "tick {}", tick);
+        }
+        // Replaying does not move the timeline
+        assert_eq!((sparse.get_tick(), sparse.current_timeline().len()), (30, 31));
+    }
+
+    #[test]
+    fn the_builder_assembles_the_same_state_as_the_constructors() {
+        // The demo setup: a generated world, a few species, life seeded on it, one god
+        let demo = || {
+            let mut rng = ChaCha12Rng::seed_from_u64(30);
+            let world = World3D::generate_basic_world_with(
+                16,
+                16,
+                8,
+                &crate::world3d::StrataConfig::default(),
+                &crate::world3d::ThermalProfile::default(),
+                rng.gen(),
+            )
+            .unwrap();
+            let mut species = SpeciesRegistry::new();
+            for _ in 0..3 {
+                species.create(&mut rng);
+            }
+            // Seeding may register species adapted to the spots it picks
+            let mut seeded = SimulationStateBuilder::new()
+                .world(world.clone())
+                .species(species)
+                .seed(0)
+                .build()
+                .unwrap();
+            seeded.seed_life(10, &mut rng);
+            let species = (*seeded.species).clone();
+            (world, species, seeded.populations, GodState::random(&mut rng))
+        };
+
+        let (world, species, populations, god) = demo();
+        let built = SimulationStateBuilder::new()
+            .world(world)
+            .physics_rules(PhysicsRules::default())
+            .species(species)
+            .populations(populations)
+            .god_state(god)
+            .seed(77)
+            .build()
+            .unwrap();
+        let (world, species, populations, god) = demo();
+        let constructed = SimulationState::new(
+            world.clone(),
+            PhysicsRules::default(),
+            species.clone(),
+            populations.clone(),
+            god.clone(),
+        );
+        let rules = PhysicsRules::default();
+        let mut seeded = SimulationState::new_seeded(77, world, rules, species, populations, god);
+
+        // `new` draws its RNG from entropy, but holds the same world and life
+        assert_eq!(constructed.tick, built.tick);
+        assert_eq!(constructed.world.content_hash(), built.world.content_hash());
+        assert_eq!(constructed.species.len(), built.species.len());
+        let life = |state: &SimulationState| -> Vec<_> {
+            state.populations.iter().map(|p| (p.species_id, p.x, p.y, p.z, p.size)).collect()
+        };
+        assert_eq!(life(&constructed), life(&built));
+        assert!(constructed.civilizations.is_empty() && built.civilizations.is_empty());
+        assert_eq!(constructed.gods.len(), built.gods.len());
+        assert_eq!(constructed.gods[0].curiosity, built.gods[0].curiosity);
+
+        // With the same seed, the two are indistinguishable, now and as they run
+        let mut built = built;
+        assert_eq!(seeded.content_hash(), built.content_hash());
+        for _ in 0..10 {
+            simulate_tick(&mut seeded);
+            simulate_tick(&mut built);
+        }
+        assert_eq!(seeded.content_hash(), built.content_hash());
+    }
+
+    #[test]
+    fn the_tick_iterator_advances_one_state_per_item() {
+        let mut multiverse = Multiverse::new(seeded_state(4));
+        assert_eq!(multiverse.ticks().take(10).count(), 10);
+        assert_eq!(multiverse.get_tick(), 10);
+        assert_eq!(multiverse.current_timeline().len(), 11);
+
+        // Items are the states just pushed, in order, and match plain steps
+        let mut stepped = Multiverse::new(seeded_state(4));
+        for _ in 0..10 {
+            stepped.step().unwrap();
+        }
+        let items: Vec<SimulationState> = multiverse.ticks().take(5).collect();
+        assert_eq!(items.iter().map(|state| state.tick).collect::<Vec<_>>(), [11, 12, 13, 14, 15]);
+        for item in &items {
+            stepped.step().unwrap();
+            assert_eq!(item.content_hash(), stepped.current_state().unwrap().content_hash());
+        }
+
+        // Lazy: `find` stops stepping as soon as it has its answer
+        let found = multiverse.ticks().find(|state| state.tick == 18).unwrap();
+        assert_eq!((found.tick, multiverse.get_tick()), (18, 18));
+
+        // A strict memory budget ends the iteration instead of failing
+        let single = seeded_state(4).estimated_memory_bytes();
+        let budget = MemoryBudget { max_bytes: 2 * single + single / 2, strict: true };
+        let mut bounded = Multiverse::new(seeded_state(4)).with_memory_budget(budget);
+        assert!(bounded.ticks().take(10).count() < 10);
+    }
+
+    #[test]
+    fn opposite_edges_are_neighbors_in_a_wrapping_world() {
+        // One population and one civilization at each x edge of a 16-wide world
+        let mut state = seeded_state(0);
+        state.populations =
+            vec![Population::new(0, 0, 8, 4, 100), Population::new(0, 15, 8, 4, 100)];
+        state.civilizations =
+            vec![Civilization::new(0, 0, 8, 4, 100), Civilization::new(1, 15, 8, 4, 100)];
+        let near_west = |state: &SimulationState| {
+            let populations: Vec<u32> =
+                state.populations_within((0, 8, 4), 1.5).map(|p| p.x).collect();
+            let civilizations: Vec<u32> =
+                state.civilizations_within((0, 8, 4), 1.5).map(|c| c.id).collect();
+            (populations, civilizations)
+        };
+
+        state.world.wrapping = false;
+        assert_eq!(near_west(&state), (vec![0], vec![0]));
+        // Around the edge, x = 15 is one voxel from x = 0
+        state.world.wrapping = true;
+        assert_eq!(near_west(&state), (vec![0, 15], vec![0, 1]));
+    }
+
+    #[test]
+    fn capped_speciation_recycles_extinct_ids() {
+        let mut state = seeded_state(3);
+        state.biology_rules.max_species = 3;
+        state.biology_rules.speciation_chance = 1.0;
+        state.biology_rules.speciation_min_size = 10;
+        let mut newest = state.populations[0].species_id;
+        let mut born = Vec::new();
+        for _ in 0..10 {
+            // Only the newest species is left alive, as a population big enough to split
+            state.populations = vec![Population::new(newest, 8, 8, 4, 10_000)];
+            speciate(&mut state);
+            assert!(state.species.len() <= 3, "{} species", state.species.len());
+            assert_eq!(state.populations.len(), 2, "the founder buds a new species");
+            newest = state.populations.last().unwrap().species_id;
+            born.push(newest);
+        }
+        // Ten species came and went under the same three ids
+        assert!(born.iter().all(|&id| id < 3), "{:?}", born);
+        assert!(born.windows(2).all(|w| w[0] != w[1]), "{:?}", born);
+    }
+
+    #[test]
+    fn cold_settlers_bud_species_that_prefer_the_cold() {
+        use crate::biology::Diet;
+
+        // A soil voxel under air, held at 12 degrees, settled by a species at
+        // home at 20 degrees
+        let mut world = World3D::new(1, 1, 2);
+        world.set_material(0, 0, 0, VoxelMaterial::Soil);
+        let mut species = SpeciesRegistry::new();
+        let settlers = Species {
+            id: 0,
+            metabolism: 0.5,
+            reproduction_rate: 0.1,
+            mobility: 0.0,
+            preferred_temperature: 20.0,
+            thermal_tolerance: 5.0,
+            salinity_tolerance: (0.0, 1.0),
+            diet: Diet::Producer,
+        };
+        species.insert(settlers).unwrap();
+        let rules = BiologyRules {
+            speciation_chance: 0.02,
+            speciation_min_size: 500,
+            ..Default::default()
+        };
+        let mut state = SimulationStateBuilder::new()
+            .world(world)
+            .biology_rules(rules)
+            .species(species)
+            .populations(vec![Population::new(0, 0, 0, 0, 1000)])
+            .seed(8)
+            .build()
+            .unwrap();
+
+        for _ in 0..400 {
+            for voxel in &mut state.world.voxels {
+                voxel.temperature = 12.0;
+            }
+            state.world.get_mut(0, 0, 0).nutrients = 10_000.0;
+            simulate_tick(&mut state);
+        }
+        let derived: Vec<f32> = state
+            .species
+            .iter()
+            .filter(|s| s.id != 0)
+            .map(|s| s.preferred_temperature)
+            .collect();
+        assert!(derived.iter().any(|&t| t < 17.0), "{:?}", derived);
+    }
 }
@@ -1,6 +1,14 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum VoxelMaterial {
     Air,
     Rock,
@@ -11,12 +19,108 @@ pub enum VoxelMaterial {
     Organic(u8),
 }
 
-#[derive(Debug, Clone)]
+impl VoxelMaterial {
+    // Materials that primitive life can live in
+    pub fn is_habitable(&self) -> bool {
+        matches!(
+            self,
+            VoxelMaterial::Soil | VoxelMaterial::Water | VoxelMaterial::Organic(_)
+        )
+    }
+
+    // Relative thermal conductivity (1.0 = best conductor)
+    pub fn conductivity(&self) -> f32 {
+        match self {
+            VoxelMaterial::Water => 1.0,
+            VoxelMaterial::Ice => 0.8,
+            VoxelMaterial::Air => 0.6,
+            VoxelMaterial::Lava => 0.5,
+            VoxelMaterial::Soil => 0.4,
+            VoxelMaterial::Organic(_) => 0.4,
+            VoxelMaterial::Rock => 0.3,
+        }
+    }
+
+    // Heat needed to warm a voxel by one degree, relative to air (1.0, the
+    // smallest): diffusion changes a voxel's temperature by its exchange
+    // divided by this, so water holds its temperature longest
+    pub fn heat_capacity(&self) -> f32 {
+        match self {
+            VoxelMaterial::Water => 4.0,
+            VoxelMaterial::Organic(_) => 3.0,
+            VoxelMaterial::Ice => 2.0,
+            VoxelMaterial::Soil => 2.0,
+            VoxelMaterial::Rock => 2.0,
+            VoxelMaterial::Lava => 2.0,
+            VoxelMaterial::Air => 1.0,
+        }
+    }
+
+    // One material per histogram slot, in kind_index order
+    pub const KINDS: [VoxelMaterial; MATERIAL_KINDS] = [
+        VoxelMaterial::Air,
+        VoxelMaterial::Rock,
+        VoxelMaterial::Soil,
+        VoxelMaterial::Water,
+        VoxelMaterial::Lava,
+        VoxelMaterial::Ice,
+        VoxelMaterial::Organic(0),
+    ];
+
+    // Slot of this material in World3D's material histogram
+    pub fn kind_index(&self) -> usize {
+        match self {
+            VoxelMaterial::Air => 0,
+            VoxelMaterial::Rock => 1,
+            VoxelMaterial::Soil => 2,
+            VoxelMaterial::Water => 3,
+            VoxelMaterial::Lava => 4,
+            VoxelMaterial::Ice => 5,
+            VoxelMaterial::Organic(_) => 6,
+        }
+    }
+
+    // Density a voxel takes on when it turns into this material
+    pub fn default_density(&self) -> f32 {
+        match self {
+            VoxelMaterial::Air => 0.0,
+            VoxelMaterial::Rock => 2.5,
+            VoxelMaterial::Soil => 1.2,
+            VoxelMaterial::Water => 1.0,
+            VoxelMaterial::Lava => 2.6,
+            VoxelMaterial::Ice => 0.9,
+            VoxelMaterial::Organic(_) => 0.8,
+        }
+    }
+
+    // Nutrient level a voxel of this material recovers toward once depleted
+    pub fn base_nutrients(&self) -> f32 {
+        match self {
+            VoxelMaterial::Soil | VoxelMaterial::Organic(_) => 10.0,
+            VoxelMaterial::Water => 5.0,
+            _ => 0.0,
+        }
+    }
+}
+
+// Number of distinct material kinds (all Organic levels count as one)
+pub const MATERIAL_KINDS: usize = 7;
+
+// Largest world World3D::new accepts (a few GiB of voxels); it also keeps
+// every voxel index within u32, as `index` and `coords` assume
+pub const MAX_VOXELS: u64 = 1 << 27;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Voxel {
     pub material: VoxelMaterial,
     pub temperature: f32,
     pub density: f32,
     pub nutrients: f32, // Ajout des nutriments
+    pub salinity: f32,  // 0.0 = fresh water, 1.0 = open ocean brine
+    pub fertility: f32, // Multiplier on the carrying capacity (1.0 = average soil)
+    // Ore richness of the voxel, mined away by civilizations (0 = barren)
+    #[serde(default)]
+    pub mineral: f32,
 }
 
 impl Voxel {
@@ -26,9 +130,18 @@ impl Voxel {
             temperature,
             density,
             nutrients,
+            salinity: 0.0,
+            fertility: 1.0,
+            mineral: 0.0,
         }
     }
 
+    // Change material in place (e.g. a phase transition), keeping temperature
+    pub fn set_material(&mut self, material: VoxelMaterial) {
+        self.material = material;
+        self.density = material.default_density();
+    }
+
     pub fn air() -> Self {
         Self::new(VoxelMaterial::Air, 20.0, 0.0, 0.0)
     }
@@ -46,40 +159,559 @@ impl Voxel {
     }
 }
 
+// A scalar voxel property that can be exported with `World3D::dump_field`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Temperature,
+    Nutrients,
+    Density,
+    Salinity,
+    Fertility,
+    Mineral,
+}
+
+impl Field {
+    pub fn value(&self, voxel: &Voxel) -> f32 {
+        match self {
+            Field::Temperature => voxel.temperature,
+            Field::Nutrients => voxel.nutrients,
+            Field::Density => voxel.density,
+            Field::Salinity => voxel.salinity,
+            Field::Fertility => voxel.fertility,
+            Field::Mineral => voxel.mineral,
+        }
+    }
+}
+
+const ORTHOGONAL_OFFSETS: [(i32, i32, i32); 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+const FULL_OFFSETS: [(i32, i32, i32); 26] = [
+    (-1, -1, -1),
+    (0, -1, -1),
+    (1, -1, -1),
+    (-1, 0, -1),
+    (0, 0, -1),
+    (1, 0, -1),
+    (-1, 1, -1),
+    (0, 1, -1),
+    (1, 1, -1),
+    (-1, -1, 0),
+    (0, -1, 0),
+    (1, -1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (-1, 1, 0),
+    (0, 1, 0),
+    (1, 1, 0),
+    (-1, -1, 1),
+    (0, -1, 1),
+    (1, -1, 1),
+    (-1, 0, 1),
+    (0, 0, 1),
+    (1, 0, 1),
+    (-1, 1, 1),
+    (0, 1, 1),
+    (1, 1, 1),
+];
+
+// Which neighboring voxels count as adjacent: the 6 face neighbors,
+// or all 26 voxels of the surrounding cube (faces, edges and corners)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NeighborSet {
+    Orthogonal,
+    Full,
+}
+
+// Physics calls the neighbor set used for heat exchange a diffusion stencil
+pub type DiffusionStencil = NeighborSet;
+
+impl NeighborSet {
+    pub fn offsets(&self) -> &'static [(i32, i32, i32)] {
+        match self {
+            NeighborSet::Orthogonal => &ORTHOGONAL_OFFSETS,
+            NeighborSet::Full => &FULL_OFFSETS,
+        }
+    }
+}
+
+// Vertical layering used by world generation; air and water fill the remainder
+#[derive(Debug, Clone, Copy)]
+pub struct StrataConfig {
+    pub rock_fraction: f32,
+    pub soil_fraction: f32,
+}
+
+impl Default for StrataConfig {
+    fn default() -> Self {
+        Self {
+            rock_fraction: 0.3,
+            soil_fraction: 0.4,
+        }
+    }
+}
+
+impl StrataConfig {
+    pub fn validate(&self) -> Result<(), WorldGenError> {
+        let valid_fraction = |f: f32| (0.0..=1.0).contains(&f);
+        if !valid_fraction(self.rock_fraction)
+            || !valid_fraction(self.soil_fraction)
+            || self.rock_fraction + self.soil_fraction > 1.0
+        {
+            return Err(WorldGenError::InvalidStrata(*self));
+        }
+        Ok(())
+    }
+}
+
+// Starting temperature of each generated material, drawn uniformly from its
+// range (an empty range gives exactly its start)
+#[derive(Debug, Clone)]
+pub struct ThermalProfile {
+    pub rock: Range<f32>,
+    pub soil: Range<f32>,
+    pub water: Range<f32>,
+    pub air: Range<f32>,
+}
+
+impl Default for ThermalProfile {
+    fn default() -> Self {
+        Self {
+            rock: 15.0..15.0,
+            soil: 15.0..25.0,
+            water: 10.0..10.0,
+            air: 18.0..26.0,
+        }
+    }
+}
+
+impl ThermalProfile {
+    // A young planet still glowing from its formation: rock starts above its
+    // melting point and everything cools over the run
+    pub fn molten() -> Self {
+        Self {
+            rock: 1400.0..1800.0,
+            soil: 600.0..900.0,
+            water: 100.0..100.0,
+            air: 300.0..500.0,
+        }
+    }
+
+    fn sample(range: &Range<f32>, rng: &mut impl Rng) -> f32 {
+        if range.end > range.start {
+            range.start + rng.gen::<f32>() * (range.end - range.start)
+        } else {
+            range.start
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WorldGenError {
+    InvalidStrata(StrataConfig),
+}
+
+impl fmt::Display for WorldGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldGenError::InvalidStrata(config) => write!(
+                f,
+                "invalid strata: rock {} + soil {} must be fractions summing to at most 1.0",
+                config.rock_fraction, config.soil_fraction
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorldGenError {}
+
+// FNV-1a: small, fast and stable across runs and platforms (unlike DefaultHasher)
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    // Temperatures and other floats are hashed at 0.01 resolution
+    pub(crate) fn write_quantized(&mut self, value: f32) {
+        self.write(&((value * 100.0).round() as i64).to_le_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Temperature statistics over a set of voxels (all zero when the set is empty)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TempStats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub variance: f32,
+}
+
+impl TempStats {
+    // Single pass (Welford's algorithm), accumulated in f64 for large worlds
+    pub fn from_temperatures(temperatures: impl IntoIterator<Item = f32>) -> Self {
+        let mut count = 0u64;
+        let mut mean = 0.0f64;
+        let mut m2 = 0.0f64;
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+
+        for t in temperatures {
+            count += 1;
+            min = min.min(t);
+            max = max.max(t);
+            let delta = t as f64 - mean;
+            mean += delta / count as f64;
+            m2 += delta * (t as f64 - mean);
+        }
+
+        if count == 0 {
+            return Self::default();
+        }
+        Self {
+            min,
+            max,
+            mean: mean as f32,
+            variance: (m2 / count as f64) as f32,
+        }
+    }
+}
+
+// Ecological profile of the world's surface (the topmost non-air voxel of each column)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldStats {
+    // Fractions of all columns, in 0..=1; columns with no surface count as none
+    pub land_fraction: f32,
+    pub water_fraction: f32,
+    pub ice_fraction: f32,
+    // Mean surface temperature per latitude (y) band, south to north
+    pub band_temperatures: Vec<f32>,
+    pub habitable_surface: u32,
+}
+
+// Z level where a layer covering `fraction` of the depth ends
+fn layer_top(depth: u32, fraction: f32) -> u32 {
+    ((depth as f32 * fraction + 1e-3) as u32).min(depth)
+}
+
+// Noise-based generation (see `World3D::generate_with_noise`). Ground height
+// spans LOWEST_GROUND..LOWEST_GROUND + GROUND_RANGE of the depth; columns
+// above MOUNTAIN_ELEVATION are bare rock.
+const SEA_LEVEL: f32 = 0.45;
+const LOWEST_GROUND: f32 = 0.15;
+const GROUND_RANGE: f32 = 0.7;
+const MOUNTAIN_ELEVATION: f32 = 0.7;
+const SOIL_THICKNESS: u32 = 3;
+// Surface climate: warmest at the equator, POLAR_COOLING degrees colder at the
+// poles, and LAPSE_RATE degrees colder per voxel above sea level
+const EQUATOR_TEMPERATURE: f32 = 28.0;
+const POLAR_COOLING: f32 = 35.0;
+const LAPSE_RATE: f32 = 1.0;
+// Ore veins run through the rock of the columns where a second noise field
+// exceeds ORE_THRESHOLD, richer the further above it
+const ORE_THRESHOLD: f32 = 0.65;
+const ORE_SEED_OFFSET: u64 = 0x0de5;
+
+// Smooth pseudo-random field in 0..=1: `octaves` layers of value noise, each
+// with half the period and half the weight of the previous one
+fn fractal_noise(seed: u64, x: f32, y: f32, period: f32, octaves: u32) -> f32 {
+    let (mut total, mut weight, mut amplitude, mut period) = (0.0, 0.0, 1.0, period);
+    for octave in 0..octaves {
+        let layer_seed = seed.wrapping_add(octave as u64);
+        total += amplitude * value_noise(layer_seed, x / period, y / period);
+        weight += amplitude;
+        amplitude *= 0.5;
+        period = (period * 0.5).max(1.0);
+    }
+    total / weight
+}
+
+// Random values on the integer lattice, smoothly interpolated in between
+fn value_noise(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let (ix, iy) = (x0 as i64, y0 as i64);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let south = lerp(lattice_value(seed, ix, iy), lattice_value(seed, ix + 1, iy), tx);
+    let north = lerp(lattice_value(seed, ix, iy + 1), lattice_value(seed, ix + 1, iy + 1), tx);
+    lerp(south, north, ty)
+}
+
+fn lattice_value(seed: u64, x: i64, y: i64) -> f32 {
+    let mut hasher = StableHasher::new();
+    hasher.write(&seed.to_le_bytes());
+    hasher.write(&x.to_le_bytes());
+    hasher.write(&y.to_le_bytes());
+    // Fold the well-mixed high bits into the 24 bits kept
+    let hash = hasher.finish();
+    ((hash ^ (hash >> 40)) & 0xff_ffff) as f32 / 0xff_ffff as f32
+}
+
+// Physics needs at least 1x1x1 voxels, and gravity at least two layers of depth
 #[derive(Clone)]
 pub struct World3D {
     pub width: u32,
     pub height: u32,
     pub depth: u32,
     pub voxels: Vec<Voxel>,
+    // Toroidal world: opposite edges are adjacent for distance queries
+    pub wrapping: bool,
+    // Layers below this one (z < sea_level) belong to the sea. Raising it
+    // floods the new layers on the next `flood_to_sea_level`.
+    pub sea_level: u32,
+    // Layers already flooded, so a rise only converts the layers it adds
+    flooded_level: u32,
+    // Voxel count per material kind. Kept up to date by set_material; get_mut
+    // marks it stale until the next refresh_material_counts.
+    material_counts: [u32; MATERIAL_KINDS],
+    material_counts_stale: bool,
+    // Named voxels for scenario scripting (a capital, a sacred mountain...);
+    // tags stay on their coordinates whatever happens to the voxel
+    tags: HashMap<(u32, u32, u32), String>,
 }
 
 impl World3D {
+    // Panics if the world would hold more than MAX_VOXELS voxels
     pub fn new(width: u32, height: u32, depth: u32) -> Self {
-        let size = (width * height * depth) as usize;
+        let size = width as u64 * height as u64 * depth as u64;
+        assert!(
+            size <= MAX_VOXELS,
+            "a {}x{}x{} world exceeds {} voxels",
+            width,
+            height,
+            depth,
+            MAX_VOXELS
+        );
+        let size = size as usize;
         let voxels = vec![Voxel::air(); size];
+        let mut material_counts = [0; MATERIAL_KINDS];
+        material_counts[VoxelMaterial::Air.kind_index()] = size as u32;
         Self {
             width,
             height,
             depth,
             voxels,
+            wrapping: false,
+            sea_level: 0,
+            flooded_level: 0,
+            material_counts,
+            material_counts_stale: false,
+            tags: HashMap::new(),
         }
     }
 
+    // Name the voxel at (x, y, z), replacing any previous tag there. Returns
+    // false (and tags nothing) if the voxel is outside the world.
+    pub fn tag(&mut self, x: u32, y: u32, z: u32, name: &str) -> bool {
+        if x >= self.width || y >= self.height || z >= self.depth {
+            return false;
+        }
+        self.tags.insert((x, y, z), name.to_string());
+        true
+    }
+
+    // Remove the tag at (x, y, z), returning it
+    pub fn untag(&mut self, x: u32, y: u32, z: u32) -> Option<String> {
+        self.tags.remove(&(x, y, z))
+    }
+
+    pub fn tag_at(&self, x: u32, y: u32, z: u32) -> Option<&str> {
+        self.tags.get(&(x, y, z)).map(String::as_str)
+    }
+
+    // Every voxel tagged `name`, in (x, y, z) order
+    pub fn find_tagged(&self, name: &str) -> Vec<(u32, u32, u32)> {
+        let mut found: Vec<(u32, u32, u32)> =
+            self.tags.iter().filter(|(_, tag)| *tag == name).map(|(pos, _)| *pos).collect();
+        found.sort_unstable();
+        found
+    }
+
+    pub fn dimensions(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.depth)
+    }
+
+    // Per-axis gap between two voxels; on a wrapping world, the shorter way around
+    pub fn separation(&self, a: (u32, u32, u32), b: (u32, u32, u32)) -> (u32, u32, u32) {
+        let axis = |p: u32, q: u32, size: u32| {
+            let d = p.abs_diff(q);
+            if self.wrapping {
+                d.min(size.saturating_sub(d))
+            } else {
+                d
+            }
+        };
+        (axis(a.0, b.0, self.width), axis(a.1, b.1, self.height), axis(a.2, b.2, self.depth))
+    }
+
+    // Straight-line distance between two voxels, honoring wrapping
+    pub fn distance(&self, a: (u32, u32, u32), b: (u32, u32, u32)) -> f32 {
+        let (dx, dy, dz) = self.separation(a, b);
+        let (dx, dy, dz) = (dx as f32, dy as f32, dz as f32);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+
+    // Every voxel at most `reach` steps from `center` along each axis, listed
+    // once each; the cube continues across the edges of a wrapping world
+    pub fn cube_around(&self, center: (u32, u32, u32), reach: u32) -> Vec<(u32, u32, u32)> {
+        let span = |c: u32, size: u32| -> Vec<u32> {
+            if size == 0 {
+                Vec::new()
+            } else if !self.wrapping {
+                (c.saturating_sub(reach)..=c.saturating_add(reach).min(size - 1)).collect()
+            } else if reach as u64 * 2 + 1 >= size as u64 {
+                (0..size).collect()
+            } else {
+                let size = size as i64;
+                (-(reach as i64)..=reach as i64)
+                    .map(|d| (c as i64 + d).rem_euclid(size) as u32)
+                    .collect()
+            }
+        };
+        let (xs, ys, zs) =
+            (span(center.0, self.width), span(center.1, self.height), span(center.2, self.depth));
+        let mut cube = Vec::with_capacity(xs.len() * ys.len() * zs.len());
+        for &z in &zs {
+            for &y in &ys {
+                for &x in &xs {
+                    cube.push((x, y, z));
+                }
+            }
+        }
+        cube
+    }
+
     #[inline]
     pub fn index(&self, x: u32, y: u32, z: u32) -> usize {
         (z * self.width * self.height + y * self.width + x) as usize
     }
 
+    // Inverse of index()
+    #[inline]
+    pub fn coords(&self, index: usize) -> (u32, u32, u32) {
+        let index = index as u32;
+        let layer = self.width * self.height;
+        (index % self.width, (index % layer) / self.width, index / layer)
+    }
+
     pub fn get(&self, x: u32, y: u32, z: u32) -> &Voxel {
         &self.voxels[self.index(x, y, z)]
     }
 
+    // Callers may change the material through the returned reference, so the
+    // material histogram is considered stale afterwards
     pub fn get_mut(&mut self, x: u32, y: u32, z: u32) -> &mut Voxel {
         let idx = self.index(x, y, z);
+        self.material_counts_stale = true;
         &mut self.voxels[idx]
     }
 
+    pub fn set_material(&mut self, x: u32, y: u32, z: u32, material: VoxelMaterial) {
+        let idx = self.index(x, y, z);
+        self.set_material_at(idx, material);
+    }
+
+    // While the histogram is stale it is left alone: the next refresh
+    // recounts everything anyway
+    pub(crate) fn set_material_at(&mut self, idx: usize, material: VoxelMaterial) {
+        let voxel = &mut self.voxels[idx];
+        if !self.material_counts_stale {
+            self.material_counts[voxel.material.kind_index()] -= 1;
+            self.material_counts[material.kind_index()] += 1;
+        }
+        voxel.set_material(material);
+    }
+
+    // Turn the air and exposed soil (under air or water) of the layers added
+    // since the last call, up to sea_level, into water; returns the voxels
+    // that were flooded. Layers flooded before are not scanned again.
+    pub fn flood_to_sea_level(&mut self) -> Vec<(u32, u32, u32)> {
+        let sea_level = self.sea_level.min(self.depth);
+        if sea_level <= self.flooded_level {
+            // The sea went down (or stayed): water left behind stays put
+            self.flooded_level = sea_level;
+            return Vec::new();
+        }
+
+        self.ensure_material_counts();
+        let mut flooded = Vec::new();
+        for z in self.flooded_level..sea_level {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let idx = self.index(x, y, z);
+                    let exposed = z + 1 >= self.depth
+                        || matches!(
+                            self.get(x, y, z + 1).material,
+                            VoxelMaterial::Air | VoxelMaterial::Water
+                        );
+                    let floods = match self.voxels[idx].material {
+                        VoxelMaterial::Air => true,
+                        VoxelMaterial::Soil => exposed,
+                        _ => false,
+                    };
+                    if floods {
+                        self.set_material_at(idx, VoxelMaterial::Water);
+                        flooded.push((x, y, z));
+                    }
+                }
+            }
+        }
+        self.flooded_level = sea_level;
+        flooded
+    }
+
+    // Number of voxels of the given material kind (any Organic level counts as Organic)
+    pub fn material_count(&self, material: VoxelMaterial) -> u32 {
+        if self.material_counts_stale {
+            let kind = material.kind_index();
+            return self
+                .voxels
+                .iter()
+                .filter(|v| v.material.kind_index() == kind)
+                .count() as u32;
+        }
+        self.material_counts[material.kind_index()]
+    }
+
+    // Rebuild the histogram only if get_mut may have invalidated it
+    pub fn ensure_material_counts(&mut self) {
+        if self.material_counts_stale {
+            self.refresh_material_counts();
+        }
+    }
+
+    // Rebuild the material histogram from scratch (after direct voxel edits)
+    pub fn refresh_material_counts(&mut self) {
+        self.material_counts = [0; MATERIAL_KINDS];
+        for voxel in &self.voxels {
+            self.material_counts[voxel.material.kind_index()] += 1;
+        }
+        self.material_counts_stale = false;
+    }
+
     pub fn is_valid(&self, x: i32, y: i32, z: i32) -> bool {
         x >= 0
             && y >= 0
@@ -89,40 +721,873 @@ impl World3D {
             && z < self.depth as i32
     }
 
+    // Hash of dimensions, sea level, materials, temperatures (quantized to
+    // 0.01°) and mineral deposits, for cheap equality checks between worlds
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(self.width);
+        hasher.write_u32(self.height);
+        hasher.write_u32(self.depth);
+        hasher.write_u32(self.sea_level);
+        hasher.write_u32(self.flooded_level);
+        for voxel in &self.voxels {
+            let tag = match voxel.material {
+                VoxelMaterial::Air => [0, 0],
+                VoxelMaterial::Rock => [1, 0],
+                VoxelMaterial::Soil => [2, 0],
+                VoxelMaterial::Water => [3, 0],
+                VoxelMaterial::Lava => [4, 0],
+                VoxelMaterial::Ice => [5, 0],
+                VoxelMaterial::Organic(level) => [6, level],
+            };
+            hasher.write(&tag);
+            hasher.write_quantized(voxel.temperature);
+            hasher.write_quantized(voxel.mineral);
+        }
+    }
+
+    // Raw dump of one field: width, height and depth as little-endian u32,
+    // then one little-endian f32 per voxel in index order (x fastest, z
+    // slowest). In numpy:
+    //   np.fromfile(path, dtype="<f4", offset=12).reshape(depth, height, width)
+    pub fn dump_field(&self, field: Field, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        for size in [self.width, self.height, self.depth] {
+            out.write_all(&size.to_le_bytes())?;
+        }
+        for voxel in &self.voxels {
+            out.write_all(&field.value(voxel).to_le_bytes())?;
+        }
+        out.flush()
+    }
+
+    pub fn temperature_stats(&self) -> TempStats {
+        TempStats::from_temperatures(self.voxels.iter().map(|v| v.temperature))
+    }
+
+    // Stats over the box [min, max) clamped to the world, e.g. to size up a target area
+    pub fn temperature_stats_within(
+        &self,
+        min: (u32, u32, u32),
+        max: (u32, u32, u32),
+    ) -> TempStats {
+        let (x1, y1, z1) = (max.0.min(self.width), max.1.min(self.height), max.2.min(self.depth));
+        let temperatures = (min.2..z1).flat_map(move |z| {
+            (min.1..y1).flat_map(move |y| {
+                (min.0..x1).map(move |x| self.get(x, y, z).temperature)
+            })
+        });
+        TempStats::from_temperatures(temperatures)
+    }
+
+    // Surface statistics, with surface temperatures averaged over `bands` latitude bands
+    pub fn world_stats(&self, bands: usize) -> WorldStats {
+        let bands = bands.clamp(1, self.height.max(1) as usize);
+        let mut band_sums = vec![(0.0f32, 0u32); bands];
+        let (mut land, mut water, mut ice, mut habitable) = (0u32, 0u32, 0u32, 0u32);
+
+        for y in 0..self.height {
+            let band = y as usize * bands / self.height as usize;
+            for x in 0..self.width {
+                let z = match self.surface_height_at(x, y) {
+                    Some(z) => z,
+                    None => continue,
+                };
+                let voxel = self.get(x, y, z);
+                match voxel.material {
+                    VoxelMaterial::Water => water += 1,
+                    VoxelMaterial::Ice => ice += 1,
+                    _ => land += 1,
+                }
+                if voxel.material.is_habitable() {
+                    habitable += 1;
+                }
+                band_sums[band].0 += voxel.temperature;
+                band_sums[band].1 += 1;
+            }
+        }
+
+        let columns = (self.width * self.height).max(1) as f32;
+        WorldStats {
+            land_fraction: land as f32 / columns,
+            water_fraction: water as f32 / columns,
+            ice_fraction: ice as f32 / columns,
+            band_temperatures: band_sums
+                .iter()
+                .map(|&(sum, count)| if count > 0 { sum / count as f32 } else { 0.0 })
+                .collect(),
+            habitable_surface: habitable,
+        }
+    }
+
+    // Copy of the box [min, max), clamped to the world; voxels keep all their fields
+    pub fn extract_region(&self, min: (u32, u32, u32), max: (u32, u32, u32)) -> World3D {
+        let x1 = max.0.min(self.width);
+        let y1 = max.1.min(self.height);
+        let z1 = max.2.min(self.depth);
+        let (x0, y0, z0) = (min.0.min(x1), min.1.min(y1), min.2.min(z1));
+
+        let mut region = World3D::new(x1 - x0, y1 - y0, z1 - z0);
+        for z in z0..z1 {
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let idx = region.index(x - x0, y - y0, z - z0);
+                    region.voxels[idx] = self.get(x, y, z).clone();
+                }
+            }
+        }
+        for (&(x, y, z), name) in &self.tags {
+            if (x0..x1).contains(&x) && (y0..y1).contains(&y) && (z0..z1).contains(&z) {
+                region.tags.insert((x - x0, y - y0, z - z0), name.clone());
+            }
+        }
+        region.refresh_material_counts();
+        region
+    }
+
+    // Stamp `other` into this world with its origin at `offset`; whatever falls
+    // outside this world is clipped
+    pub fn paste(&mut self, offset: (i32, i32, i32), other: &World3D) {
+        for z in 0..other.depth {
+            for y in 0..other.height {
+                for x in 0..other.width {
+                    let tx = offset.0 + x as i32;
+                    let ty = offset.1 + y as i32;
+                    let tz = offset.2 + z as i32;
+                    if !self.is_valid(tx, ty, tz) {
+                        continue;
+                    }
+                    let idx = self.index(tx as u32, ty as u32, tz as u32);
+                    self.voxels[idx] = other.get(x, y, z).clone();
+                }
+            }
+        }
+        for (&(x, y, z), name) in &other.tags {
+            let (tx, ty, tz) = (offset.0 + x as i32, offset.1 + y as i32, offset.2 + z as i32);
+            if self.is_valid(tx, ty, tz) {
+                self.tags.insert((tx as u32, ty as u32, tz as u32), name.clone());
+            }
+        }
+        self.refresh_material_counts();
+    }
+
+    // Every voxel that is not Air, with its coordinates
+    pub fn iter_non_air(&self) -> impl Iterator<Item = (u32, u32, u32, &Voxel)> + '_ {
+        self.voxels
+            .iter()
+            .enumerate()
+            .filter(|(_, voxel)| voxel.material != VoxelMaterial::Air)
+            .map(move |(i, voxel)| {
+                let (x, y, z) = self.coords(i);
+                (x, y, z, voxel)
+            })
+    }
+
+    // Height of the topmost non-air voxel in the (x, y) column, if any
+    pub fn surface_height_at(&self, x: u32, y: u32) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        (0..self.depth)
+            .rev()
+            .find(|&z| self.get(x, y, z).material != VoxelMaterial::Air)
+    }
+
+    // Mean fertility of the columns whose topmost non-air voxel is soil (0 if none)
+    pub fn mean_surface_fertility(&self) -> f32 {
+        let (mut total, mut columns) = (0.0, 0);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(z) = self.surface_height_at(x, y) {
+                    let voxel = self.get(x, y, z);
+                    if voxel.material == VoxelMaterial::Soil {
+                        total += voxel.fertility;
+                        columns += 1;
+                    }
+                }
+            }
+        }
+        if columns > 0 {
+            total / columns as f32
+        } else {
+            0.0
+        }
+    }
+
+    // True if nothing but Air or Water lies above (x, y, z): sunlight reaches it
+    pub fn has_sky_above(&self, x: u32, y: u32, z: u32) -> bool {
+        ((z + 1)..self.depth).all(|above| {
+            matches!(
+                self.get(x, y, above).material,
+                VoxelMaterial::Air | VoxelMaterial::Water
+            )
+        })
+    }
+
+    // In-bounds neighbors of (x, y, z) for the given neighbor set
+    pub fn neighbors(
+        &self,
+        x: u32,
+        y: u32,
+        z: u32,
+        set: NeighborSet,
+    ) -> impl Iterator<Item = (u32, u32, u32)> + '_ {
+        set.offsets().iter().filter_map(move |(dx, dy, dz)| {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            let nz = z as i32 + dz;
+            if self.is_valid(nx, ny, nz) {
+                Some((nx as u32, ny as u32, nz as u32))
+            } else {
+                None
+            }
+        })
+    }
+
     pub fn generate_basic_world(width: u32, height: u32, depth: u32) -> Self {
+        let seed = rand::thread_rng().gen();
+        let (config, thermal) = (StrataConfig::default(), ThermalProfile::default());
+        Self::generate_basic_world_with(width, height, depth, &config, &thermal, seed)
+            .expect("default strata are valid")
+    }
+
+    pub fn generate_basic_world_with(
+        width: u32,
+        height: u32,
+        depth: u32,
+        config: &StrataConfig,
+        thermal: &ThermalProfile,
+        seed: u64,
+    ) -> Result<Self, WorldGenError> {
+        config.validate()?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        Ok(Self::generate_layers(width, height, depth, config, thermal, &mut rng))
+    }
+
+    // Standard generation followed by `post`, which receives the generator's
+    // RNG so custom features (mountains, rivers, caves) stay reproducible
+    // from the seed
+    pub fn generate_with(
+        dims: (u32, u32, u32),
+        seed: u64,
+        mut post: impl FnMut(&mut World3D, &mut StdRng),
+    ) -> Self {
+        let (width, height, depth) = dims;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (config, thermal) = (StrataConfig::default(), ThermalProfile::default());
+        let mut world = Self::generate_layers(width, height, depth, &config, &thermal, &mut rng);
+        post(&mut world, &mut rng);
+        // The hook may have edited voxels in place
+        world.refresh_material_counts();
+        world
+    }
+
+    // Varied terrain from coherent noise: rolling hills, bare rock mountains,
+    // lakes and seas pooling in the valleys below sea level, and a climate that
+    // cools toward the poles (y = 0 and y = height - 1) and with altitude.
+    // Unlike `generate_basic_world`, the whole world follows from the seed.
+    pub fn generate_with_noise(width: u32, height: u32, depth: u32, seed: u64) -> Self {
         let mut world = Self::new(width, height, depth);
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sea_level = layer_top(depth, SEA_LEVEL);
+        let period = (width.max(height) as f32 / 3.0).max(2.0);
+
+        for y in 0..height {
+            // From -1 at the south pole to 1 at the north pole
+            let latitude =
+                if height > 1 { y as f32 / (height - 1) as f32 * 2.0 - 1.0 } else { 0.0 };
+            let climate = EQUATOR_TEMPERATURE - POLAR_COOLING * latitude.abs();
+            for x in 0..width {
+                let elevation = fractal_noise(seed, x as f32, y as f32, period, 4);
+                let ore_seed = seed.wrapping_add(ORE_SEED_OFFSET);
+                let ore = fractal_noise(ore_seed, x as f32, y as f32, period / 2.0, 2);
+                let richness = ((ore - ORE_THRESHOLD) / (1.0 - ORE_THRESHOLD)).max(0.0);
+                let ground = layer_top(depth, LOWEST_GROUND + GROUND_RANGE * elevation).max(1);
+                let mountain = elevation > MOUNTAIN_ELEVATION;
+                let soil_bottom =
+                    if mountain { ground } else { ground.saturating_sub(SOIL_THICKNESS) };
+                // Deep basins hold salty seas, shallow ones fresh lakes
+                let basin_depth = sea_level.saturating_sub(ground) as f32;
+                let salinity = (0.1 + 0.9 * basin_depth / sea_level.max(1) as f32).min(1.0);
+
+                for z in 0..depth {
+                    let altitude = (z as f32 - sea_level as f32).max(0.0);
+                    let local = climate - LAPSE_RATE * altitude + rng.gen_range(-1.0..1.0);
+                    let voxel = world.get_mut(x, y, z);
+                    if z < soil_bottom {
+                        *voxel = Voxel::rock();
+                        voxel.mineral = richness;
+                        // Snow caps on cold peaks
+                        if mountain && z + 1 == ground && local < 0.0 {
+                            *voxel = Voxel::new(VoxelMaterial::Ice, local, 0.9, 0.0);
+                        }
+                    } else if z < ground {
+                        *voxel = Voxel::soil();
+                        voxel.temperature = local;
+                        // Lowlands by the water are the most fertile
+                        let near_water = if ground <= sea_level + 1 { 1.5 } else { 1.0 };
+                        voxel.fertility = rng.gen_range(0.5..1.5) * near_water;
+                    } else if z < sea_level {
+                        // Cold seas freeze over at the surface only
+                        *voxel = if z + 1 == sea_level && local < 0.0 {
+                            Voxel::new(VoxelMaterial::Ice, local, 0.9, 0.0)
+                        } else {
+                            let mut water = Voxel::water();
+                            water.temperature = local.max(1.0);
+                            water.salinity = salinity;
+                            water
+                        };
+                    } else {
+                        *voxel = Voxel::air();
+                        voxel.temperature = local;
+                    }
+                }
+            }
+        }
+
+        world.sea_level = sea_level;
+        world.flooded_level = sea_level;
+        world.refresh_material_counts();
+        world
+    }
+
+    fn generate_layers(
+        width: u32,
+        height: u32,
+        depth: u32,
+        config: &StrataConfig,
+        thermal: &ThermalProfile,
+        rng: &mut StdRng,
+    ) -> Self {
+        let mut world = Self::new(width, height, depth);
+
+        let rock_top = layer_top(depth, config.rock_fraction);
+        let soil_top = layer_top(depth, config.rock_fraction + config.soil_fraction);
+        let ocean_top = layer_top(depth, config.rock_fraction + config.soil_fraction + 0.05);
 
         for z in 0..depth {
             for y in 0..height {
                 for x in 0..width {
                     let voxel = world.get_mut(x, y, z);
 
-                    // Bottom 30% is rock
-                    if z < depth * 3 / 10 {
+                    // Bottom layer is rock
+                    if z < rock_top {
                         *voxel = Voxel::rock();
+                        voxel.temperature = ThermalProfile::sample(&thermal.rock, rng);
                     }
-                    // Next 40% is soil
-                    else if z < depth * 7 / 10 {
+                    // Then soil
+                    else if z < soil_top {
                         *voxel = Voxel::soil();
-                        voxel.temperature = 15.0 + rng.gen::<f32>() * 10.0;
+                        voxel.temperature = ThermalProfile::sample(&thermal.soil, rng);
+                        // Floodplains along the coasts are the most fertile
+                        let coast_distance = x.abs_diff(width / 4).min(x.abs_diff(width * 3 / 4));
+                        let near_water = if coast_distance <= 3 { 1.5 } else { 1.0 };
+                        voxel.fertility = rng.gen_range(0.5..1.5) * near_water;
                     }
-                    // Top 30% is air with occasional water (oceans)
+                    // The rest is air with occasional water (oceans)
                     else {
                         // Create water "oceans" in some regions
-                        let is_ocean = (x < width / 4 || x > width * 3 / 4) && z < depth * 75 / 100;
+                        let is_ocean = (x < width / 4 || x > width * 3 / 4) && z < ocean_top;
 
                         if is_ocean {
                             *voxel = Voxel::water();
+                            voxel.temperature = ThermalProfile::sample(&thermal.water, rng);
+                            // Saltier the further out from the coastline
+                            let shore_distance = if x < width / 4 {
+                                width / 4 - x
+                            } else {
+                                x - width * 3 / 4
+                            };
+                            let ocean_width = (width / 4).max(1) as f32;
+                            voxel.salinity =
+                                (0.6 + 0.4 * shore_distance as f32 / ocean_width).min(1.0);
                         } else {
                             *voxel = Voxel::air();
-                            voxel.temperature = 18.0 + rng.gen::<f32>() * 8.0;
+                            voxel.temperature = ThermalProfile::sample(&thermal.air, rng);
                         }
                     }
                 }
             }
         }
 
+        world.refresh_material_counts();
         world
     }
 }
+
+// Serialized form of a world: runs of identical consecutive voxels, so large
+// stretches of air or rock cost a single entry
+#[derive(Serialize, Deserialize)]
+struct WorldRepr<V> {
+    width: u32,
+    height: u32,
+    depth: u32,
+    wrapping: bool,
+    #[serde(default)]
+    sea_level: u32,
+    #[serde(default)]
+    flooded_level: u32,
+    runs: Vec<(u32, V)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<((u32, u32, u32), String)>,
+}
+
+impl Serialize for World3D {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut runs: Vec<(u32, &Voxel)> = Vec::new();
+        for voxel in &self.voxels {
+            match runs.last_mut() {
+                Some((count, last)) if *last == voxel => *count += 1,
+                _ => runs.push((1, voxel)),
+            }
+        }
+
+        WorldRepr {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            wrapping: self.wrapping,
+            sea_level: self.sea_level,
+            flooded_level: self.flooded_level,
+            runs,
+            tags: {
+                let mut tags: Vec<_> =
+                    self.tags.iter().map(|(pos, name)| (*pos, name.clone())).collect();
+                tags.sort_unstable();
+                tags
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for World3D {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = WorldRepr::<Voxel>::deserialize(deserializer)?;
+
+        let expected = repr.width as u64 * repr.height as u64 * repr.depth as u64;
+        if expected > MAX_VOXELS {
+            return Err(serde::de::Error::custom(format!(
+                "world of {}x{}x{} exceeds {} voxels",
+                repr.width, repr.height, repr.depth, MAX_VOXELS
+            )));
+        }
+        let stored: u64 = repr.runs.iter().map(|(count, _)| *count as u64).sum();
+        if stored != expected {
+            return Err(serde::de::Error::custom(format!(
+                "world of {}x{}x{} needs {} voxels, found {}",
+                repr.width, repr.height, repr.depth, expected, stored
+            )));
+        }
+
+        let mut world = World3D::new(repr.width, repr.height, repr.depth);
+        world.wrapping = repr.wrapping;
+        world.sea_level = repr.sea_level;
+        world.flooded_level = repr.flooded_level;
+        world.voxels.clear();
+        for (count, voxel) in repr.runs {
+            let len = world.voxels.len() + count as usize;
+            world.voxels.resize(len, voxel);
+        }
+        for ((x, y, z), name) in repr.tags {
+            world.tag(x, y, z, &name);
+        }
+        world.refresh_material_counts();
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_tracks_voxel_edits() {
+        let mut world = World3D::generate_basic_world(8, 8, 8);
+        let hash = world.content_hash();
+        assert_eq!(world.content_hash(), hash);
+        assert_eq!(world.clone().content_hash(), hash);
+
+        world.set_material(3, 3, 0, VoxelMaterial::Lava);
+        assert_ne!(world.content_hash(), hash);
+
+        // A higher sea floods the world on the next tick, even if no voxel changed yet
+        let hash = world.content_hash();
+        world.sea_level += 1;
+        assert_ne!(world.content_hash(), hash);
+
+        // Mining changes what a civilization can still extract
+        let hash = world.content_hash();
+        world.get_mut(2, 2, 0).mineral += 0.5;
+        assert_ne!(world.content_hash(), hash);
+    }
+
+    fn scanned_counts(world: &World3D) -> [u32; MATERIAL_KINDS] {
+        let mut counts = [0; MATERIAL_KINDS];
+        for voxel in &world.voxels {
+            counts[voxel.material.kind_index()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn material_counts_match_a_scan_after_a_catastrophe() {
+        use crate::config::SimulationConfig;
+        use crate::god::{apply_action, CatastropheKind, GodAction};
+
+        let config = SimulationConfig { width: 16, height: 16, depth: 16, ..Default::default() };
+        let mut state = config.initial_state(3);
+        // Leave the histogram stale first, as direct voxel edits do
+        state.world.get_mut(0, 0, 15).temperature = 5000.0;
+        for kind in CatastropheKind::ALL {
+            let action = GodAction::SpawnCatastrophe {
+                kind,
+                x: 8,
+                y: 8,
+                z: 6,
+                intensity: 1.0,
+                radius: 4.0,
+            };
+            apply_action(&mut state, action).unwrap();
+            state.world.ensure_material_counts();
+            assert_eq!(state.world.material_counts, scanned_counts(&state.world));
+        }
+    }
+
+    #[test]
+    fn phase_changes_after_direct_edits_keep_counts_consistent() {
+        let mut world = World3D::new(2, 2, 2);
+        let voxel = world.get_mut(0, 0, 0);
+        voxel.set_material(VoxelMaterial::Rock);
+        voxel.temperature = 5000.0;
+        crate::physics::apply_physics(
+            &mut world,
+            &crate::physics::PhysicsRules::default(),
+            0,
+            &mut rand::thread_rng(),
+        );
+        assert_eq!(world.material_count(VoxelMaterial::Lava), 1);
+        world.ensure_material_counts();
+        assert_eq!(world.material_counts, scanned_counts(&world));
+    }
+
+    #[test]
+    fn serialization_round_trips() {
+        let mut world = World3D::generate_basic_world(8, 8, 8);
+        world.wrapping = true;
+        world.sea_level = 2;
+        world.get_mut(1, 2, 3).set_material(VoxelMaterial::Organic(7));
+        world.tag(4, 4, 4, "capital");
+
+        let json = serde_json::to_string(&world).unwrap();
+        let loaded: World3D = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.dimensions(), world.dimensions());
+        assert_eq!(loaded.voxels, world.voxels);
+        assert_eq!((loaded.wrapping, loaded.sea_level), (true, 2));
+        assert_eq!(loaded.tag_at(4, 4, 4), Some("capital"));
+        assert_eq!(loaded.material_count(VoxelMaterial::Organic(0)), 1);
+    }
+
+    #[test]
+    fn mostly_air_worlds_serialize_compactly() {
+        let mut world = World3D::new(32, 32, 32);
+        for x in 0..32 {
+            world.set_material(x, 0, 0, VoxelMaterial::Soil);
+        }
+
+        let json = serde_json::to_string(&world).unwrap();
+        assert!(json.len() < world.voxels.len() / 10, "{} bytes", json.len());
+        let loaded: World3D = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.voxels, world.voxels);
+    }
+
+    #[test]
+    fn oversized_worlds_are_rejected_before_allocating() {
+        let air = serde_json::to_value(Voxel::air()).unwrap();
+        let json = serde_json::json!({
+            "width": u32::MAX,
+            "height": 2,
+            "depth": 1,
+            "wrapping": false,
+            "runs": [[u32::MAX, air], [u32::MAX, air]],
+        });
+        assert!(serde_json::from_value::<World3D>(json).is_err());
+    }
+
+    #[test]
+    fn non_air_iteration_yields_exactly_the_solid_voxels() {
+        // Uneven dimensions, so swapped axes would show
+        let mut world = World3D::new(3, 4, 2);
+        let placed = [
+            (0, 0, 0, VoxelMaterial::Rock),
+            (2, 1, 0, VoxelMaterial::Water),
+            (1, 3, 1, VoxelMaterial::Soil),
+            (2, 3, 1, VoxelMaterial::Lava),
+        ];
+        for &(x, y, z, material) in &placed {
+            world.set_material(x, y, z, material);
+        }
+
+        let found: Vec<_> =
+            world.iter_non_air().map(|(x, y, z, voxel)| (x, y, z, voxel.material)).collect();
+        assert_eq!(found, placed);
+    }
+
+    #[test]
+    fn strata_follow_their_configured_fractions() {
+        let strata = StrataConfig { rock_fraction: 0.5, soil_fraction: 0.2 };
+        let thermal = ThermalProfile::default();
+        let world = World3D::generate_basic_world_with(8, 8, 10, &strata, &thermal, 3).unwrap();
+        // Half the depth is rock and the next fifth soil, in every column
+        assert_eq!(world.material_count(VoxelMaterial::Rock), 8 * 8 * 5);
+        assert_eq!(world.material_count(VoxelMaterial::Soil), 8 * 8 * 2);
+        assert_eq!(world.get(4, 4, 4).material, VoxelMaterial::Rock);
+        assert_eq!(world.get(4, 4, 6).material, VoxelMaterial::Soil);
+        assert_eq!(world.surface_height_at(4, 4), Some(6));
+
+        let invalid = [(1.2, 0.0), (-0.1, 0.5), (0.6, 0.5), (f32::NAN, 0.1)];
+        for (rock_fraction, soil_fraction) in invalid {
+            let strata = StrataConfig { rock_fraction, soil_fraction };
+            let result = World3D::generate_basic_world_with(8, 8, 10, &strata, &thermal, 3);
+            assert!(matches!(result, Err(WorldGenError::InvalidStrata(_))), "{:?}", strata);
+        }
+    }
+
+    #[test]
+    fn temperature_stats_match_hand_computed_values() {
+        // Voxels at 1 to 8 degrees, in index order (x fastest)
+        let mut world = World3D::new(2, 2, 2);
+        for (voxel, temperature) in world.voxels.iter_mut().zip(1..=8) {
+            voxel.temperature = temperature as f32;
+        }
+        let stats = world.temperature_stats();
+        assert_eq!((stats.min, stats.max), (1.0, 8.0));
+        assert_eq!((stats.mean, stats.variance), (4.5, 5.25));
+
+        // The x = 0 half holds 1, 3, 5 and 7
+        let half = world.temperature_stats_within((0, 0, 0), (1, 2, 2));
+        assert_eq!((half.min, half.max, half.mean, half.variance), (1.0, 7.0, 4.0, 5.0));
+        // A single voxel, and a box reaching past the edges, clamped to the world
+        let corner = world.temperature_stats_within((1, 1, 1), (9, 9, 9));
+        assert_eq!((corner.min, corner.max, corner.mean, corner.variance), (8.0, 8.0, 8.0, 0.0));
+        assert_eq!(world.temperature_stats_within((0, 0, 0), (9, 9, 9)), stats);
+        // An empty box has no temperatures to speak of
+        assert_eq!(world.temperature_stats_within((1, 0, 0), (1, 2, 2)), TempStats::default());
+    }
+
+    // A world whose voxels carry their own coordinates as x + 10y + 100z degrees
+    fn coordinate_world(width: u32, height: u32, depth: u32) -> World3D {
+        let mut world = World3D::new(width, height, depth);
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    world.get_mut(x, y, z).temperature = (x + 10 * y + 100 * z) as f32;
+                }
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn extracted_regions_keep_their_voxels_and_stop_at_the_edges() {
+        let mut world = coordinate_world(6, 5, 4);
+        world.set_material(3, 2, 1, VoxelMaterial::Rock);
+        world.get_mut(3, 2, 1).nutrients = 7.0;
+        world.tag(3, 2, 1, "peak");
+
+        let region = world.extract_region((2, 1, 1), (5, 4, 3));
+        assert_eq!(region.dimensions(), (3, 3, 2));
+        assert_eq!(region.get(0, 0, 0).temperature, 112.0);
+        assert_eq!(region.get(2, 2, 1).temperature, 234.0);
+        let peak = region.get(1, 1, 0);
+        assert_eq!((peak.material, peak.nutrients), (VoxelMaterial::Rock, 7.0));
+        assert_eq!(region.tags.get(&(1, 1, 0)).map(String::as_str), Some("peak"));
+        assert_eq!(region.material_count(VoxelMaterial::Rock), 1);
+
+        // Past the far edges, the region is cut down to what the world holds
+        assert_eq!(world.extract_region((4, 3, 2), (10, 10, 10)).dimensions(), (2, 2, 2));
+        assert_eq!(world.extract_region((5, 0, 0), (3, 5, 4)).dimensions(), (0, 5, 4));
+    }
+
+    #[test]
+    fn pasted_worlds_land_at_their_offset_and_are_clipped() {
+        let stamp = coordinate_world(3, 3, 2);
+        let mut world = World3D::new(5, 5, 3);
+        for voxel in &mut world.voxels {
+            voxel.temperature = -1.0;
+        }
+
+        world.paste((1, 2, 0), &stamp);
+        assert_eq!(world.get(1, 2, 0).temperature, 0.0);
+        assert_eq!(world.get(3, 4, 1).temperature, 122.0);
+        assert_eq!(world.get(0, 2, 0).temperature, -1.0);
+        assert_eq!(world.get(1, 2, 2).temperature, -1.0);
+
+        // Half over the corner: only the stamp's overlap with the world is copied
+        world.paste((-2, 3, 2), &stamp);
+        assert_eq!(world.get(0, 3, 2).temperature, 2.0);
+        assert_eq!(world.get(0, 4, 2).temperature, 12.0);
+        assert_eq!(world.get(1, 4, 2).temperature, -1.0);
+        let changed = world.voxels.iter().filter(|voxel| voxel.temperature != -1.0).count();
+        assert_eq!(changed, 18 + 2);
+    }
+
+    #[test]
+    fn water_worlds_report_their_few_islands() {
+        // A generated world drowned two layers above its soil, then a soil
+        // island of four columns and a single rock stack rising out of the sea
+        let (strata, thermal) = (StrataConfig::default(), ThermalProfile::default());
+        let mut world =
+            World3D::generate_basic_world_with(16, 16, 10, &strata, &thermal, 3).unwrap();
+        world.sea_level = 9;
+        world.flood_to_sea_level();
+        for (x, y) in [(2, 2), (2, 3), (3, 2), (3, 3)] {
+            world.set_material(x, y, 9, VoxelMaterial::Soil);
+        }
+        world.set_material(10, 10, 9, VoxelMaterial::Rock);
+
+        let stats = world.world_stats(4);
+        assert_eq!(stats.water_fraction, 251.0 / 256.0);
+        assert_eq!(stats.land_fraction, 5.0 / 256.0);
+        assert_eq!(stats.ice_fraction, 0.0);
+        // Open water and the soil island are habitable, the rock stack is not
+        assert_eq!(stats.habitable_surface, 255);
+        assert_eq!(stats.band_temperatures.len(), 4);
+    }
+
+    #[test]
+    fn edits_made_by_the_generation_hook_survive() {
+        // A lava vent at a known spot, and an ice block placed by the generator's RNG
+        let edit = |world: &mut World3D, rng: &mut StdRng| {
+            let vent = world.get_mut(3, 4, 5);
+            vent.material = VoxelMaterial::Lava;
+            vent.temperature = 1500.0;
+            let x = rng.gen_range(0..8);
+            world.set_material(x, 0, 5, VoxelMaterial::Ice);
+        };
+        let world = World3D::generate_with((8, 8, 6), 7, edit);
+        let plain = World3D::generate_with((8, 8, 6), 7, |_, _| {});
+
+        assert_eq!(world.get(3, 4, 5).material, VoxelMaterial::Lava);
+        assert_eq!(world.get(3, 4, 5).temperature, 1500.0);
+        let lava = |world: &World3D| world.material_count(VoxelMaterial::Lava);
+        assert_eq!(lava(&world), lava(&plain) + 1);
+        // Only the two edited voxels differ from the untouched world
+        let changed = world.voxels.iter().zip(&plain.voxels).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 2);
+        // The hook draws from the seeded stream, so the same seed gives the same world
+        let again = World3D::generate_with((8, 8, 6), 7, edit);
+        assert_eq!(again.content_hash(), world.content_hash());
+    }
+
+    #[test]
+    fn molten_planets_start_far_hotter() {
+        let generate = |thermal: &ThermalProfile| {
+            World3D::generate_basic_world_with(12, 12, 8, &StrataConfig::default(), thermal, 3)
+                .unwrap()
+        };
+        let (temperate, molten) =
+            (generate(&ThermalProfile::default()), generate(&ThermalProfile::molten()));
+        // Same strata, only the temperatures differ
+        assert!(temperate.voxels.iter().zip(&molten.voxels).all(|(a, b)| a.material == b.material));
+
+        let (temperate, molten) = (temperate.temperature_stats(), molten.temperature_stats());
+        assert!(temperate.min >= 10.0 && temperate.max <= 26.0, "{:?}", temperate);
+        // Even the coolest molten voxel (boiling water) beats the warmest temperate one
+        assert!(molten.min >= 100.0, "{:?}", molten);
+        assert!(molten.mean > temperate.mean * 10.0, "{} vs {}", molten.mean, temperate.mean);
+    }
+
+    #[test]
+    fn tags_stay_on_their_voxel_while_the_world_changes_around_them() {
+        use crate::physics::{apply_physics, PhysicsRules};
+
+        let mut world = World3D::generate_with((8, 8, 6), 2, |_, _| {});
+        assert!(world.tag(3, 4, 2, "capital"));
+        assert!(world.tag(5, 5, 1, "capital"));
+        assert!(world.tag(1, 1, 1, "sacred"));
+        assert!(!world.tag(8, 0, 0, "outside"));
+
+        // Lava all around the capital, a few ticks of physics, then the tag moves off
+        // the sacred site and back
+        for (x, y, z) in world.neighbors(3, 4, 2, NeighborSet::Full).collect::<Vec<_>>() {
+            world.set_material(x, y, z, VoxelMaterial::Lava);
+            world.get_mut(x, y, z).temperature = 1500.0;
+        }
+        world.set_material(3, 4, 2, VoxelMaterial::Water);
+        let mut rng = StdRng::seed_from_u64(0);
+        for tick in 0..5 {
+            apply_physics(&mut world, &PhysicsRules::default(), tick, &mut rng);
+        }
+        assert_eq!(world.untag(1, 1, 1).as_deref(), Some("sacred"));
+        assert!(world.tag(1, 1, 1, "sacred"));
+
+        assert_eq!(world.find_tagged("capital"), [(3, 4, 2), (5, 5, 1)]);
+        assert_eq!(world.find_tagged("sacred"), [(1, 1, 1)]);
+        assert_eq!(world.tag_at(3, 4, 2), Some("capital"));
+        assert!(world.find_tagged("outside").is_empty());
+        assert_eq!(world.clone().find_tagged("capital"), world.find_tagged("capital"));
+    }
+
+    #[test]
+    fn dumped_temperatures_read_back_in_index_order() {
+        // Every voxel of a 3×2×2 world gets a temperature naming its position
+        let mut world = World3D::new(3, 2, 2);
+        for z in 0..2 {
+            for y in 0..2 {
+                for x in 0..3 {
+                    world.get_mut(x, y, z).temperature = (100 * z + 10 * y + x) as f32;
+                }
+            }
+        }
+        let path = std::env::temp_dir().join(format!("field_{}.raw", std::process::id()));
+        world.dump_field(Field::Temperature, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let words: Vec<[u8; 4]> = bytes.chunks(4).map(|c| [c[0], c[1], c[2], c[3]]).collect();
+        let dims: Vec<u32> = words[..3].iter().map(|&w| u32::from_le_bytes(w)).collect();
+        let values: Vec<f32> = words[3..].iter().map(|&w| f32::from_le_bytes(w)).collect();
+        assert_eq!(bytes.len(), 4 * (3 + 12));
+        assert_eq!(dims, [3, 2, 2]);
+        // x fastest, then y, then z, as reshape(depth, height, width) expects
+        let expected = [0, 1, 2, 10, 11, 12, 100, 101, 102, 110, 111, 112];
+        assert_eq!(values, expected.iter().map(|&t| t as f32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn noise_worlds_mix_materials_over_uneven_ground() {
+        for seed in 0..4 {
+            let world = World3D::generate_with_noise(32, 32, 16, seed);
+            let materials = [
+                VoxelMaterial::Air,
+                VoxelMaterial::Rock,
+                VoxelMaterial::Soil,
+                VoxelMaterial::Water,
+                VoxelMaterial::Ice,
+                VoxelMaterial::Lava,
+            ];
+            let present = materials.iter().filter(|&&m| world.material_count(m) > 0).count();
+            assert!(present >= 4, "seed {}: {} materials", seed, present);
+
+            // Height of the rock and soil of each column, whatever lies on top
+            let solid = |x, y, z| {
+                let material = world.get(x, y, z).material;
+                material == VoxelMaterial::Rock || material == VoxelMaterial::Soil
+            };
+            let mut heights = std::collections::BTreeSet::new();
+            for y in 0..32 {
+                for x in 0..32 {
+                    let ground = (0..16).rev().find(|&z| solid(x, y, z));
+                    heights.insert(ground.map_or(0, |z| z + 1));
+                }
+            }
+            assert!(heights.len() >= 4, "seed {}: heights {:?}", seed, heights);
+        }
+    }
+}